@@ -5,22 +5,16 @@ extern crate fastcan;
 use std::collections::HashMap;
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion as Bencher};
-use fastcan::dbc::{DbcSignal, DbcSignalDefinition};
+use fastcan::dbc::{DbcLibrary, DbcSignal, DbcSignalDefinition};
 use fastcan::mapper::DecodeMessage;
 
 lazy_static! {
-    static ref SIGNAL_DEF: DbcSignalDefinition = DbcSignalDefinition {
-        name: "Engine_Speed".to_string(),
-        start_bit: 24,
-        bit_len: 16,
-        little_endian: true,
-        signed: false,
-        scale: 0.125,
-        offset: 0.0,
-        min_value: 0.0,
-        max_value: 8031.88,
-        units: "rpm".to_string(),
-        receiving_node: "Vector__XXX".to_string()
+    static ref SIGNAL_DEF: DbcSignalDefinition = {
+        let mut definition =
+            DbcSignalDefinition::new("Engine_Speed", 24, 16, true, false, 0.125, 0.0);
+        definition.max_value = 8031.88;
+        definition.units = "rpm".to_string();
+        definition
     };
     static ref MSG: [u8; 8] = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
 }
@@ -41,6 +35,31 @@ fn bench_parse_message(b: &mut Bencher) {
     });
 }
 
-criterion_group!(benches, bench_parse_array, bench_parse_message,);
+fn bench_load_dbc(b: &mut Bencher) {
+    let data = std::fs::read("./tests/data/ff.dbc").unwrap();
+    let data = String::from_utf8_lossy(&data).into_owned();
+
+    b.bench_function("bench_load_dbc", move |b| {
+        b.iter(|| black_box(DbcLibrary::from_dbc_str(&data)))
+    });
+}
+
+fn bench_load_cache(b: &mut Bencher) {
+    let cache = DbcLibrary::from_dbc_file("./tests/data/ff.dbc")
+        .unwrap()
+        .to_cache(0);
+
+    b.bench_function("bench_load_cache", move |b| {
+        b.iter(|| black_box(DbcLibrary::from_cache(&cache, 0).unwrap()))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_parse_array,
+    bench_parse_message,
+    bench_load_dbc,
+    bench_load_cache,
+);
 
 criterion_main!(benches);
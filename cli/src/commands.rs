@@ -0,0 +1,589 @@
+//! Subcommands inspecting DBC files and single frames
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use fastcan::bus::BusLibrary;
+use fastcan::dbc::{DbcFrame, DbcLibrary, DbcSignal, Impact, MergePolicy, Multiplexing, Severity};
+use fastcan::mapper::{DecodeMessage, EncodeMessage};
+use fastcan::trace::Channel;
+use serde_json::{json, Value};
+
+use crate::{log, Cli, Command, ConflictPolicy};
+
+/// Runs a subcommand, returns a message for the user on failure
+pub fn run(cli: Cli) -> Result<(), String> {
+    match cli.command {
+        Command::Lint => return lint(&cli.dbc, cli.json),
+        Command::Diff { ref old, ref new } => return diff(old, new, cli.json),
+        Command::DecodeLog {
+            ref input,
+            format,
+            ref frames,
+            ref signals,
+            ref buses,
+        } => {
+            let buses = load_buses(&cli.dbc, buses, cli.on_conflict)?;
+            return log::decode_log(&buses, input, format, frames, signals, cli.json);
+        }
+        _ => {}
+    }
+
+    let lib = load_library(&cli.dbc, cli.on_conflict)?;
+    let json = cli.json;
+
+    match cli.command {
+        Command::Info => info(&lib, json),
+        Command::ListFrames => list_frames(&lib, json),
+        Command::ShowFrame { frame } => show_frame(find_frame(&lib, &frame)?, json),
+        Command::Decode { frame, data } => {
+            decode(find_frame(&lib, &frame)?, &parse_hex(&data)?, json)
+        }
+        Command::Encode { frame, signals } => encode(find_frame(&lib, &frame)?, &signals, json),
+        Command::Lint | Command::Diff { .. } | Command::DecodeLog { .. } => unreachable!(),
+    }
+}
+
+/// Loads the DBC files into a single library, reports conflicting frame definitions on stderr
+fn load_library(paths: &[PathBuf], on_conflict: ConflictPolicy) -> Result<DbcLibrary, String> {
+    if paths.is_empty() {
+        return Err("no DBC file given, use --dbc <FILE>".to_string());
+    }
+
+    let policy = match on_conflict {
+        ConflictPolicy::Error => MergePolicy::Error,
+        ConflictPolicy::PreferLeft => MergePolicy::PreferLeft,
+        ConflictPolicy::PreferRight => MergePolicy::PreferRight,
+    };
+
+    let mut merged = DbcLibrary::default();
+    for path in paths {
+        let lib = DbcLibrary::from_dbc_file(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        match merged.merge(lib, policy) {
+            Ok(conflicts) => {
+                for conflict in conflicts {
+                    eprintln!("warning: {}: {}", path.display(), conflict);
+                }
+            }
+            Err(e) => {
+                for conflict in &e.conflicts {
+                    eprintln!("{}: {}", path.display(), conflict);
+                }
+                return Err(format!(
+                    "conflicting frame definitions in {}",
+                    path.display()
+                ));
+            }
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Loads the DBC files of every `CHANNEL=FILE` bus, the `--dbc` files are used for the other
+/// channels
+fn load_buses(
+    paths: &[PathBuf],
+    buses: &[String],
+    on_conflict: ConflictPolicy,
+) -> Result<BusLibrary, String> {
+    if paths.is_empty() && buses.is_empty() {
+        return Err("no DBC file given, use --dbc <FILE> or --bus <CHANNEL=FILE>".to_string());
+    }
+
+    let mut channels: Vec<(Channel, Vec<PathBuf>)> = Vec::new();
+    for bus in buses {
+        let (channel, path) = match bus.find('=') {
+            Some(index) if index > 0 => (Channel::from(&bus[..index]), &bus[index + 1..]),
+            _ => return Err(format!("invalid bus {}, expected CHANNEL=FILE", bus)),
+        };
+        match channels.iter_mut().find(|(c, _)| *c == channel) {
+            Some((_, files)) => files.push(PathBuf::from(path)),
+            None => channels.push((channel, vec![PathBuf::from(path)])),
+        }
+    }
+
+    let mut lib = BusLibrary::new();
+    for (channel, files) in channels {
+        lib.insert(channel, load_library(&files, on_conflict)?);
+    }
+    if !paths.is_empty() {
+        lib.set_fallback(load_library(paths, on_conflict)?);
+    }
+
+    Ok(lib)
+}
+
+fn lint(paths: &[PathBuf], json: bool) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("no DBC file given, use --dbc <FILE>".to_string());
+    }
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    let mut reports = Vec::new();
+
+    for path in paths {
+        let lib = DbcLibrary::from_dbc_file(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+
+        for diagnostic in lib.lint() {
+            match diagnostic.severity {
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+
+            if json {
+                reports.push(json!({
+                    "file": path.display().to_string(),
+                    "severity": diagnostic.severity.to_string(),
+                    "check": diagnostic.check.name(),
+                    "frame_id": diagnostic.frame_id,
+                    "message": diagnostic.message,
+                }));
+            } else {
+                println!("{}: {}", path.display(), diagnostic);
+            }
+        }
+    }
+
+    if json {
+        println!("{}", Value::from(reports));
+    } else {
+        eprintln!("{} errors, {} warnings", errors, warnings);
+    }
+
+    if errors > 0 {
+        Err(format!("{} errors found", errors))
+    } else {
+        Ok(())
+    }
+}
+
+fn diff(old: &Path, new: &Path, json: bool) -> Result<(), String> {
+    let load = |path: &Path| {
+        DbcLibrary::from_dbc_file(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))
+    };
+    let changes = load(old)?.diff(&load(new)?);
+    let breaking = changes
+        .iter()
+        .filter(|change| change.impact() == Impact::Breaking)
+        .count();
+
+    if json {
+        let changes: Vec<Value> = changes
+            .iter()
+            .map(|change| {
+                json!({
+                    "impact": change.impact().to_string(),
+                    "kind": change.kind.description(),
+                    "frame_id": change.frame_id,
+                    "frame": change.frame,
+                    "signal": change.signal,
+                    "old": change.old,
+                    "new": change.new,
+                })
+            })
+            .collect();
+        println!("{}", Value::from(changes));
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+        eprintln!("{} changes, {} breaking", changes.len(), breaking);
+    }
+
+    Ok(())
+}
+
+fn info(lib: &DbcLibrary, json: bool) -> Result<(), String> {
+    let frames = lib.get_frames();
+    let signals: usize = frames.iter().map(|frame| frame.get_signals().len()).sum();
+    let extended = frames
+        .iter()
+        .filter(|frame| frame.get_can_id().is_extended())
+        .count();
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "frames": frames.len(),
+                "extended_frames": extended,
+                "signals": signals,
+            })
+        );
+    } else {
+        println!("frames:          {}", frames.len());
+        println!("extended frames: {}", extended);
+        println!("signals:         {}", signals);
+    }
+
+    Ok(())
+}
+
+fn list_frames(lib: &DbcLibrary, json: bool) -> Result<(), String> {
+    let frames = sorted_frames(lib);
+
+    if json {
+        let frames: Vec<Value> = frames
+            .iter()
+            .map(|frame| {
+                json!({
+                    "name": frame.get_name(),
+                    "id": frame.get_can_id().raw(),
+                    "extended": frame.get_can_id().is_extended(),
+                    "length": frame.get_message_len(),
+                    "signals": frame.get_signals().len(),
+                })
+            })
+            .collect();
+        println!("{}", Value::from(frames));
+    } else {
+        for frame in frames {
+            println!(
+                "{:>10}  {:<32} {:>2} bytes  {} signals",
+                format_id(frame),
+                frame.get_name(),
+                frame.get_message_len(),
+                frame.get_signals().len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn show_frame(frame: &DbcFrame, json: bool) -> Result<(), String> {
+    let signals = sorted_signals(frame);
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "name": frame.get_name(),
+                "id": frame.get_can_id().raw(),
+                "extended": frame.get_can_id().is_extended(),
+                "length": frame.get_message_len(),
+                "sending_node": frame.get_sending_node(),
+                "description": frame.get_description(),
+                "attributes": frame.get_attributes(),
+                "signals": signals.iter().map(|signal| signal_json(signal)).collect::<Vec<_>>(),
+            })
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} ({}), {} bytes, sent by {}",
+        frame.get_name(),
+        format_id(frame),
+        frame.get_message_len(),
+        frame.get_sending_node()
+    );
+    if let Some(description) = frame.get_description() {
+        println!("  {}", description);
+    }
+    for (name, value) in sorted(frame.get_attributes()) {
+        println!("  {} = {}", name, value);
+    }
+
+    for signal in signals {
+        let definition = signal.get_definition();
+        println!();
+        println!(
+            "  {} : {}|{}@{}{} ({},{}) [{}|{}] \"{}\" {}",
+            definition.name,
+            definition.start_bit,
+            definition.bit_len,
+            if definition.little_endian { 1 } else { 0 },
+            if definition.signed { '-' } else { '+' },
+            definition.scale,
+            definition.offset,
+            definition.min_value,
+            definition.max_value,
+            definition.units,
+            definition.receiving_node
+        );
+        if let Some(description) = signal.get_description() {
+            println!("    {}", description);
+        }
+        for (name, value) in sorted(signal.get_attributes()) {
+            println!("    {} = {}", name, value);
+        }
+        if let Some(values) = signal.get_value_definition() {
+            for (value, label) in values.get_values() {
+                println!("    {} \"{}\"", value, label);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn decode(frame: &DbcFrame, data: &[u8], json: bool) -> Result<(), String> {
+    if data.is_empty() {
+        return Err("empty payload".to_string());
+    }
+
+    let signals = sort_signals(frame.get_signals_in_message(data));
+    let values: Vec<(&DbcSignal, f32)> = signals
+        .into_iter()
+        .filter_map(|signal| signal.decode_message(data).map(|value| (signal, value)))
+        .collect();
+
+    if json {
+        let signals: Vec<Value> = values
+            .iter()
+            .map(|(signal, value)| value_json(signal, *value, data))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "frame": frame.get_name(),
+                "id": frame.get_can_id().raw(),
+                "signals": signals,
+            })
+        );
+    } else {
+        println!("{} ({})", frame.get_name(), format_id(frame));
+        for (signal, value) in values {
+            println!("  {}", format_value(signal, value, data));
+        }
+    }
+
+    Ok(())
+}
+
+fn encode(frame: &DbcFrame, assignments: &[String], json: bool) -> Result<(), String> {
+    let mut values = HashMap::new();
+    for signal in frame.get_signals() {
+        let definition = signal.get_definition();
+        values.insert(definition.name.clone(), f64::from(definition.offset));
+    }
+
+    for assignment in assignments {
+        let (name, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("expected SIGNAL=VALUE, got '{}'", assignment))?;
+        let signal = frame
+            .get_signal(name)
+            .ok_or_else(|| format!("frame {} has no signal {}", frame.get_name(), name))?;
+        values.insert(name.to_string(), parse_value(signal, value)?);
+    }
+
+    let mut data: Vec<u8> = frame.encode_message(&values)?;
+    data.truncate(frame.get_message_len() as usize);
+
+    // alternatives of other multiplexor values are not in the message
+    let active = frame.get_signals_in_message(&data);
+    let multiplexor = active
+        .iter()
+        .find(|signal| signal.get_definition().multiplexing == Multiplexing::Multiplexor);
+    for assignment in assignments {
+        let name = assignment.split('=').next().unwrap_or_default();
+        if let Some(multiplexor) = multiplexor {
+            if !active
+                .iter()
+                .any(|signal| signal.get_definition().name == name)
+            {
+                return Err(format!(
+                    "signal {} is not in the message for {}={}",
+                    name,
+                    multiplexor.get_definition().name,
+                    multiplexor.decode_raw(&data).unwrap_or_default()
+                ));
+            }
+        }
+    }
+    let hex = format_hex(&data);
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "frame": frame.get_name(),
+                "id": frame.get_can_id().raw(),
+                "extended": frame.get_can_id().is_extended(),
+                "data": hex,
+            })
+        );
+    } else {
+        println!("{}", hex);
+    }
+
+    Ok(())
+}
+
+/// Parses a physical signal value, or a label of the signal's value table
+fn parse_value(signal: &DbcSignal, value: &str) -> Result<f64, String> {
+    if let Ok(value) = value.parse::<f64>() {
+        return Ok(value);
+    }
+
+    let definition = signal.get_definition();
+    signal
+        .get_value_definition()
+        .and_then(|values| values.get_values().iter().find(|(_, label)| label == value))
+        .map(|(raw, _)| *raw as f64 * f64::from(definition.scale) + f64::from(definition.offset))
+        .ok_or_else(|| format!("invalid value for {}: '{}'", definition.name, value))
+}
+
+/// Finds a frame by ID (decimal or `0x` prefixed hex, as encoded in the DBC or as seen on the
+/// bus) or by name
+pub fn find_frame<'a>(lib: &'a DbcLibrary, key: &str) -> Result<&'a DbcFrame, String> {
+    let frame = match parse_id(key) {
+        Some(id) => lib.get_frame(id),
+        None => lib
+            .get_frames()
+            .into_iter()
+            .find(|frame| frame.get_name() == key),
+    };
+
+    frame.ok_or_else(|| format!("no frame {} in DBC", key))
+}
+
+fn parse_id(key: &str) -> Option<u32> {
+    match key.strip_prefix("0x").or_else(|| key.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => key.parse().ok(),
+    }
+}
+
+/// Parses hex bytes, optionally separated by spaces, colons or dots
+pub fn parse_hex(data: &str) -> Result<Vec<u8>, String> {
+    let digits: String = data
+        .chars()
+        .filter(|c| !matches!(c, ' ' | ':' | '.'))
+        .collect();
+    if !digits.is_ascii() {
+        return Err(format!("invalid hex payload '{}'", data));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits in '{}'", data));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex payload '{}'", data))
+        })
+        .collect()
+}
+
+fn format_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn format_id(frame: &DbcFrame) -> String {
+    let id = frame.get_can_id();
+    if id.is_extended() {
+        format!("0x{:08X}", id.raw())
+    } else {
+        format!("0x{:03X}", id.raw())
+    }
+}
+
+fn signal_json(signal: &DbcSignal) -> Value {
+    let definition = signal.get_definition();
+    let values: Vec<Value> = signal
+        .get_value_definition()
+        .map(|values| {
+            values
+                .get_values()
+                .iter()
+                .map(|(value, label)| json!({ "value": value, "label": label }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "name": definition.name,
+        "start_bit": definition.start_bit,
+        "bit_len": definition.bit_len,
+        "little_endian": definition.little_endian,
+        "signed": definition.signed,
+        "scale": definition.scale,
+        "offset": definition.offset,
+        "min": definition.min_value,
+        "max": definition.max_value,
+        "unit": definition.units,
+        "receiving_node": definition.receiving_node,
+        "description": signal.get_description(),
+        "attributes": signal.get_attributes(),
+        "values": values,
+    })
+}
+
+/// Returns a decoded value as `name: value unit (label)`
+pub fn format_value(signal: &DbcSignal, value: f32, data: &[u8]) -> String {
+    let definition = signal.get_definition();
+    let mut text = format!("{}: {}", definition.name, value);
+    if !definition.units.is_empty() {
+        text = format!("{} {}", text, definition.units);
+    }
+    if let Some(label) = signal.decode_label(data) {
+        text = format!("{} ({})", text, label);
+    }
+    text
+}
+
+/// Returns a decoded value as JSON object with name, value, unit and label
+pub fn value_json(signal: &DbcSignal, value: f32, data: &[u8]) -> Value {
+    json!({
+        "name": signal.get_definition().name,
+        "value": value,
+        "unit": signal.get_definition().units,
+        "label": signal.decode_label(data),
+    })
+}
+
+fn sorted_frames(lib: &DbcLibrary) -> Vec<&DbcFrame> {
+    let mut frames = lib.get_frames();
+    frames.sort_by_key(|frame| (frame.get_can_id().raw(), frame.get_can_id().is_extended()));
+    frames
+}
+
+fn sorted_signals(frame: &DbcFrame) -> Vec<&DbcSignal> {
+    sort_signals(frame.get_signals())
+}
+
+pub fn sort_signals(mut signals: Vec<&DbcSignal>) -> Vec<&DbcSignal> {
+    signals.sort_by(|a, b| {
+        let (a, b) = (a.get_definition(), b.get_definition());
+        (a.start_bit, &a.name).cmp(&(b.start_bit, &b.name))
+    });
+    signals
+}
+
+fn sorted(map: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort();
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("DEADbeef").unwrap(), vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(
+            parse_hex("de:ad be.ef").unwrap(),
+            vec![0xDE, 0xAD, 0xBE, 0xEF]
+        );
+        assert!(parse_hex("123").is_err());
+        assert!(parse_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_parse_id() {
+        assert_eq!(parse_id("0x0CF00400"), Some(0x0CF00400));
+        assert_eq!(parse_id("2364539904"), Some(2364539904));
+        assert_eq!(parse_id("EEC1"), None);
+    }
+}
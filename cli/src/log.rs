@@ -0,0 +1,211 @@
+//! `decode-log` subcommand
+
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use fastcan::bus::BusLibrary;
+use fastcan::dbc::DbcSignal;
+use fastcan::mapper::DecodeMessage;
+use fastcan::mdf::MdfReader;
+use fastcan::trace::asc::AscReader;
+use fastcan::trace::blf::BlfReader;
+use fastcan::trace::candump::CandumpReader;
+use fastcan::trace::trc::TrcReader;
+use fastcan::trace::TraceFrame;
+use serde_json::{json, Value};
+
+use crate::commands::{format_value, sort_signals, value_json};
+use crate::Format;
+
+type Frames = Box<dyn Iterator<Item = io::Result<TraceFrame>>>;
+
+/// Streams a trace file and prints the decoded signals of every frame known to the library of its
+/// channel, one line per frame
+pub fn decode_log(
+    buses: &BusLibrary,
+    input: &Path,
+    format: Option<Format>,
+    frame_patterns: &[String],
+    signal_patterns: &[String],
+    json: bool,
+) -> Result<(), String> {
+    let frames = open_trace(input, format)
+        .map_err(|e| format!("could not read {}: {}", input.display(), e))?;
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+
+    for frame in frames {
+        let frame = frame.map_err(|e| format!("could not read {}: {}", input.display(), e))?;
+        if !frame.is_data() || frame.data.is_empty() {
+            continue;
+        }
+        let definition = match buses.get_frame(&frame.channel, frame.id) {
+            Some(definition) if matches_any(frame_patterns, definition.get_name()) => definition,
+            _ => continue,
+        };
+
+        let values: Vec<(&DbcSignal, f32)> =
+            sort_signals(definition.get_signals_in_message(&frame.data))
+                .into_iter()
+                .filter(|signal| matches_any(signal_patterns, &signal.get_definition().name))
+                .filter_map(|signal| {
+                    signal
+                        .decode_message(&frame.data[..])
+                        .map(|value| (signal, value))
+                })
+                .collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        let line = if json {
+            let signals: Vec<Value> = values
+                .iter()
+                .map(|(signal, value)| value_json(signal, *value, &frame.data))
+                .collect();
+            json!({
+                "timestamp": frame.timestamp,
+                "channel": frame.channel.to_string(),
+                "id": frame.id.raw(),
+                "extended": frame.id.is_extended(),
+                "frame": definition.get_name(),
+                "signals": signals,
+            })
+            .to_string()
+        } else {
+            let signals: Vec<String> = values
+                .iter()
+                .map(|(signal, value)| format_value(signal, *value, &frame.data))
+                .collect();
+            format!(
+                "{:.6} {} {} {}",
+                frame.timestamp,
+                frame.channel,
+                definition.get_name(),
+                signals.join(", ")
+            )
+        };
+
+        match writeln!(out, "{}", line) {
+            Ok(()) => {}
+            // the reader of the output went away, e.g. `| head`
+            Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    match out.flush() {
+        Err(ref e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Opens a trace file with the reader of its format, `-` reads a candump log from standard input
+fn open_trace(input: &Path, format: Option<Format>) -> io::Result<Frames> {
+    if input == Path::new("-") {
+        return match format.unwrap_or(Format::Candump) {
+            Format::Candump => Ok(Box::new(CandumpReader::new(BufReader::new(io::stdin())))),
+            Format::Asc => Ok(Box::new(AscReader::new(BufReader::new(io::stdin())))),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only candump and ASC traces can be read from standard input",
+            )),
+        };
+    }
+
+    let format = match format {
+        Some(format) => format,
+        None => detect_format(input)?,
+    };
+
+    Ok(match format {
+        Format::Candump => Box::new(CandumpReader::open(input)?),
+        Format::Asc => Box::new(AscReader::open(input)?),
+        Format::Blf => Box::new(BlfReader::open(input)?),
+        Format::Trc => Box::new(TrcReader::open(input)?),
+        Format::Mdf => Box::new(MdfReader::open(input)?),
+    })
+}
+
+fn detect_format(input: &Path) -> io::Result<Format> {
+    let extension = input
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("log") => Ok(Format::Candump),
+        Some("asc") => Ok(Format::Asc),
+        Some("blf") => Ok(Format::Blf),
+        Some("trc") => Ok(Format::Trc),
+        Some("mf4") | Some("mdf") => Ok(Format::Mdf),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unknown trace format, use --format",
+        )),
+    }
+}
+
+/// Returns true if there are no patterns or any of them matches
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| glob_match(pattern, name))
+}
+
+/// Matches a name against a glob pattern with `*` (any characters) and `?` (one character)
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it was tried at
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    p = star + 1;
+                    n = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("EEC1", "EEC1"));
+        assert!(glob_match("EEC*", "EEC1"));
+        assert!(glob_match("*_Speed", "Engine_Speed"));
+        assert!(glob_match("E?C1", "EEC1"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(!glob_match("EEC", "EEC1"));
+        assert!(!glob_match("*_Torque", "Engine_Speed"));
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format(Path::new("a.LOG")).unwrap(), Format::Candump);
+        assert_eq!(detect_format(Path::new("a.mf4")).unwrap(), Format::Mdf);
+        assert!(detect_format(Path::new("a.txt")).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! `fastcan` command-line tool
+//!
+//! Inspects DBC files, decodes or encodes single CAN frames and decodes trace files, with
+//! human-readable or JSON output.
+
+use std::path::PathBuf;
+use std::process;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+mod commands;
+mod log;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "fastcan",
+    version,
+    about = "Inspect DBC files and decode CAN frames"
+)]
+struct Cli {
+    /// DBC file to load, may be given several times.  Conflicting frame definitions are
+    /// resolved by `--on-conflict`.
+    #[arg(short, long, global = true, value_name = "FILE")]
+    dbc: Vec<PathBuf>,
+
+    /// How to resolve frame IDs defined differently by several DBC files
+    #[arg(long, global = true, value_enum, default_value = "prefer-left")]
+    on_conflict: ConflictPolicy,
+
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Summarize the contents of the DBC file
+    Info,
+    /// List all frames ordered by ID
+    ListFrames,
+    /// Show the definition of a frame and its signals
+    ShowFrame {
+        /// Frame ID (decimal or `0x` prefixed hex) or name
+        frame: String,
+    },
+    /// Decode the signals of a frame payload
+    Decode {
+        /// Frame ID (decimal or `0x` prefixed hex) or name
+        frame: String,
+        /// Payload as hex bytes, e.g. `1122334455667788` or `11:22:33:44`
+        data: String,
+    },
+    /// Encode signal values into a frame payload
+    ///
+    /// Signals not given are encoded with a raw value of 0.
+    Encode {
+        /// Frame ID (decimal or `0x` prefixed hex) or name
+        frame: String,
+        /// Signal values as `name=value`, the value may be a label of the signal's value table
+        #[arg(value_name = "SIGNAL=VALUE")]
+        signals: Vec<String>,
+    },
+    /// Check the DBC files for broken or questionable definitions
+    ///
+    /// Every file is checked on its own.  Exits with an error if any file has errors.
+    Lint,
+    /// Compare two DBC files and classify the changes as breaking or non-breaking
+    Diff {
+        /// Old version of the DBC file
+        old: PathBuf,
+        /// New version of the DBC file
+        new: PathBuf,
+    },
+    /// Decode all known frames of a trace file
+    DecodeLog {
+        /// Trace file, `-` reads a candump log from standard input
+        input: PathBuf,
+        /// Format of the trace file, detected from the file extension by default
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+        /// Only decode frames whose name matches the glob pattern, may be given several times
+        #[arg(long = "frame", value_name = "GLOB")]
+        frames: Vec<String>,
+        /// Only print signals whose name matches the glob pattern, may be given several times
+        #[arg(long = "signal", value_name = "GLOB")]
+        signals: Vec<String>,
+        /// DBC file of the bus on a channel (number or interface name), may be given several
+        /// times.  Channels without a file of their own are decoded with the `--dbc` files.
+        #[arg(long = "bus", value_name = "CHANNEL=FILE")]
+        buses: Vec<String>,
+    },
+}
+
+/// Trace file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// candump log (`.log`)
+    Candump,
+    /// Vector ASC (`.asc`)
+    Asc,
+    /// Vector BLF (`.blf`)
+    Blf,
+    /// PEAK PCAN trace (`.trc`)
+    Trc,
+    /// ASAM MDF4 bus logging (`.mf4`)
+    Mdf,
+}
+
+/// Resolutions of conflicting frame definitions, left is the file given first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ConflictPolicy {
+    /// Fail on conflicting definitions
+    Error,
+    /// Keep the definition of the file given first
+    PreferLeft,
+    /// Keep the definition of the file given last
+    PreferRight,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = commands::run(cli) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
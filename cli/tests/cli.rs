@@ -0,0 +1,372 @@
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+const DBC: &str = "../tests/data/sample.dbc";
+
+fn fastcan(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fastcan"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn fastcan_json(args: &[&str]) -> Value {
+    let output = fastcan(args);
+    assert!(output.status.success(), "{:?}", output);
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn info() {
+    let info = fastcan_json(&["--dbc", DBC, "--json", "info"]);
+    assert_eq!(info["frames"], 1);
+    assert_eq!(info["signals"], 1);
+}
+
+#[test]
+fn list_frames() {
+    let frames = fastcan_json(&["--dbc", DBC, "--json", "list-frames"]);
+    assert_eq!(frames[0]["name"], "EEC1");
+    assert_eq!(frames[0]["id"], 0x0CF00400);
+    assert_eq!(frames[0]["extended"], true);
+
+    let output = fastcan(&["--dbc", DBC, "list-frames"]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("0x0CF00400"));
+}
+
+#[test]
+fn show_frame() {
+    let frame = fastcan_json(&["--dbc", DBC, "--json", "show-frame", "EEC1"]);
+    assert_eq!(frame["description"], "Engine Controller");
+
+    let signal = &frame["signals"][0];
+    assert_eq!(signal["name"], "Engine_Speed");
+    assert_eq!(signal["unit"], "rpm");
+    assert_eq!(signal["attributes"]["SPN"], "190");
+    assert_eq!(signal["values"][0]["label"], "NotAvailable");
+}
+
+#[test]
+fn decode() {
+    let decoded = fastcan_json(&[
+        "--dbc",
+        DBC,
+        "--json",
+        "decode",
+        "0x0CF00400",
+        "1122334455667788",
+    ]);
+    assert_eq!(decoded["frame"], "EEC1");
+    assert_eq!(decoded["signals"][0]["value"], 2728.5);
+    assert_eq!(decoded["signals"][0]["label"], Value::Null);
+
+    let output = fastcan(&["--dbc", DBC, "decode", "EEC1", "112233FFFF667788"]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("Engine_Speed: 8191.875 rpm (NotAvailable)"));
+
+    // only the signals of the multiplexor value in the message
+    let decoded = fastcan_json(&[
+        "--dbc",
+        "../tests/data/ff.dbc",
+        "--json",
+        "decode",
+        "0x312",
+        "6409",
+    ]);
+    let names: Vec<&Value> = decoded["signals"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|signal| &signal["name"])
+        .collect();
+    assert_eq!(names, ["FF_BMS_Control_mux", "FF_BMS_Control_Balance"]);
+}
+
+#[test]
+fn encode() {
+    let encoded = fastcan_json(&[
+        "--dbc",
+        DBC,
+        "--json",
+        "encode",
+        "EEC1",
+        "Engine_Speed=2728.5",
+    ]);
+    assert_eq!(encoded["data"], "0000004455000000");
+
+    let output = fastcan(&["--dbc", DBC, "encode", "EEC1", "Engine_Speed=NotAvailable"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "000000FFFF000000\n"
+    );
+}
+
+#[test]
+fn encode_multiplexed() {
+    let layouts = "../tests/data/layouts.dbc";
+
+    // Speed of the other alternative overlaps Pressure
+    let output = fastcan(&["--dbc", layouts, "encode", "Muxed", "Mode=1", "Pressure=10"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "0105000000000000\n"
+    );
+
+    let output = fastcan(&["--dbc", layouts, "encode", "Muxed", "Mode=1", "Speed=5"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("signal Speed is not in the message for Mode=1"));
+}
+
+#[test]
+fn encode_fd() {
+    let encoded = fastcan_json(&[
+        "--dbc",
+        "../tests/data/layouts.dbc",
+        "--json",
+        "encode",
+        "Long",
+        "Tail=4660",
+        "Checksum=165",
+    ]);
+    let data = encoded["data"].as_str().unwrap();
+    assert_eq!(data.len(), 128);
+    assert!(data.ends_with("341200A5"), "{}", data);
+}
+
+#[test]
+fn errors() {
+    let output = fastcan(&["--dbc", DBC, "show-frame", "0x123"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no frame 0x123"));
+
+    let output = fastcan(&["--dbc", DBC, "encode", "EEC1", "Engine_Sped=1"]);
+    assert!(!output.status.success());
+
+    let output = fastcan(&["info"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn decode_log() {
+    let output = fastcan(&["--dbc", DBC, "decode-log", "../tests/data/sample.log"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "1436509052.249713 can0 EEC1 Engine_Speed: 2728.5 rpm\n\
+         1436509052.289713 can0 EEC1 Engine_Speed: 2741 rpm\n"
+    );
+}
+
+#[test]
+fn decode_log_json_lines() {
+    let output = fastcan(&[
+        "--dbc",
+        DBC,
+        "--dbc",
+        "../tests/data/ff.dbc",
+        "--json",
+        "decode-log",
+        "--frame",
+        "EEC*",
+        "../tests/data/sample.asc",
+    ]);
+    assert!(output.status.success());
+
+    let lines: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["frame"], "EEC1");
+    assert_eq!(lines[1]["signals"][0]["value"], 2741.0);
+}
+
+#[test]
+fn decode_log_filters() {
+    let output = fastcan(&[
+        "--dbc",
+        DBC,
+        "decode-log",
+        "--signal",
+        "*_Torque",
+        "../tests/data/sample.mf4",
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = fastcan(&["--dbc", DBC, "decode-log", "../tests/data/sample.txt"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn lint() {
+    let output = fastcan(&["--dbc", DBC, "lint"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = fastcan(&[
+        "--dbc",
+        DBC,
+        "--dbc",
+        "../tests/data/ff.dbc",
+        "--json",
+        "lint",
+    ]);
+    assert!(!output.status.success());
+
+    let diagnostics: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let errors: Vec<&Value> = diagnostics
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|diagnostic| diagnostic["severity"] == "error")
+        .collect();
+    // big-endian signals of ff.dbc use Vector's bit numbering instead of the crate's
+    assert_eq!(errors.len(), 12);
+    assert!(errors
+        .iter()
+        .all(|error| error["file"] == "../tests/data/ff.dbc"));
+    let checks: Vec<&Value> = errors.iter().map(|error| &error["check"]).collect();
+    assert_eq!(
+        checks
+            .iter()
+            .filter(|check| **check == "signal-out-of-frame")
+            .count(),
+        11
+    );
+    assert_eq!(checks[11], "duplicate-frame-name");
+}
+
+#[test]
+fn diff() {
+    let output = fastcan(&["diff", DBC, DBC]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let changes = fastcan_json(&["--json", "diff", DBC, "../tests/data/ff.dbc"]);
+    let removed: Vec<&Value> = changes
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|change| change["impact"] == "breaking")
+        .collect();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0]["kind"], "frame removed");
+    assert_eq!(removed[0]["frame"], "EEC1");
+}
+
+#[test]
+fn on_conflict() {
+    let conflicting = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("conflicting.dbc");
+    std::fs::write(
+        &conflicting,
+        "BO_ 2364539904 EEC1 : 8 Vector__XXX\n \
+         SG_ Engine_Speed : 24|16@1+ (0.25,0) [0|16000] \"rpm\" Vector__XXX\n",
+    )
+    .unwrap();
+    let conflicting = conflicting.to_str().unwrap();
+
+    let output = fastcan(&[
+        "--dbc",
+        DBC,
+        "--dbc",
+        conflicting,
+        "decode",
+        "EEC1",
+        "1122334455667788",
+    ]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("2728.5"));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Engine_Speed scaling changed"));
+
+    let output = fastcan(&[
+        "--dbc",
+        DBC,
+        "--dbc",
+        conflicting,
+        "--on-conflict",
+        "prefer-right",
+        "decode",
+        "EEC1",
+        "1122334455667788",
+    ]);
+    assert!(String::from_utf8(output.stdout).unwrap().contains("5457"));
+
+    let output = fastcan(&[
+        "--dbc",
+        DBC,
+        "--dbc",
+        conflicting,
+        "--on-conflict",
+        "error",
+        "info",
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn decode_log_buses() {
+    let output = fastcan(&[
+        "decode-log",
+        "--bus",
+        "can1=../tests/data/ff.dbc",
+        "../tests/data/sample.log",
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = fastcan(&[
+        "--dbc",
+        "../tests/data/ff.dbc",
+        "decode-log",
+        "--bus",
+        &format!("can0={}", DBC),
+        "../tests/data/sample.log",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 2);
+
+    let output = fastcan(&["decode-log", "--bus", DBC, "../tests/data/sample.log"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn dangling_entries() {
+    let dangling = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("dangling.dbc");
+    std::fs::write(
+        &dangling,
+        "BO_ 100 X : 1 Vector__XXX\n \
+         SG_ S : 0|8@1+ (1,0) [0|255] \"\" Vector__XXX\n\
+         CM_ SG_ 100 T \"Comment without signal\";\n\
+         BA_ \"SPN\" SG_ 100 U 190;\n\
+         VAL_ 100 V 1 \"On\" ;\n\
+         VAL_ 200 S 1 \"On\" ;\n",
+    )
+    .unwrap();
+    let dangling = dangling.to_str().unwrap();
+
+    let info = fastcan_json(&["--dbc", dangling, "--json", "info"]);
+    assert_eq!(info["frames"], 1);
+    assert_eq!(info["signals"], 1);
+
+    let frame = fastcan_json(&["--dbc", dangling, "--json", "show-frame", "X"]);
+    assert_eq!(frame["signals"].as_array().unwrap().len(), 1);
+    assert_eq!(frame["signals"][0]["name"], "S");
+
+    let decoded = fastcan_json(&["--dbc", dangling, "--json", "decode", "X", "07"]);
+    assert_eq!(decoded["signals"].as_array().unwrap().len(), 1);
+
+    let output = fastcan(&["--dbc", dangling, "encode", "X", "S=7"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "07");
+}
@@ -0,0 +1,174 @@
+//! Procedural macros binding DBC files at compile time
+//!
+//! `dbc!` is an alternative to generating frame structs with `fastcan::codegen` in a build
+//! script.  It reads a DBC file with the parser of `fastcan::dbc::DbcLibrary` while the crate
+//! is compiled and expands to the module `fastcan::codegen::generate` produces.  Definitions the
+//! parser can not read are compile errors instead of silently skipped lines.
+//!
+//! `fastcan` re-exports the macro as `fastcan::dbc!` with its `macros` feature.  To avoid a
+//! dependency cycle, this crate uses the sources of `fastcan` built as the package
+//! `fastcan-macros-support` in `macros/support`.
+
+extern crate proc_macro;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use fastcan::codegen::generate;
+use fastcan::dbc::{DbcLibrary, Entry};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// Expands to typed frame structs for every frame of a DBC file
+///
+/// The path is relative to the directory of the `Cargo.toml` of the crate invoking the macro,
+/// and the crate is rebuilt when the file changes.  As the expansion contains private helper
+/// functions, invoke it once per module:
+///
+/// ```rust
+/// mod frames {
+///     fastcan_macros::dbc!("../tests/data/sample.dbc");
+/// }
+///
+/// let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+/// let eec1 = frames::Eec1::decode(&payload).unwrap();
+///
+/// assert_eq!(eec1.engine_speed, frames::Eec1EngineSpeed::Value(2728.5));
+/// ```
+///
+/// Frame (`BO_`), signal (`SG_`), node (`BU_`) and value table (`VAL_`) definitions that can
+/// not be parsed fail the build with the file name and line of the definition.
+#[proc_macro]
+pub fn dbc(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+
+    match expand(&path) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(path: &LitStr) -> syn::Result<proc_macro2::TokenStream> {
+    let error = |message: String| syn::Error::new(path.span(), message);
+
+    let name = path.value();
+    let file = match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(&name),
+        None => PathBuf::from(&name),
+    };
+    let bytes =
+        fs::read(&file).map_err(|e| error(format!("could not read {}: {}", file.display(), e)))?;
+    // DBC files are ISO-8859-1 encoded, see `DbcLibrary::from_dbc_file`
+    let data: String = bytes.iter().map(|&byte| char::from(byte)).collect();
+
+    let lib =
+        parse(&data).map_err(|(line, message)| error(format!("{}:{}: {}", name, line, message)))?;
+    let code: proc_macro2::TokenStream = generate(&lib)
+        .parse()
+        .map_err(|e| error(format!("could not generate code for {}: {}", name, e)))?;
+
+    // makes cargo rebuild the crate when the file changes
+    let file = file.display().to_string();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#file);
+        #code
+    })
+}
+
+/// Keywords of the entries that have to be parsed and a description for error messages
+const CHECKED: &[(&str, &str)] = &[
+    ("BU_", "node list"),
+    ("BO_", "frame definition"),
+    ("SG_", "signal definition"),
+    ("VAL_", "value table"),
+];
+
+/// Reads the DBC file like `DbcLibrary::from_dbc_str`, but fails with the line number and a
+/// message on checked entries that can not be parsed or added
+fn parse(data: &str) -> Result<DbcLibrary, (usize, String)> {
+    let mut lib = DbcLibrary::default();
+
+    for (index, line) in data.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap_or("").trim_end_matches(':');
+        // the `NS_` section lists keywords on lines of their own, and the library only reads
+        // value tables of signals, not those of environment variables (`VAL_ EnvVar ...`)
+        let checked = match tokens.next() {
+            Some(id) if keyword != "VAL_" || id.parse::<u32>().is_ok() => {
+                CHECKED.iter().find(|(checked, _)| *checked == keyword)
+            }
+            _ => None,
+        };
+
+        let entry = match (line.parse::<Entry>(), checked) {
+            (Ok(entry), _) => entry,
+            (Err(_), Some((_, kind))) => {
+                return Err((index + 1, format!("invalid {} `{}`", kind, line.trim())))
+            }
+            (Err(_), None) => continue,
+        };
+        if let Err(e) = lib.add_entry(entry) {
+            if let Some((_, kind)) = checked {
+                return Err((index + 1, format!("invalid {}: {}", kind, e)));
+            }
+        }
+    }
+
+    Ok(lib)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let lib = parse(
+            "VERSION \"\"\n\nBO_ 100 Status: 1 Engine\n SG_ Running : 0|1@1+ (1,0) [0|1] \"\" X\n",
+        )
+        .unwrap();
+        assert!(lib.get_frame(100).unwrap().get_signal("Running").is_some());
+
+        for file in &["sample", "ff", "j1939", "codegen"] {
+            let bytes = fs::read(format!("../tests/data/{}.dbc", file)).unwrap();
+            let data: String = bytes.iter().map(|&byte| char::from(byte)).collect();
+            assert_eq!(parse(&data).err(), None, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("BO_ 100 Status: 1 Engine\n SG_ Running : 0|1@1+ (1,0) X\n").unwrap_err();
+        assert_eq!(
+            error,
+            (
+                2,
+                "invalid signal definition `SG_ Running : 0|1@1+ (1,0) X`".to_string()
+            )
+        );
+
+        let error = parse("\n SG_ Running : 0|1@1+ (1,0) [0|1] \"\" X\n").unwrap_err();
+        assert_eq!(error.0, 2);
+        assert!(error.1.starts_with("invalid signal definition: "));
+
+        assert_eq!(parse("BO_ Status\n").unwrap_err().0, 1);
+        // entries the library does not use are not checked
+        assert!(parse("BO_TX_BU_ 100 : Engine;\nBA_DEF_ broken\n").is_ok());
+        assert!(parse("VAL_ EnvVar 0 \"Off\" 1 \"On\" ;\n").is_ok());
+        assert_eq!(parse("VAL_ 100 Running 1 On ;\n").unwrap_err().0, 1);
+    }
+
+    #[test]
+    fn test_parse_valid_lines() {
+        let lib = parse(concat!(
+            "BO_ 100 Status: 2 Engine\n",
+            " SG_ Mux M : 0|4@1+ (1,0) [0|15] \"\" X\n",
+            " SG_ Page m1M : 4|4@1+ (1,0) [0|15] \"\" X\n",
+            " SG_ Current m1 : 8|8@1- (1E-005,0) [-0.00128|0.00127] \"A\" X\n",
+        ))
+        .unwrap();
+        assert_eq!(lib.get_frame(100).unwrap().get_signals().len(), 3);
+    }
+}
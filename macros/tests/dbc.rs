@@ -0,0 +1,33 @@
+mod frames {
+    fastcan_macros::dbc!("../tests/data/codegen.dbc");
+}
+
+mod sample {
+    fastcan_macros::dbc!("../tests/data/sample.dbc");
+}
+
+use frames::{Diagnostics, Eec1, Eec1EngineTorqueMode};
+
+#[test]
+fn decode_and_encode() {
+    assert_eq!(Eec1::ID, 0x0CF00400);
+
+    let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    let eec1 = Eec1::decode(&payload).unwrap();
+    assert_eq!(
+        eec1.engine_torque_mode,
+        Eec1EngineTorqueMode::AcceleratorPedal
+    );
+    assert_eq!(Eec1::decode(&eec1.encode()), Some(eec1));
+
+    let diagnostics = Diagnostics::decode(&[0x01, 0x64, 0x34, 0x12]).unwrap();
+    assert_eq!(diagnostics.oil_pressure, Some(400.0));
+    assert_eq!(diagnostics.error_code, Some(0x1234));
+}
+
+#[test]
+fn same_frames_in_several_modules() {
+    let payload = [0x11, 0x22, 0x33, 0xFF, 0xFF, 0x66, 0x77, 0x88];
+    let eec1 = sample::Eec1::decode(&payload).unwrap();
+    assert_eq!(eec1.engine_speed, sample::Eec1EngineSpeed::NotAvailable);
+}
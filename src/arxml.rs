@@ -0,0 +1,824 @@
+//! Reading CAN clusters of AUTOSAR system descriptions
+//!
+//! ARXML files (AUTOSAR 4) describe the `CAN-FRAME-TRIGGERING`s of a `CAN-CLUSTER`, which give
+//! the ID of a `CAN-FRAME`.  Each triggered frame becomes a `DbcFrame`, the `I-SIGNAL`s mapped to
+//! the PDUs of the frame its `DbcSignal`s.  The `COMPU-METHOD` of a signal gives its scale and
+//! offset (`LINEAR` scales) and value table (`TEXTTABLE` scales), the `DESC` of the signal or its
+//! system signal its description, and the cyclic timing of a PDU the `GenMsgCycleTime` attribute.
+//!
+//! The selector field of a `MULTIPLEXED-I-PDU` becomes the multiplexor of the frame, named after
+//! the PDU with a `_Selector` suffix.  Signals of the static part are plain signals, signals of
+//! the dynamic part alternatives are multiplexed with their selector field code.
+//!
+//! AUTOSAR numbers bits from the first byte of the frame and gives the position of the least
+//! significant bit of little endian signals, but of the most significant bit of big endian
+//! signals and selector fields.  Positions are converted to the numbering of
+//! `DbcSignalDefinition`.  References are resolved by their absolute path.  ECU
+//! instances are not read, so senders and receivers are `Vector__XXX`.  Floating point signals
+//! (`IEEE754` base types), signal groups and text table ranges of more than 256 values are not
+//! supported and skipped.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//!
+//! let lib = DbcLibrary::from_arxml_file("./tests/data/sample.arxml").unwrap();
+//!
+//! let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+//! let decoded = lib.decode(0.0, lib.get_frame(0x0CF00400).unwrap().get_can_id(), &payload);
+//! assert_eq!(decoded.unwrap().signals["Engine_Speed"], 2728.5);
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+use crate::bus::BusLibrary;
+use crate::dbc::{
+    invalid, DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, Multiplexing, ValueDefinition,
+    CYCLE_TIME, NO_NODE,
+};
+use crate::id::CanId;
+
+/// Text table ranges with more values are skipped
+const MAX_LABEL_GROUP: i64 = 256;
+
+impl DbcLibrary {
+    /// Reads the frames of an ARXML file with a single CAN cluster, see
+    /// `BusLibrary::from_arxml_file` for files with several clusters
+    pub fn from_arxml_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_arxml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the frames of an ARXML document with a single CAN cluster
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the document is not valid XML, has no or
+    /// several CAN clusters, unresolved references, or frames and signals with invalid values.
+    pub fn from_arxml_str(data: &str) -> io::Result<Self> {
+        let mut clusters = read_arxml(data)?;
+        match clusters.len() {
+            1 => Ok(clusters.remove(0).1),
+            0 => Err(invalid("ARXML document without CAN cluster")),
+            n => Err(invalid(format!(
+                "ARXML document with {} CAN clusters, read it as BusLibrary",
+                n
+            ))),
+        }
+    }
+}
+
+impl BusLibrary {
+    /// Reads an ARXML file with a library per CAN cluster, the channels are the cluster names
+    pub fn from_arxml_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_arxml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Reads an ARXML document with a library per CAN cluster, the channels are the cluster names
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::bus::BusLibrary;
+    /// use fastcan::trace::Channel;
+    ///
+    /// let buses = BusLibrary::from_arxml_file("./tests/data/sample.arxml").unwrap();
+    ///
+    /// assert!(buses.get(&Channel::from("Powertrain")).is_some());
+    /// ```
+    pub fn from_arxml_str(data: &str) -> io::Result<Self> {
+        let mut buses = BusLibrary::new();
+        for (name, lib) in read_arxml(data)? {
+            buses.insert(name.as_str(), lib);
+        }
+        Ok(buses)
+    }
+}
+
+fn is_element(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|child| is_element(child, name))
+}
+
+fn descendants<'a, 'i>(node: Node<'a, 'i>, name: &'a str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.descendants()
+        .filter(move |descendant| is_element(descendant, name))
+}
+
+fn descendant<'a, 'i>(node: Node<'a, 'i>, name: &'a str) -> Option<Node<'a, 'i>> {
+    descendants(node, name).next()
+}
+
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name)
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+fn short_name<'a>(node: Node<'a, '_>) -> &'a str {
+    text(node, "SHORT-NAME").unwrap_or_default()
+}
+
+/// Tag and short name of an element for error messages
+fn describe(node: Node) -> String {
+    match text(node, "SHORT-NAME") {
+        Some(name) => format!("{} {}", node.tag_name().name(), name),
+        None => node.tag_name().name().to_string(),
+    }
+}
+
+fn parse<T>(value: &str, name: &str, owner: Node) -> io::Result<T>
+where
+    T: std::str::FromStr,
+{
+    value.parse().map_err(|_| {
+        invalid(format!(
+            "invalid {} \"{}\" of {}",
+            name,
+            value,
+            describe(owner)
+        ))
+    })
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal integer, `None` if the element is missing
+fn integer<T>(node: Node, name: &str) -> io::Result<Option<T>>
+where
+    T: std::convert::TryFrom<u64>,
+{
+    let value = match text(node, name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    };
+    parsed
+        .and_then(|parsed| T::try_from(parsed).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            invalid(format!(
+                "invalid {} \"{}\" of {}",
+                name,
+                value,
+                describe(node)
+            ))
+        })
+}
+
+/// Parses a floating point number, `None` if the element is missing
+fn float(node: Node, name: &str) -> io::Result<Option<f64>> {
+    text(node, name)
+        .map(|value| parse(value, name, node))
+        .transpose()
+}
+
+/// Text of the first `L-2` element of the `DESC` of an element
+fn description(node: Node) -> Option<String> {
+    child(node, "DESC")
+        .and_then(|desc| child(desc, "L-2"))
+        .and_then(|text| text.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Elements of a document by their absolute path of short names
+struct Arxml<'a, 'i> {
+    elements: HashMap<String, Node<'a, 'i>>,
+}
+
+impl<'a, 'i> Arxml<'a, 'i> {
+    fn new(document: &'a Document<'i>) -> Self {
+        let mut elements = HashMap::new();
+        for node in document.descendants() {
+            if node.is_element() && text(node, "SHORT-NAME").is_some() {
+                let mut names: Vec<&str> = node
+                    .ancestors()
+                    .filter_map(|ancestor| text(ancestor, "SHORT-NAME"))
+                    .collect();
+                names.reverse();
+                elements.insert(format!("/{}", names.join("/")), node);
+            }
+        }
+        Arxml { elements }
+    }
+
+    /// Element referenced by the text of a reference element
+    fn resolve(&self, reference: Node<'a, 'i>) -> io::Result<Node<'a, 'i>> {
+        let path = reference.text().unwrap_or_default().trim();
+        self.elements
+            .get(path)
+            .copied()
+            .ok_or_else(|| invalid(format!("unresolved reference \"{}\"", path)))
+    }
+
+    /// Element referenced by the child `name` of a node, `None` without such child
+    fn reference(&self, node: Node<'a, 'i>, name: &str) -> io::Result<Option<Node<'a, 'i>>> {
+        child(node, name)
+            .map(|reference| self.resolve(reference))
+            .transpose()
+    }
+}
+
+fn read_arxml(data: &str) -> io::Result<Vec<(String, DbcLibrary)>> {
+    let document = Document::parse(data).map_err(invalid)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "AUTOSAR" {
+        return Err(invalid("not an ARXML document"));
+    }
+    let arxml = Arxml::new(&document);
+
+    let mut clusters = Vec::new();
+    for cluster in descendants(root, "CAN-CLUSTER") {
+        let mut lib = DbcLibrary::default();
+        for triggering in descendants(cluster, "CAN-FRAME-TRIGGERING") {
+            let frame = read_frame(&arxml, triggering)?;
+            let id = frame.get_can_id();
+            if lib.frames_mut().insert(id, frame).is_some() {
+                return Err(invalid(format!("duplicate frame ID 0x{}", id)));
+            }
+        }
+        clusters.push((short_name(cluster).to_string(), lib));
+    }
+
+    Ok(clusters)
+}
+
+/// Signal read from a PDU, before its position is converted
+struct ArxmlSignal {
+    position: usize,
+    definition: DbcSignalDefinition,
+    description: Option<String>,
+    values: Option<ValueDefinition>,
+}
+
+fn read_frame(arxml: &Arxml, triggering: Node) -> io::Result<DbcFrame> {
+    let frame = arxml
+        .reference(triggering, "FRAME-REF")?
+        .ok_or_else(|| invalid(format!("{} without frame", describe(triggering))))?;
+    let name = short_name(frame).to_string();
+
+    let raw_id = integer(triggering, "IDENTIFIER")?
+        .ok_or_else(|| invalid(format!("{} without identifier", describe(triggering))))?;
+    let extended = text(triggering, "CAN-ADDRESSING-MODE") == Some("EXTENDED");
+    let id = CanId::new(raw_id, extended)
+        .ok_or_else(|| invalid(format!("invalid ID {} of frame {}", raw_id, name)))?;
+
+    let mut signals = Vec::new();
+    let mut description = description(frame);
+    let mut cycle_time = None;
+    for mapping in descendants(frame, "PDU-TO-FRAME-MAPPING") {
+        let pdu = match arxml.reference(mapping, "PDU-REF")? {
+            Some(pdu) => pdu,
+            None => continue,
+        };
+        let offset = integer(mapping, "START-POSITION")?.unwrap_or(0);
+        read_pdu(arxml, pdu, offset, Multiplexing::None, &mut signals)?;
+
+        description = description.or_else(|| self::description(pdu));
+        if cycle_time.is_none() {
+            cycle_time = read_cycle_time(pdu)?;
+        }
+    }
+
+    let message_len: u32 = integer(frame, "FRAME-LENGTH")?.unwrap_or(0);
+    let len = (message_len as usize).max(8);
+
+    let mut signal_map = HashMap::new();
+    for signal in signals {
+        let mut definition = signal.definition;
+        let beyond = || {
+            invalid(format!(
+                "signal {} beyond the end of frame {}",
+                definition.name, name
+            ))
+        };
+        // AUTOSAR numbers the bits of all signals from the first byte
+        let start_bit = if definition.little_endian {
+            signal.position
+        } else {
+            let position = least_significant_bit(signal.position, definition.bit_len);
+            let byte = (len - 1).checked_sub(position / 8).ok_or_else(beyond)?;
+            8 * byte + position % 8
+        };
+        if start_bit + definition.bit_len > 8 * len {
+            return Err(beyond());
+        }
+        definition.start_bit = start_bit;
+
+        let signal = DbcSignal::new(
+            Some(definition),
+            signal.description,
+            HashMap::new(),
+            signal.values,
+        );
+        signal_map.insert(signal.get_definition().name.clone(), signal);
+    }
+
+    let mut attributes = HashMap::new();
+    if let Some(cycle_time) = cycle_time {
+        attributes.insert(CYCLE_TIME.to_string(), cycle_time);
+    }
+
+    Ok(DbcFrame::new(
+        name,
+        id.to_dbc(),
+        message_len,
+        NO_NODE.to_string(),
+        attributes,
+        description,
+        signal_map,
+    ))
+}
+
+/// Position of the least significant bit of a big endian signal from the position of its most
+/// significant bit, following the bits down to bit 0 of a byte and on at bit 7 of the next byte
+fn least_significant_bit(position: usize, bit_len: usize) -> usize {
+    let remaining = bit_len.saturating_sub(1);
+    let bit = position % 8;
+    if remaining <= bit {
+        return position - remaining;
+    }
+    let remaining = remaining - bit - 1;
+    8 * (position / 8 + 1 + remaining / 8) + 7 - remaining % 8
+}
+
+/// Cycle time in milliseconds of the cyclic timing of a PDU
+fn read_cycle_time(pdu: Node) -> io::Result<Option<String>> {
+    let period =
+        descendant(pdu, "CYCLIC-TIMING").and_then(|timing| descendant(timing, "TIME-PERIOD"));
+    Ok(match period {
+        Some(period) => {
+            float(period, "VALUE")?.map(|seconds| format!("{}", (seconds * 1000.0).round()))
+        }
+        None => None,
+    })
+}
+
+/// Reads the signals of a PDU mapped at `offset` bits into the frame
+fn read_pdu(
+    arxml: &Arxml,
+    pdu: Node,
+    offset: usize,
+    multiplexing: Multiplexing,
+    signals: &mut Vec<ArxmlSignal>,
+) -> io::Result<()> {
+    if pdu.tag_name().name() != "MULTIPLEXED-I-PDU" {
+        for mapping in descendants(pdu, "I-SIGNAL-TO-I-PDU-MAPPING") {
+            // mappings of signal groups have no signal
+            if let Some(signal) = arxml.reference(mapping, "I-SIGNAL-REF")? {
+                signals.extend(read_signal(arxml, mapping, signal, offset, multiplexing)?);
+            }
+        }
+        return Ok(());
+    }
+
+    let position: usize = integer(pdu, "SELECTOR-FIELD-START-POSITION")?.unwrap_or(0);
+    let bit_len: usize = integer(pdu, "SELECTOR-FIELD-LENGTH")?.unwrap_or(0);
+    signals.push(ArxmlSignal {
+        position: offset + position,
+        definition: DbcSignalDefinition {
+            name: format!("{}_Selector", short_name(pdu)),
+            start_bit: 0,
+            bit_len,
+            little_endian: text(pdu, "SELECTOR-FIELD-BYTE-ORDER")
+                != Some("MOST-SIGNIFICANT-BYTE-FIRST"),
+            signed: false,
+            scale: 1.0,
+            offset: 0.0,
+            min_value: 0.0,
+            max_value: 0.0,
+            units: String::new(),
+            receiving_node: NO_NODE.to_string(),
+            multiplexing: Multiplexing::Multiplexor,
+        },
+        description: None,
+        values: None,
+    });
+
+    for part in descendants(pdu, "STATIC-PART") {
+        if let Some(static_pdu) = arxml.reference(part, "I-PDU-REF")? {
+            read_pdu(arxml, static_pdu, offset, multiplexing, signals)?;
+        }
+    }
+    for alternative in descendants(pdu, "DYNAMIC-PART-ALTERNATIVE") {
+        let code = integer(alternative, "SELECTOR-FIELD-CODE")?.unwrap_or(0);
+        if let Some(dynamic_pdu) = arxml.reference(alternative, "I-PDU-REF")? {
+            read_pdu(
+                arxml,
+                dynamic_pdu,
+                offset,
+                Multiplexing::Multiplexed(code),
+                signals,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_signal(
+    arxml: &Arxml,
+    mapping: Node,
+    signal: Node,
+    offset: usize,
+    multiplexing: Multiplexing,
+) -> io::Result<Option<ArxmlSignal>> {
+    let name = short_name(signal).to_string();
+    let position: usize = integer(mapping, "START-POSITION")?.unwrap_or(0);
+    let bit_len = integer(signal, "LENGTH")?
+        .ok_or_else(|| invalid(format!("{} without length", describe(signal))))?;
+    let system_signal = arxml.reference(signal, "SYSTEM-SIGNAL-REF")?;
+
+    // data properties of the network representation take precedence over the physical ones
+    let props: Vec<Node> = child(signal, "NETWORK-REPRESENTATION-PROPS")
+        .into_iter()
+        .chain(system_signal.and_then(|system| child(system, "PHYSICAL-PROPS")))
+        .collect();
+    let property = |name: &'static str| -> io::Result<Option<Node>> {
+        props
+            .iter()
+            .find_map(|props| descendant(*props, name))
+            .map(|reference| arxml.resolve(reference))
+            .transpose()
+    };
+
+    let signed = match property("BASE-TYPE-REF")?.and_then(|base| text(base, "BASE-TYPE-ENCODING"))
+    {
+        Some("2C") => true,
+        Some("IEEE754") => return Ok(None),
+        _ => false,
+    };
+
+    let compu_method = property("COMPU-METHOD-REF")?;
+    let compu = match compu_method {
+        Some(compu_method) => read_compu_method(compu_method)?,
+        None => CompuMethod::default(),
+    };
+    let unit = match compu_method {
+        Some(compu_method) => arxml.reference(compu_method, "UNIT-REF")?,
+        None => None,
+    };
+    let unit = match unit {
+        Some(unit) => Some(unit),
+        None => property("UNIT-REF")?,
+    };
+
+    let definition = DbcSignalDefinition {
+        name,
+        start_bit: 0,
+        bit_len,
+        little_endian: text(mapping, "PACKING-BYTE-ORDER") != Some("MOST-SIGNIFICANT-BYTE-FIRST"),
+        signed,
+        scale: compu.scale as f32,
+        offset: compu.offset as f32,
+        min_value: compu.min_value as f32,
+        max_value: compu.max_value as f32,
+        units: unit
+            .and_then(|unit| text(unit, "DISPLAY-NAME").or_else(|| text(unit, "SHORT-NAME")))
+            .unwrap_or_default()
+            .to_string(),
+        receiving_node: NO_NODE.to_string(),
+        multiplexing,
+    };
+
+    Ok(Some(ArxmlSignal {
+        position: offset + position,
+        definition,
+        description: system_signal
+            .and_then(description)
+            .or_else(|| description(signal)),
+        values: Some(ValueDefinition::new(compu.values))
+            .filter(|values| !values.get_values().is_empty()),
+    }))
+}
+
+/// Scaling and value table of a `COMPU-METHOD`
+struct CompuMethod {
+    scale: f64,
+    offset: f64,
+    min_value: f64,
+    max_value: f64,
+    values: Vec<(i64, String)>,
+}
+
+impl Default for CompuMethod {
+    fn default() -> Self {
+        CompuMethod {
+            scale: 1.0,
+            offset: 0.0,
+            min_value: 0.0,
+            max_value: 0.0,
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Reads the first linear scale and the text table scales of a compu method
+fn read_compu_method(compu_method: Node) -> io::Result<CompuMethod> {
+    let mut compu = CompuMethod::default();
+    let mut linear = false;
+
+    let scales = child(compu_method, "COMPU-INTERNAL-TO-PHYS")
+        .into_iter()
+        .flat_map(|internal_to_phys| descendants(internal_to_phys, "COMPU-SCALE"));
+    for scale in scales {
+        let lower = float(scale, "LOWER-LIMIT")?;
+        let upper = float(scale, "UPPER-LIMIT")?;
+
+        if let Some(coeffs) = child(scale, "COMPU-RATIONAL-COEFFS") {
+            if linear {
+                continue;
+            }
+            linear = true;
+
+            let coefficients = |name: &str| -> io::Result<Vec<f64>> {
+                child(coeffs, name)
+                    .into_iter()
+                    .flat_map(|coefficients| coefficients.children().filter(|v| is_element(v, "V")))
+                    .map(|v| parse(v.text().unwrap_or_default().trim(), name, compu_method))
+                    .collect()
+            };
+            let numerator = coefficients("COMPU-NUMERATOR")?;
+            let denominator = coefficients("COMPU-DENOMINATOR")?
+                .first()
+                .copied()
+                .unwrap_or(1.0);
+            if denominator == 0.0 {
+                return Err(invalid(format!(
+                    "invalid COMPU-DENOMINATOR \"0\" of {}",
+                    describe(compu_method)
+                )));
+            }
+            compu.offset = numerator.first().copied().unwrap_or(0.0) / denominator;
+            compu.scale = numerator.get(1).copied().unwrap_or(1.0) / denominator;
+
+            if let (Some(lower), Some(upper)) = (lower, upper) {
+                if lower.is_finite() && upper.is_finite() {
+                    let (a, b) = (
+                        lower * compu.scale + compu.offset,
+                        upper * compu.scale + compu.offset,
+                    );
+                    compu.min_value = a.min(b);
+                    compu.max_value = a.max(b);
+                }
+            }
+        } else if let Some(label) = child(scale, "COMPU-CONST").and_then(|c| text(c, "VT")) {
+            let from = lower.unwrap_or(0.0);
+            let to = upper.unwrap_or(from);
+            if from.is_finite() && to.is_finite() {
+                let (from, to) = (from as i64, to as i64);
+                if to >= from && to - from < MAX_LABEL_GROUP {
+                    compu
+                        .values
+                        .extend((from..=to).map(|value| (value, label.to_string())));
+                }
+            }
+        }
+    }
+
+    Ok(compu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapper::{DecodeMessage, EncodeMessage};
+    use crate::tests::helpers::assert_like_dbc;
+
+    fn sample() -> String {
+        fs::read_to_string("./tests/data/sample.arxml").unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let lib = DbcLibrary::from_arxml_str(&sample()).unwrap();
+        assert_eq!(lib.len(), 3);
+
+        let eec1 = lib.get_frame_by_id(CanId::Extended(0x0CF00400)).unwrap();
+        assert_eq!(eec1.get_name(), "EEC1");
+        assert_eq!(eec1.get_sending_node(), NO_NODE);
+        assert_eq!(
+            eec1.get_description(),
+            Some("Electronic Engine Controller 1")
+        );
+        assert_eq!(eec1.get_attributes()[CYCLE_TIME], "100");
+
+        let speed = eec1.get_signal("Engine_Speed").unwrap();
+        assert_eq!(speed.get_description(), Some("Actual engine speed"));
+        let definition = speed.get_definition();
+        assert_eq!(definition.units, "rpm");
+        assert_eq!(definition.scale, 0.125);
+        assert_eq!(definition.max_value, 8031.875);
+        assert_eq!(
+            speed.decode_label(&[0, 0, 0, 0xFF, 0xFF, 0, 0, 0]),
+            Some("NotAvailable")
+        );
+        let temp = eec1.get_signal("Engine_Temp").unwrap().get_definition();
+        assert_eq!(
+            (temp.offset, temp.min_value, temp.max_value),
+            (-40.0, -40.0, 210.0)
+        );
+        let mode = eec1.get_signal("Engine_Torque_Mode").unwrap();
+        assert_eq!(mode.get_value_definition().unwrap().get_values().len(), 3);
+
+        let transmission = lib.get_frame(0x123).unwrap();
+        let gear = transmission.get_signal("Gear").unwrap().get_definition();
+        assert!(gear.signed);
+        assert_eq!((gear.min_value, gear.max_value), (-8.0, 7.0));
+        let torque = transmission
+            .get_signal("Output_Torque")
+            .unwrap()
+            .get_definition();
+        assert_eq!((torque.start_bit, torque.little_endian), (40, false));
+        assert_eq!(torque.units, "Nm");
+
+        let diagnostics = lib.get_frame(0x400).unwrap();
+        assert_eq!(diagnostics.get_message_len(), 5);
+        let multiplexing = |name: &str| {
+            diagnostics
+                .get_signal(name)
+                .unwrap()
+                .get_definition()
+                .multiplexing
+        };
+        assert_eq!(
+            multiplexing("Diagnostics_Selector"),
+            Multiplexing::Multiplexor
+        );
+        assert_eq!(multiplexing("Counter"), Multiplexing::None);
+        assert_eq!(
+            multiplexing("Battery_Voltage"),
+            Multiplexing::Multiplexed(0)
+        );
+        assert_eq!(multiplexing("Error_Code"), Multiplexing::Multiplexed(1));
+    }
+
+    #[test]
+    fn test_like_dbc() {
+        let dbc = DbcLibrary::from_dbc_file("./tests/data/codegen.dbc").unwrap();
+        let arxml = DbcLibrary::from_arxml_str(&sample()).unwrap();
+
+        let payload = [0x3C, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+        for frame in dbc.get_frames() {
+            let copy = arxml.get_frame_by_id(frame.get_can_id()).unwrap();
+            for signal in frame.get_signals() {
+                let name = &signal.get_definition().name;
+                if let Some(copied) = copy.get_signal(name) {
+                    assert_eq!(
+                        copied.decode_message(&payload),
+                        signal.decode_message(&payload),
+                        "{}",
+                        name
+                    );
+                    // value tables are in the order of the compu scales
+                    let values = |signal: &DbcSignal| {
+                        let mut values = signal
+                            .get_value_definition()
+                            .map(|values| values.get_values().to_vec())
+                            .unwrap_or_default();
+                        values.sort();
+                        values
+                    };
+                    assert_eq!(values(copied), values(signal), "{}", name);
+                } else {
+                    assert_eq!(name, "Page");
+                }
+            }
+        }
+
+        let mut values = HashMap::new();
+        values.insert("Gear".to_string(), -2.0);
+        values.insert("Clutch_Engaged".to_string(), 1.0);
+        values.insert("ShiftCount".to_string(), 42.0);
+        values.insert("Output_Torque".to_string(), -100.0);
+        let encoded: [u8; 8] = dbc
+            .get_frame(0x123)
+            .unwrap()
+            .encode_message(&values)
+            .unwrap();
+        let copied: [u8; 8] = arxml
+            .get_frame(0x123)
+            .unwrap()
+            .encode_message(&values)
+            .unwrap();
+        assert_eq!(encoded, copied);
+
+        // selector fields have no value table
+        let dbc = fs::read_to_string("./tests/data/layouts.dbc")
+            .unwrap()
+            .replace("VAL_ 1792 Mode 0 \"Drive\" 1 \"Service\" ;", "");
+        let dbc = DbcLibrary::from_dbc_str(&dbc);
+        let arxml = DbcLibrary::from_arxml_file("./tests/data/layouts.arxml").unwrap();
+        assert_like_dbc(&dbc, &arxml);
+        assert_eq!(
+            arxml.get_frame(0x600).unwrap().get_description(),
+            Some("CAN FD frame with signals past the first 8 bytes")
+        );
+    }
+
+    #[test]
+    fn test_big_endian_positions() {
+        assert_eq!(least_significant_bit(15, 16), 16);
+        assert_eq!(least_significant_bit(7, 12), 12);
+        assert_eq!(least_significant_bit(511, 8), 504);
+        assert_eq!(least_significant_bit(3, 4), 0);
+
+        // the same selector field in the other byte order
+        let lib = DbcLibrary::from_arxml_str(&sample()).unwrap();
+        let big_endian = DbcLibrary::from_arxml_str(
+            &sample()
+                .replace(
+                    "<SELECTOR-FIELD-BYTE-ORDER>MOST-SIGNIFICANT-BYTE-LAST<",
+                    "<SELECTOR-FIELD-BYTE-ORDER>MOST-SIGNIFICANT-BYTE-FIRST<",
+                )
+                .replace(
+                    "<SELECTOR-FIELD-START-POSITION>0<",
+                    "<SELECTOR-FIELD-START-POSITION>7<",
+                ),
+        )
+        .unwrap();
+        let payload = [1, 0x10, 0x27, 0, 0];
+        let selector = |lib: &DbcLibrary| {
+            let signal = lib
+                .get_frame(0x400)
+                .unwrap()
+                .get_signal("Diagnostics_Selector")
+                .unwrap();
+            signal.decode_raw(&payload)
+        };
+        assert_eq!(selector(&big_endian), Some(1));
+        assert_eq!(selector(&big_endian), selector(&lib));
+    }
+
+    #[test]
+    fn test_clusters() {
+        let arxml = sample().replace(
+            "</ELEMENTS>\n    </AR-PACKAGE>\n    <AR-PACKAGE>\n      <SHORT-NAME>Frames",
+            "<CAN-CLUSTER><SHORT-NAME>Body</SHORT-NAME></CAN-CLUSTER></ELEMENTS>\n    </AR-PACKAGE>\n    <AR-PACKAGE>\n      <SHORT-NAME>Frames",
+        );
+        let error = DbcLibrary::from_arxml_str(&arxml).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ARXML document with 2 CAN clusters, read it as BusLibrary"
+        );
+
+        let buses = BusLibrary::from_arxml_str(&arxml).unwrap();
+        let channel = crate::trace::Channel::from("Powertrain");
+        assert!(buses.get_frame(&channel, CanId::Standard(0x123)).is_some());
+        assert!(buses
+            .get(&crate::trace::Channel::from("Body"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error = |arxml: &str| DbcLibrary::from_arxml_str(arxml).unwrap_err().to_string();
+        assert_eq!(error("<FIBEX/>"), "not an ARXML document");
+        assert_eq!(error("<AUTOSAR/>"), "ARXML document without CAN cluster");
+        assert!(DbcLibrary::from_arxml_str("<AUTOSAR>").is_err());
+
+        let sample = sample();
+        assert_eq!(
+            error(&sample.replace("<IDENTIFIER>291<", "<IDENTIFIER>2048<")),
+            "invalid ID 2048 of frame Transmission"
+        );
+        assert_eq!(
+            error(&sample.replace("<IDENTIFIER>291<", "<IDENTIFIER>x<")),
+            "invalid IDENTIFIER \"x\" of CAN-FRAME-TRIGGERING Transmission_Triggering"
+        );
+        assert_eq!(
+            error(&sample.replace("/Frames/Transmission<", "/Frames/Gearbox<")),
+            "unresolved reference \"/Frames/Gearbox\""
+        );
+        assert_eq!(
+            error(&sample.replace("<IDENTIFIER>1024<", "<IDENTIFIER>291<")),
+            "duplicate frame ID 0x123"
+        );
+        assert_eq!(
+            error(&sample.replace("<V>0.125</V>", "<V>x</V>")),
+            "invalid COMPU-NUMERATOR \"x\" of COMPU-METHOD Engine_Speed"
+        );
+        assert_eq!(
+            error(&sample.replace("<START-POSITION>32<", "<START-POSITION>60<")),
+            "signal ShiftCount beyond the end of frame Transmission"
+        );
+    }
+}
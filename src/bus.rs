@@ -0,0 +1,256 @@
+//! Libraries of networks with several CAN buses
+//!
+//! The same ID usually means different frames on different buses.  A `BusLibrary` holds one
+//! `DbcLibrary` per channel and decodes frames with the library of the channel they were
+//! received on.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::bus::BusLibrary;
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::trace::candump::CandumpReader;
+//!
+//! let mut buses = BusLibrary::new();
+//! buses.insert("can0", DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap());
+//! buses.insert("can1", DbcLibrary::from_dbc_file("./tests/data/ff.dbc").unwrap());
+//!
+//! let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+//! for decoded in buses.decode_trace(reader) {
+//!     let (channel, decoded) = decoded.unwrap();
+//!     println!("{} {} {:?}", channel, decoded.frame.get_name(), decoded.signals);
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+
+use crate::dbc::{DbcFrame, DbcLibrary};
+use crate::id::CanId;
+use crate::mapper::DecodedFrame;
+use crate::trace::{Channel, TraceFrame};
+
+/// Collection of `DbcLibrary`s by the channel of their bus
+#[derive(Clone, Debug, Default)]
+pub struct BusLibrary {
+    buses: HashMap<Channel, DbcLibrary>,
+    fallback: Option<DbcLibrary>,
+}
+
+impl BusLibrary {
+    /// Creates an empty `BusLibrary`
+    pub fn new() -> Self {
+        BusLibrary::default()
+    }
+
+    /// Sets the library of a channel, returns the library previously set.  Channels are given
+    /// as `Channel`, channel number or interface name, e.g. `1` or `"can0"`.
+    pub fn insert<C>(&mut self, channel: C, lib: DbcLibrary) -> Option<DbcLibrary>
+    where
+        C: Into<Channel>,
+    {
+        self.buses.insert(channel.into(), lib)
+    }
+
+    /// Removes the library of a channel and returns it
+    pub fn remove(&mut self, channel: &Channel) -> Option<DbcLibrary> {
+        self.buses.remove(channel)
+    }
+
+    /// Sets the library used for channels without a library of their own, returns the library
+    /// previously set
+    pub fn set_fallback(&mut self, lib: DbcLibrary) -> Option<DbcLibrary> {
+        self.fallback.replace(lib)
+    }
+
+    /// Returns the library of a channel, or the fallback library if the channel has none
+    pub fn get(&self, channel: &Channel) -> Option<&DbcLibrary> {
+        self.buses.get(channel).or(self.fallback.as_ref())
+    }
+
+    /// Returns the channels with a library of their own, ordered by channel
+    pub fn channels(&self) -> Vec<&Channel> {
+        let mut channels: Vec<&Channel> = self.buses.keys().collect();
+        channels.sort();
+        channels
+    }
+
+    /// Returns how many channels have a library of their own
+    pub fn len(&self) -> usize {
+        self.buses.len()
+    }
+
+    /// Returns true if no channel has a library of its own
+    pub fn is_empty(&self) -> bool {
+        self.buses.is_empty()
+    }
+
+    /// Query the definition of a frame received on a channel
+    pub fn get_frame(&self, channel: &Channel, id: CanId) -> Option<&DbcFrame> {
+        self.get(channel)?.get_frame_by_id(id)
+    }
+
+    /// Decodes all signals of a frame received on a channel with the library of the channel
+    ///
+    /// Returns `None` if there is no library for the channel, it does not contain the ID or the
+    /// message is empty
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::bus::BusLibrary;
+    /// use fastcan::dbc::DbcLibrary;
+    /// use fastcan::id::CanId;
+    /// use fastcan::trace::Channel;
+    ///
+    /// let mut buses = BusLibrary::new();
+    /// buses.insert(1, DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap());
+    ///
+    /// let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    /// let id = CanId::Extended(0x0CF00400);
+    ///
+    /// let decoded = buses.decode(&Channel::Index(1), 0.0, id, &payload).unwrap();
+    /// assert_eq!(decoded.signals["Engine_Speed"], 2728.5);
+    /// assert!(buses.decode(&Channel::Index(2), 0.0, id, &payload).is_none());
+    /// ```
+    pub fn decode(
+        &self,
+        channel: &Channel,
+        timestamp: f64,
+        id: CanId,
+        msg: &[u8],
+    ) -> Option<DecodedFrame<'_>> {
+        self.get(channel)?.decode(timestamp, id, msg)
+    }
+
+    /// Decodes a stream of trace frames with the libraries of their channels into the channel
+    /// and timestamped signal values.  Remote and error frames as well as frames of channels or
+    /// IDs without definition are skipped, read errors are passed on.
+    pub fn decode_trace<I>(&self, frames: I) -> DecodeBusTrace<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = io::Result<TraceFrame>>,
+    {
+        DecodeBusTrace {
+            buses: self,
+            frames: frames.into_iter(),
+        }
+    }
+}
+
+/// Iterator returned by `BusLibrary::decode_trace`
+pub struct DecodeBusTrace<'a, I> {
+    buses: &'a BusLibrary,
+    frames: I,
+}
+
+impl<'a, I> Iterator for DecodeBusTrace<'a, I>
+where
+    I: Iterator<Item = io::Result<TraceFrame>>,
+{
+    type Item = io::Result<(Channel, DecodedFrame<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = match self.frames.next()? {
+                Ok(frame) => frame,
+                Err(e) => return Some(Err(e)),
+            };
+            if !frame.is_data() {
+                continue;
+            }
+            let decoded = self
+                .buses
+                .decode(&frame.channel, frame.timestamp, frame.id, &frame.data);
+            if let Some(decoded) = decoded {
+                return Some(Ok((frame.channel, decoded)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POWERTRAIN: &str = r#"
+BO_ 256 Engine : 2 Engine
+ SG_ Speed : 0|16@1+ (0.25,0) [0|16383.75] "rpm" Dashboard
+"#;
+
+    const BODY: &str = r#"
+BO_ 256 Doors : 1 Body
+ SG_ Open : 0|1@1+ (1,0) [0|1] "" Dashboard
+"#;
+
+    fn buses() -> BusLibrary {
+        let mut buses = BusLibrary::new();
+        buses.insert("can0", DbcLibrary::from_dbc_str(POWERTRAIN));
+        buses.insert(2, DbcLibrary::from_dbc_str(BODY));
+        buses
+    }
+
+    #[test]
+    fn test_decode_by_channel() {
+        let buses = buses();
+        let id = CanId::Standard(0x100);
+
+        let engine = buses
+            .decode(&Channel::from("can0"), 0.0, id, &[0x10, 0x00])
+            .unwrap();
+        assert_eq!(engine.frame.get_name(), "Engine");
+        assert_eq!(engine.signals["Speed"], 4.0);
+
+        let doors = buses.decode(&Channel::Index(2), 0.0, id, &[0x01]).unwrap();
+        assert_eq!(doors.frame.get_name(), "Doors");
+        assert_eq!(doors.signals["Open"], 1.0);
+
+        assert!(buses.decode(&Channel::Index(1), 0.0, id, &[0x01]).is_none());
+        assert_eq!(
+            buses.channels(),
+            vec![&Channel::Index(2), &Channel::Name("can0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_fallback() {
+        let mut buses = buses();
+        buses.set_fallback(DbcLibrary::from_dbc_str(BODY));
+
+        let id = CanId::Standard(0x100);
+        let frame = buses.get_frame(&Channel::Index(1), id).unwrap();
+        assert_eq!(frame.get_name(), "Doors");
+        let frame = buses.get_frame(&Channel::from("can0"), id).unwrap();
+        assert_eq!(frame.get_name(), "Engine");
+    }
+
+    #[test]
+    fn test_decode_trace() {
+        let buses = buses();
+        let id = CanId::Standard(0x100);
+        let frames = vec![
+            Ok(TraceFrame::new(
+                0.0,
+                Channel::from("can0"),
+                id,
+                vec![0x10, 0x00],
+            )),
+            Ok(TraceFrame::new(0.1, Channel::Index(2), id, vec![0x01])),
+            Ok(TraceFrame::new(0.2, Channel::Index(3), id, vec![0x01])),
+        ];
+
+        let decoded: Vec<(Channel, String)> = buses
+            .decode_trace(frames)
+            .map(|decoded| {
+                let (channel, decoded) = decoded.unwrap();
+                (channel, decoded.frame.get_name().to_string())
+            })
+            .collect();
+        assert_eq!(
+            decoded,
+            vec![
+                (Channel::from("can0"), "Engine".to_string()),
+                (Channel::Index(2), "Doors".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Allocation free signal encoding and decoding
+//!
+//! This module is the core of the crate that is available without the `std` feature, e.g. on
+//! microcontrollers.  Signals are described by `SignalDescriptor`s, which can be built at compile
+//! time and kept in `static` tables, while payloads are plain byte slices.
+//!
+//! Bits are numbered like in the rest of the crate: little endian signals start at bit
+//! `start_bit % 8` of byte `start_bit / 8`, big endian signals treat the payload as one large
+//! integer in network byte order, so bit 0 is the least significant bit of the last byte of a
+//! frame of at least 8 bytes.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::codec::{FrameDescriptor, SignalDescriptor};
+//! use fastcan::id::CanId;
+//!
+//! static EEC1: FrameDescriptor<'static> = FrameDescriptor {
+//!     id: CanId::Extended(0x0CF00400),
+//!     len: 8,
+//!     signals: &[
+//!         SignalDescriptor::new("Engine_Speed", 24, 16, true, false, 0.125, 0.0),
+//!         SignalDescriptor::new("Engine_Temp", 8, 8, true, true, 1.0, -40.0),
+//!     ],
+//! };
+//!
+//! let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+//!
+//! let speed = EEC1.signal("Engine_Speed").unwrap();
+//! assert_eq!(speed.decode(&payload), Some(2728.5));
+//!
+//! let mut msg = [0u8; 8];
+//! for signal in EEC1.signals {
+//!     signal.encode(f64::from(signal.decode(&payload).unwrap()), &mut msg).unwrap();
+//! }
+//! assert_eq!(msg, [0x00, 0x22, 0x00, 0x44, 0x55, 0x00, 0x00, 0x00]);
+//! ```
+
+use core::fmt;
+
+use crate::id::CanId;
+
+/// Position, byte order and scaling of a signal within a CAN frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalDescriptor<'a> {
+    /// Signal name
+    pub name: &'a str,
+    /// Bit position in frame where the signal starts
+    pub start_bit: usize,
+    /// Length of the signal in bits, at most 64
+    pub bit_len: usize,
+    /// Flag for if the signal is little endian
+    pub little_endian: bool,
+    /// Flag for if the signal is signed
+    pub signed: bool,
+    /// Factor that has to be applied to retrieve the physical value of the signal
+    pub scale: f32,
+    /// Offset that has to be applied to retrieve the physical value of the signal
+    pub offset: f32,
+}
+
+/// Error returned when a value can not be represented by a signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfRange;
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value does not fit into the signal")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OutOfRange {}
+
+impl<'a> SignalDescriptor<'a> {
+    /// Creates a `SignalDescriptor`, usable in `const` and `static` items
+    pub const fn new(
+        name: &'a str,
+        start_bit: usize,
+        bit_len: usize,
+        little_endian: bool,
+        signed: bool,
+        scale: f32,
+        offset: f32,
+    ) -> Self {
+        SignalDescriptor {
+            name,
+            start_bit,
+            bit_len,
+            little_endian,
+            signed,
+            scale,
+            offset,
+        }
+    }
+
+    /// Extracts the raw bits of the signal, without sign extension, scale and offset.  Bits
+    /// outside of the message read as zero.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::codec::SignalDescriptor;
+    ///
+    /// let signal = SignalDescriptor::new("Engine_Speed", 24, 16, true, false, 0.125, 0.0);
+    ///
+    /// assert_eq!(signal.extract(&[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]), 0x5544);
+    /// ```
+    pub fn extract(&self, msg: &[u8]) -> u64 {
+        let len = msg.len().max(8);
+
+        (0..self.bit_len.min(64)).fold(0u64, |value, i| {
+            match self
+                .byte(len, self.start_bit + i)
+                .and_then(|byte| msg.get(byte))
+            {
+                Some(byte) => value | (u64::from((byte >> ((self.start_bit + i) % 8)) & 1) << i),
+                None => value,
+            }
+        })
+    }
+
+    /// Extracts the raw value of the signal, sign extended for signed signals
+    pub fn decode_raw(&self, msg: &[u8]) -> i64 {
+        let raw = self.extract(msg);
+
+        if self.signed && self.bit_len > 0 && self.bit_len < 64 {
+            let shift = 64 - self.bit_len;
+            ((raw << shift) as i64) >> shift
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Decodes the physical value of the signal
+    ///
+    /// Returns `None` if the message is empty
+    pub fn decode(&self, msg: &[u8]) -> Option<f32> {
+        if msg.is_empty() {
+            return None;
+        }
+
+        let raw = if self.signed {
+            self.decode_raw(msg) as f32
+        } else {
+            self.extract(msg) as f32
+        };
+
+        Some(raw * self.scale + self.offset)
+    }
+
+    /// Writes the raw bits of the signal into a message, leaving the other bits untouched.  Bits
+    /// outside of the message are dropped.
+    pub fn insert(&self, raw: u64, msg: &mut [u8]) {
+        let len = msg.len().max(8);
+
+        for i in 0..self.bit_len.min(64) {
+            let bit = self.start_bit + i;
+            if let Some(byte) = self.byte(len, bit).and_then(|byte| msg.get_mut(byte)) {
+                let mask = 1 << (bit % 8);
+                if (raw >> i) & 1 == 1 {
+                    *byte |= mask;
+                } else {
+                    *byte &= !mask;
+                }
+            }
+        }
+    }
+
+    /// Encodes a physical value into a message, rounded to the nearest raw value
+    ///
+    /// Returns `OutOfRange` if the raw value does not fit into the signal, the message is left
+    /// untouched in that case.
+    pub fn encode(&self, value: f64, msg: &mut [u8]) -> Result<(), OutOfRange> {
+        let raw = (value - f64::from(self.offset)) / f64::from(self.scale);
+        // `f64::round` is not available without `std`
+        let raw = if raw < 0.0 { raw - 0.5 } else { raw + 0.5 };
+
+        let bit_len = self.bit_len.min(64) as i32;
+        let (min, max) = if self.signed {
+            (-pow2(bit_len - 1), pow2(bit_len - 1))
+        } else {
+            (0.0, pow2(bit_len))
+        };
+        // `raw` is the rounded value plus a fraction, so the bounds are exclusive
+        if !(raw > min - 1.0 && raw < max) {
+            return Err(OutOfRange);
+        }
+
+        let raw = if raw < 0.0 {
+            raw as i64 as u64
+        } else {
+            raw as u64
+        };
+        self.insert(raw, msg);
+
+        Ok(())
+    }
+
+    /// Index of the byte holding a bit of a message of `len` bytes
+    pub(crate) fn byte(&self, len: usize, bit: usize) -> Option<usize> {
+        if self.little_endian {
+            Some(bit / 8)
+        } else {
+            (len - 1).checked_sub(bit / 8)
+        }
+    }
+}
+
+/// `2^exp` as float for exponents up to 64
+fn pow2(exp: i32) -> f64 {
+    (0..exp).fold(1.0, |value, _| value * 2.0)
+}
+
+/// ID, length and signals of a CAN frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameDescriptor<'a> {
+    /// CAN ID of the frame
+    pub id: CanId,
+    /// Length of the frame in bytes
+    pub len: usize,
+    /// Signals of the frame
+    pub signals: &'a [SignalDescriptor<'a>],
+}
+
+impl<'a> FrameDescriptor<'a> {
+    /// Looks up a signal by name
+    pub fn signal(&self, name: &str) -> Option<&'a SignalDescriptor<'a>> {
+        self.signals.iter().find(|signal| signal.name == name)
+    }
+
+    /// Decodes all signals of the frame into pairs of signal name and physical value
+    ///
+    /// Yields nothing if the message is empty
+    pub fn decode<'m>(&self, msg: &'m [u8]) -> impl Iterator<Item = (&'a str, f32)> + 'm
+    where
+        'a: 'm,
+    {
+        self.signals
+            .iter()
+            .filter_map(move |signal| signal.decode(msg).map(|value| (signal.name, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMP: SignalDescriptor<'static> =
+        SignalDescriptor::new("Temp", 24, 8, true, true, 0.5, -10.0);
+    const BIG_ENDIAN: SignalDescriptor<'static> =
+        SignalDescriptor::new("Torque", 40, 16, false, true, 1.0, 0.0);
+
+    #[test]
+    fn test_decode() {
+        let payload = [0x00, 0x00, 0x00, 0xFE, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(TEMP.extract(&payload), 0xFE);
+        assert_eq!(TEMP.decode_raw(&payload), -2);
+        assert_eq!(TEMP.decode(&payload), Some(-11.0));
+        assert_eq!(TEMP.decode(&[]), None);
+
+        let payload = [0x00, 0xFF, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(BIG_ENDIAN.extract(&payload), 0xFF38);
+        assert_eq!(BIG_ENDIAN.decode(&payload), Some(-200.0));
+        // short messages are numbered like 8 byte messages
+        assert_eq!(BIG_ENDIAN.extract(&payload[..3]), 0xFF38);
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut msg = [0xFF; 8];
+        TEMP.encode(-11.0, &mut msg).unwrap();
+        assert_eq!(msg, [0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF]);
+        TEMP.encode(0.2, &mut msg).unwrap();
+        assert_eq!(TEMP.decode(&msg), Some(0.0));
+
+        let mut msg = [0u8; 8];
+        BIG_ENDIAN.encode(-200.0, &mut msg).unwrap();
+        assert_eq!(msg, [0x00, 0xFF, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        assert_eq!(TEMP.encode(54.0, &mut msg), Err(OutOfRange));
+        assert_eq!(TEMP.encode(-75.0, &mut msg), Err(OutOfRange));
+        assert_eq!(TEMP.encode(f64::NAN, &mut msg), Err(OutOfRange));
+        let unsigned = SignalDescriptor::new("Count", 0, 4, true, false, 1.0, 0.0);
+        assert_eq!(unsigned.encode(-1.0, &mut msg), Err(OutOfRange));
+        assert_eq!(unsigned.encode(16.0, &mut msg), Err(OutOfRange));
+        assert_eq!(unsigned.encode(15.0, &mut msg), Ok(()));
+        assert_eq!(msg[0], 0x0F);
+    }
+
+    #[test]
+    fn test_frame() {
+        static SIGNALS: [SignalDescriptor<'static>; 2] = [TEMP, BIG_ENDIAN];
+        let frame = FrameDescriptor {
+            id: CanId::Standard(0x123),
+            len: 8,
+            signals: &SIGNALS,
+        };
+
+        assert_eq!(frame.signal("Torque"), Some(&BIG_ENDIAN));
+        assert_eq!(frame.signal("Speed"), None);
+
+        let payload = [0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x2A];
+        let mut values = frame.decode(&payload);
+        assert_eq!(values.next(), Some(("Temp", 0.0)));
+        assert_eq!(values.next(), Some(("Torque", 0.0)));
+        assert_eq!(values.next(), None);
+        assert_eq!(frame.decode(&[]).count(), 0);
+    }
+}
@@ -0,0 +1,703 @@
+//! Generation of typed frame structs from DBC files
+//!
+//! `generate` turns every frame of a `DbcLibrary` into a Rust struct with one field per signal,
+//! so signal names and types are checked at compile time instead of looked up in a `HashMap`.
+//! The generated module does not depend on this crate.
+//!
+//! For every frame it contains:
+//!
+//! * a struct named after the frame in `CamelCase`, with the signals as `snake_case` fields,
+//! * the constants `ID` (the ID as seen on the bus), `EXTENDED` and `LEN` (frame length in
+//!   bytes),
+//! * `decode(&[u8]) -> Option<Self>`, which fails on payloads shorter than `LEN`,
+//! * `encode(&self) -> [u8; LEN]`.
+//!
+//! Signals with a scale of 1 and an offset of 0 are stored as the smallest integer type that
+//! holds them, one bit unsigned signals as `bool`, all others as physical `f64` values.
+//! Signals with a value table get an enum with a variant per label and a `Value` variant for
+//! all other values.  Multiplexed signals are `Option`s that are `None` if the multiplexor
+//! selects another signal group.  Signals are laid out like `DbcSignal::decode_message` does,
+//! with the bytes of big endian signals numbered from the end of a frame of at least 8 bytes.
+//! Items are marked `#[allow(dead_code)]`, as most users only need some of the frames.
+//!
+//! # Example
+//!
+//! The module is usually generated by a build script:
+//!
+//! ```rust,no_run
+//! // build.rs
+//! use std::env;
+//! use std::path::Path;
+//!
+//! fn main() {
+//!     let out = Path::new(&env::var("OUT_DIR").unwrap()).join("frames.rs");
+//!     fastcan::codegen::generate_file("powertrain.dbc", out).unwrap();
+//!     println!("cargo:rerun-if-changed=powertrain.dbc");
+//! }
+//! ```
+//!
+//! and included with
+//!
+//! ```rust,ignore
+//! mod frames {
+//!     include!(concat!(env!("OUT_DIR"), "/frames.rs"));
+//! }
+//!
+//! let eec1 = frames::Eec1::decode(&payload).unwrap();
+//! ```
+//!
+//! Without a build script, `fastcan::dbc!("powertrain.dbc")` expands to the same module.  The
+//! macro is re-exported from the `fastcan-macros` crate with the `macros` feature.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, Multiplexing};
+
+/// Generates a Rust module with a struct for every frame of the library
+///
+/// # Example
+///
+/// ```rust
+/// use fastcan::codegen::generate;
+/// use fastcan::dbc::DbcLibrary;
+///
+/// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+/// let code = generate(&lib);
+///
+/// assert!(code.contains("pub struct Eec1 {"));
+/// assert!(code.contains("pub engine_speed: Eec1EngineSpeed,"));
+/// ```
+pub fn generate(lib: &DbcLibrary) -> String {
+    let mut code = String::new();
+    write_module(&mut code, lib).expect("writing to a String does not fail");
+    code
+}
+
+/// Reads a DBC file and writes the module generated from it, for use in build scripts
+pub fn generate_file<P, Q>(dbc: P, out: Q) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let lib = DbcLibrary::from_dbc_file(dbc)?;
+    fs::write(out, generate(&lib))
+}
+
+/// Rust type of a signal field
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Type {
+    Bool,
+    Unsigned(usize),
+    Signed(usize),
+    Float,
+}
+
+impl Type {
+    fn of(signal: &DbcSignalDefinition) -> Self {
+        if signal.scale != 1.0 || signal.offset != 0.0 {
+            return Type::Float;
+        }
+        let bits = [8, 16, 32, 64]
+            .iter()
+            .cloned()
+            .find(|bits| *bits >= signal.bit_len)
+            .unwrap_or(64);
+        match (signal.signed, signal.bit_len) {
+            (false, 1) => Type::Bool,
+            (false, _) => Type::Unsigned(bits),
+            (true, _) => Type::Signed(bits),
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Type::Bool => "bool".to_string(),
+            Type::Unsigned(bits) => format!("u{}", bits),
+            Type::Signed(bits) => format!("i{}", bits),
+            Type::Float => "f64".to_string(),
+        }
+    }
+}
+
+/// A signal with the names and types used in the generated code
+struct Field<'a> {
+    definition: &'a DbcSignalDefinition,
+    signal: &'a DbcSignal,
+    name: String,
+    ty: Type,
+    /// Enum of the value table and its variants with their raw values
+    labels: Option<(String, Vec<(String, i64)>)>,
+    /// Raw value of the multiplexor selecting the signal
+    multiplexed: Option<u32>,
+}
+
+impl Field<'_> {
+    fn type_name(&self) -> String {
+        let name = match self.labels {
+            Some((ref name, _)) => name.clone(),
+            None => self.ty.name(),
+        };
+        match self.multiplexed {
+            Some(_) => format!("Option<{}>", name),
+            None => name,
+        }
+    }
+
+    /// Expression extracting the raw value from `data` as `u64`
+    fn extract(&self) -> String {
+        let signal = self.definition;
+        format!(
+            "extract(data, {}, {}, {})",
+            signal.start_bit,
+            signal.bit_len.min(64),
+            signal.little_endian
+        )
+    }
+
+    /// Expression converting the raw `u64` value `raw` into the field type
+    fn value(&self) -> String {
+        let signal = self.definition;
+        let signed = format!("sign_extend(raw, {})", signal.bit_len.min(64));
+        match self.ty {
+            Type::Bool => "raw != 0".to_string(),
+            Type::Unsigned(64) => "raw".to_string(),
+            Type::Unsigned(bits) => format!("raw as u{}", bits),
+            Type::Signed(64) => signed,
+            Type::Signed(bits) => format!("{} as i{}", signed, bits),
+            Type::Float => {
+                let mut expr = if signal.signed {
+                    format!("{} as f64", signed)
+                } else {
+                    "raw as f64".to_string()
+                };
+                if signal.scale != 1.0 {
+                    expr = format!("{} * {}", expr, float(signal.scale));
+                }
+                if signal.offset < 0.0 {
+                    expr = format!("{} - {}", expr, float(-signal.offset));
+                } else if signal.offset > 0.0 {
+                    expr = format!("{} + {}", expr, float(signal.offset));
+                }
+                expr
+            }
+        }
+    }
+
+    /// Expression converting `value` of the field type into the raw value as `u64`
+    fn raw(&self, value: &str) -> String {
+        let signal = self.definition;
+        match self.ty {
+            Type::Bool | Type::Unsigned(8) | Type::Unsigned(16) | Type::Unsigned(32) => {
+                format!("u64::from({})", value)
+            }
+            Type::Unsigned(_) => value.to_string(),
+            Type::Signed(_) => format!("{} as u64", value),
+            Type::Float => {
+                let mut expr = value.to_string();
+                if signal.offset < 0.0 {
+                    expr = format!("({} + {})", expr, float(-signal.offset));
+                } else if signal.offset > 0.0 {
+                    expr = format!("({} - {})", expr, float(signal.offset));
+                }
+                if signal.scale != 1.0 {
+                    expr = format!("({} / {})", expr, float(signal.scale));
+                }
+                format!("{}.round() as i64 as u64", expr)
+            }
+        }
+    }
+}
+
+fn write_module(out: &mut String, lib: &DbcLibrary) -> fmt::Result {
+    let mut frames: Vec<&DbcFrame> = lib
+        .get_frames()
+        .into_iter()
+        .filter(|frame| !frame.get_name().is_empty())
+        .collect();
+    frames.sort_by_key(|frame| frame.get_can_id());
+
+    writeln!(out, "// Generated by fastcan::codegen, do not edit.")?;
+
+    let mut types = HashSet::new();
+    for frame in frames {
+        writeln!(out)?;
+        write_frame(out, frame, &mut types)?;
+    }
+
+    out.push_str(HELPERS);
+    Ok(())
+}
+
+fn write_frame(out: &mut String, frame: &DbcFrame, types: &mut HashSet<String>) -> fmt::Result {
+    let name = unique(camel_case(frame.get_name()), types);
+    let len = frame.get_message_len();
+    let fields = fields(frame, &name, types);
+    let multiplexor = fields
+        .iter()
+        .find(|field| field.definition.multiplexing == Multiplexing::Multiplexor);
+
+    for field in &fields {
+        if let Some((ref enum_name, ref variants)) = field.labels {
+            write_enum(out, field, enum_name, variants)?;
+            writeln!(out)?;
+        }
+    }
+
+    writeln!(
+        out,
+        "/// `{}`, ID 0x{}",
+        frame.get_name(),
+        frame.get_can_id()
+    )?;
+    if let Some(description) = frame.get_description() {
+        writeln!(out, "///")?;
+        write_doc(out, "", description)?;
+    }
+    writeln!(out, "#[allow(dead_code)]")?;
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq)]")?;
+    writeln!(out, "pub struct {} {{", name)?;
+    for field in &fields {
+        let signal = field.definition;
+        let unit = if signal.units.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", signal.units)
+        };
+        writeln!(
+            out,
+            "    /// `{}`, {} to {}{}",
+            signal.name,
+            range_value(signal.min_value),
+            range_value(signal.max_value),
+            unit
+        )?;
+        if let Some(description) = field.signal.get_description() {
+            writeln!(out, "    ///")?;
+            write_doc(out, "    ", description)?;
+        }
+        writeln!(out, "    pub {}: {},", field.name, field.type_name())?;
+    }
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+
+    let id = frame.get_can_id();
+    writeln!(out, "#[allow(dead_code)]")?;
+    writeln!(out, "impl {} {{", name)?;
+    writeln!(out, "    /// ID of the frame as seen on the bus")?;
+    writeln!(out, "    pub const ID: u32 = 0x{};", id)?;
+    writeln!(out, "    /// Whether the frame has an extended ID")?;
+    writeln!(out, "    pub const EXTENDED: bool = {};", id.is_extended())?;
+    writeln!(out, "    /// Length of the frame in bytes")?;
+    writeln!(out, "    pub const LEN: usize = {};", len)?;
+    writeln!(out)?;
+
+    writeln!(
+        out,
+        "    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`"
+    )?;
+    writeln!(out, "    pub fn decode(data: &[u8]) -> Option<Self> {{")?;
+    writeln!(out, "        if data.len() < Self::LEN {{")?;
+    writeln!(out, "            return None;")?;
+    writeln!(out, "        }}")?;
+    if !fields.is_empty() {
+        writeln!(out, "        let data = &data[..Self::LEN];")?;
+    }
+    if let Some(multiplexor) = multiplexor {
+        writeln!(out, "        let mux = {};", multiplexor.extract())?;
+    }
+    writeln!(out, "        Some({} {{", name)?;
+    for field in &fields {
+        let value = decode_value(field);
+        match field.multiplexed {
+            Some(mux) => writeln!(
+                out,
+                "            {}: if mux == {} {{\n                let raw = {};\n                Some({})\n            }} else {{\n                None\n            }},",
+                field.name,
+                mux,
+                field.extract(),
+                value
+            )?,
+            _ => writeln!(
+                out,
+                "            {}: {{\n                let raw = {};\n                {}\n            }},",
+                field.name,
+                field.extract(),
+                value
+            )?,
+        }
+    }
+    writeln!(out, "        }})")?;
+    writeln!(out, "    }}")?;
+    writeln!(out)?;
+
+    writeln!(out, "    /// Encodes the frame")?;
+    writeln!(out, "    pub fn encode(&self) -> [u8; {}] {{", len)?;
+    if fields.is_empty() {
+        writeln!(out, "        [0; {}]", len)?;
+    } else {
+        writeln!(out, "        let mut data = [0; {}];", len)?;
+        for field in &fields {
+            let signal = field.definition;
+            let multiplexed = field.multiplexed.is_some();
+            let (value, indent) = if multiplexed {
+                writeln!(out, "        if let Some(value) = self.{} {{", field.name)?;
+                ("value".to_string(), "            ")
+            } else {
+                (format!("self.{}", field.name), "        ")
+            };
+            let raw = write_raw(out, indent, field, &value)?;
+            writeln!(
+                out,
+                "{}insert(&mut data, {}, {}, {}, {});",
+                indent,
+                signal.start_bit,
+                signal.bit_len.min(64),
+                signal.little_endian,
+                raw
+            )?;
+            if multiplexed {
+                writeln!(out, "        }}")?;
+            }
+        }
+        writeln!(out, "        data")?;
+    }
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")
+}
+
+fn write_enum(
+    out: &mut String,
+    field: &Field<'_>,
+    name: &str,
+    variants: &[(String, i64)],
+) -> fmt::Result {
+    writeln!(out, "/// Values of `{}`", field.definition.name)?;
+    writeln!(out, "#[allow(dead_code)]")?;
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq)]")?;
+    writeln!(out, "pub enum {} {{", name)?;
+    for (variant, raw) in variants {
+        writeln!(out, "    /// Raw value {}", raw)?;
+        writeln!(out, "    {},", variant)?;
+    }
+    writeln!(out, "    /// Value without label")?;
+    writeln!(out, "    Value({}),", field.ty.name())?;
+    writeln!(out, "}}")
+}
+
+/// Expression converting `raw` into the field value, without the `Option` of multiplexed fields
+fn decode_value(field: &Field<'_>) -> String {
+    let (name, variants) = match field.labels {
+        Some((ref name, ref variants)) => (name, variants),
+        None => return field.value(),
+    };
+
+    let raw = if field.definition.signed {
+        format!("sign_extend(raw, {})", field.definition.bit_len.min(64))
+    } else {
+        "raw as i64".to_string()
+    };
+    let mut expr = format!("match {} {{\n", raw);
+    for (variant, value) in variants {
+        expr += &format!("                    {} => {}::{},\n", value, name, variant);
+    }
+    expr += &format!(
+        "                    _ => {}::Value({}),\n                }}",
+        name,
+        field.value()
+    );
+    expr
+}
+
+/// Returns the expression converting `value` of the field into the raw `u64` value, value
+/// tables are converted into a `raw` variable first
+fn write_raw(
+    out: &mut String,
+    indent: &str,
+    field: &Field<'_>,
+    value: &str,
+) -> Result<String, fmt::Error> {
+    let (name, variants) = match field.labels {
+        Some((ref name, ref variants)) => (name, variants),
+        None => return Ok(field.raw(value)),
+    };
+
+    writeln!(out, "{}let raw = match {} {{", indent, value)?;
+    for (variant, raw) in variants {
+        if *raw < 0 {
+            writeln!(
+                out,
+                "{}    {}::{} => {}_i64 as u64,",
+                indent, name, variant, raw
+            )?;
+        } else {
+            writeln!(out, "{}    {}::{} => {},", indent, name, variant, raw)?;
+        }
+    }
+    writeln!(
+        out,
+        "{}    {}::Value(value) => {},",
+        indent,
+        name,
+        field.raw("value")
+    )?;
+    writeln!(out, "{}}};", indent)?;
+    Ok("raw".to_string())
+}
+
+/// Collects the signals of a frame ordered by start bit
+fn fields<'a>(
+    frame: &'a DbcFrame,
+    frame_name: &str,
+    types: &mut HashSet<String>,
+) -> Vec<Field<'a>> {
+    let mut signals: Vec<(&DbcSignalDefinition, &DbcSignal)> = frame
+        .signal_map()
+        .values()
+        .filter_map(|signal| signal.definition().map(|definition| (definition, signal)))
+        .filter(|(definition, _)| definition.bit_len > 0)
+        .collect();
+    signals.sort_by(|a, b| (a.0.start_bit, &a.0.name).cmp(&(b.0.start_bit, &b.0.name)));
+
+    let has_multiplexor = signals
+        .iter()
+        .any(|(definition, _)| definition.multiplexing == Multiplexing::Multiplexor);
+
+    let mut names = HashSet::new();
+    signals
+        .into_iter()
+        .map(|(definition, signal)| {
+            let labels = signal.get_value_definition().map(|values| {
+                let enum_name = unique(
+                    format!("{}{}", frame_name, camel_case(&definition.name)),
+                    types,
+                );
+                let mut variants_names = HashSet::new();
+                variants_names.insert("Value".to_string());
+                let mut values = values.get_values().to_vec();
+                values.sort();
+                values.dedup_by_key(|(value, _)| *value);
+                let variants = values
+                    .into_iter()
+                    .map(|(value, label)| (unique(camel_case(&label), &mut variants_names), value))
+                    .collect();
+                (enum_name, variants)
+            });
+
+            let multiplexed = match definition.multiplexing {
+                Multiplexing::Multiplexed(value) if has_multiplexor => Some(value),
+                _ => None,
+            };
+
+            Field {
+                definition,
+                signal,
+                name: unique(snake_case(&definition.name), &mut names),
+                ty: Type::of(definition),
+                labels,
+                multiplexed,
+            }
+        })
+        .collect()
+}
+
+fn write_doc(out: &mut String, indent: &str, text: &str) -> fmt::Result {
+    for line in text.lines() {
+        writeln!(out, "{}/// {}", indent, line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Formats the `f32` of a DBC file as the shortest `f64` literal with the same decimal digits
+fn float(value: f32) -> String {
+    let value: f64 = value
+        .to_string()
+        .parse()
+        .unwrap_or_else(|_| f64::from(value));
+    format!("{:?}", value)
+}
+
+/// Formats a range limit, integers from `f64` as the shortest `f32` representation of large ones
+/// is rounded to its significant digits, such as 4294967300
+fn range_value(value: f32) -> String {
+    if value.fract() == 0.0 {
+        f64::from(value).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Appends a number to names that are already taken
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut index = 2;
+    while taken.contains(&unique) {
+        unique = format!("{}{}", name, index);
+        index += 1;
+    }
+    taken.insert(unique.clone());
+    unique
+}
+
+/// Converts a name like `EEC1`, `Engine_Speed` or `not available` into `Eec1`, `EngineSpeed` and
+/// `NotAvailable`
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.push(first.to_ascii_uppercase());
+            if word.chars().any(|c| c.is_ascii_lowercase()) {
+                camel.extend(chars);
+            } else {
+                camel.extend(chars.map(|c| c.to_ascii_lowercase()));
+            }
+        }
+    }
+
+    match camel.chars().next() {
+        None => "Unnamed".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("V{}", camel),
+        Some(_) => camel,
+    }
+}
+
+/// Converts a name like `Engine_Speed`, `EngineSpeed` or `ABSActive` into `engine_speed`,
+/// `engine_speed` and `abs_active`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            snake.push('_');
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            if previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+
+    let snake = snake
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    match snake.chars().next() {
+        None => "signal".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("_{}", snake),
+        Some(_) if KEYWORDS.contains(&snake.as_str()) => format!("{}_", snake),
+        Some(_) => snake,
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Bit access functions of the generated module, numbered like `DbcSignal::decode_message`: big
+/// endian bytes count from the end of the payload or from byte 7 of shorter payloads
+const HELPERS: &str = r#"
+/// Reads `len` bits starting at `start`
+#[allow(dead_code)]
+fn extract(data: &[u8], start: usize, len: usize, little_endian: bool) -> u64 {
+    (0..len).fold(0, |value, i| {
+        let bit = start + i;
+        let byte = if little_endian {
+            Some(bit / 8)
+        } else {
+            (data.len().max(8) - 1).checked_sub(bit / 8)
+        };
+        match byte.and_then(|byte| data.get(byte)) {
+            Some(byte) => value | (u64::from((byte >> (bit % 8)) & 1) << i),
+            None => value,
+        }
+    })
+}
+
+/// Writes the lower `len` bits of `value` starting at `start`
+#[allow(dead_code)]
+fn insert(data: &mut [u8], start: usize, len: usize, little_endian: bool, value: u64) {
+    let last = data.len().max(8) - 1;
+    for i in 0..len {
+        let bit = start + i;
+        let byte = if little_endian {
+            Some(bit / 8)
+        } else {
+            last.checked_sub(bit / 8)
+        };
+        if let Some(byte) = byte.and_then(|byte| data.get_mut(byte)) {
+            if (value >> i) & 1 == 1 {
+                *byte |= 1 << (bit % 8);
+            }
+        }
+    }
+}
+
+/// Interprets the lower `len` bits of `raw` as two's complement number
+#[allow(dead_code)]
+fn sign_extend(raw: u64, len: usize) -> i64 {
+    let shift = 64 - len;
+    ((raw << shift) as i64) >> shift
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camel_case() {
+        assert_eq!(camel_case("EEC1"), "Eec1");
+        assert_eq!(camel_case("Engine_Speed"), "EngineSpeed");
+        assert_eq!(camel_case("not available"), "NotAvailable");
+        assert_eq!(camel_case("VehicleSpeed"), "VehicleSpeed");
+        assert_eq!(camel_case("0-10 km/h"), "V010KmH");
+        assert_eq!(camel_case("--"), "Unnamed");
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("Engine_Speed"), "engine_speed");
+        assert_eq!(snake_case("EngineSpeed"), "engine_speed");
+        assert_eq!(snake_case("ABSActive"), "abs_active");
+        assert_eq!(snake_case("Wheel2Speed"), "wheel2_speed");
+        assert_eq!(snake_case("Type"), "type_");
+        assert_eq!(snake_case("1st_Gear"), "_1st_gear");
+    }
+
+    #[test]
+    fn test_field_types() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 Status : 8 Engine
+ SG_ Running : 0|1@1+ (1,0) [0|1] "" Dashboard
+ SG_ Gear : 1|4@1- (1,0) [-8|7] "" Dashboard
+ SG_ Odometer : 8|40@1+ (1,0) [0|0] "km" Dashboard
+ SG_ Temp : 48|8@1+ (1,-40) [-40|215] "degC" Dashboard"#,
+        );
+        let code = generate(&lib);
+
+        assert!(code.contains("    pub running: bool,\n"));
+        assert!(code.contains("    pub gear: i8,\n"));
+        assert!(code.contains("    pub odometer: u64,\n"));
+        assert!(code.contains("    pub temp: f64,\n"));
+        assert!(code.contains("raw as f64 - 40.0"));
+        assert!(code.contains("(self.temp + 40.0).round() as i64 as u64"));
+    }
+}
@@ -0,0 +1,521 @@
+//! Binary cache of parsed DBC files
+//!
+//! Parsing large DBC files line by line is slow compared to reading back a library that was
+//! parsed before.  `DbcLibrary::to_cache` serializes a library into a compact binary format,
+//! `DbcLibrary::from_cache` reads it from a byte slice, e.g. a memory-mapped file.  Every cache
+//! starts with a magic number, the format version, the version of this crate and a hash of the
+//! DBC source, so caches of other versions, of libraries read by another parser or of changed
+//! DBC files are rejected.
+//!
+//! The layout is little endian: the magic `FCANDBC\0`, the format version as `u32`, the crate
+//! version as string, the source hash as `u64`, the nodes, the frames sorted by ID, the frame IDs
+//! redefined in the DBC file and the signals that entries refer to in frames without definition.
+//! Strings are stored as `u32` length and UTF-8 bytes, lists as `u32` length and elements,
+//! optional values as a `u8` flag and the value.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+use encoding::all::ISO_8859_1;
+use encoding::{DecoderTrap, Encoding};
+
+use super::{
+    invalid, DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, Multiplexing, ValueDefinition,
+};
+use crate::id::CanId;
+
+const MAGIC: &[u8; 8] = b"FCANDBC\0";
+
+/// Version of the crate writing the cache, as the parser may read the same DBC file differently
+/// in other versions
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the cache format, increased with every change of the layout
+pub const CACHE_VERSION: u32 = 3;
+
+impl DbcLibrary {
+    /// Hashes the contents of a DBC file for `to_cache` and `from_cache`.  The hash (64-bit
+    /// FNV-1a) is the same on all platforms and versions of the crate.
+    pub fn source_hash(data: &[u8]) -> u64 {
+        data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    /// Serializes the library into the binary cache format, tagged with the hash of the DBC
+    /// source it was parsed from
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let data = std::fs::read("./tests/data/sample.dbc").unwrap();
+    /// let hash = DbcLibrary::source_hash(&data);
+    ///
+    /// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    /// let cache = lib.to_cache(hash);
+    ///
+    /// let cached = DbcLibrary::from_cache(&cache, hash).unwrap();
+    /// assert!(cached.get_frame(2364539904).is_some());
+    /// assert!(DbcLibrary::from_cache(&cache, hash + 1).is_err());
+    /// ```
+    pub fn to_cache(&self, source_hash: u64) -> Vec<u8> {
+        let mut writer = Writer(Vec::new());
+        writer.0.extend_from_slice(MAGIC);
+        writer.u32(CACHE_VERSION);
+        writer.string(CRATE_VERSION);
+        writer.u64(source_hash);
+
+        writer.list(self.get_nodes(), |writer, node| writer.string(node));
+
+        let mut frames = self.get_frames();
+        frames.sort_by_key(|frame| frame.get_can_id());
+        writer.list(&frames, |writer, frame| writer.frame(frame));
+
+        writer.list(self.redefined_frames(), |writer, (id, name)| {
+            writer.id(*id);
+            writer.string(name);
+        });
+        writer.list(self.dangling_signals(), |writer, (id, name)| {
+            writer.id(*id);
+            writer.string(name);
+        });
+
+        writer.0
+    }
+
+    /// Reads a library from the binary cache format
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the data is not a cache, was written by
+    /// another version of the format or of this crate, for another source hash or is truncated.
+    pub fn from_cache(data: &[u8], source_hash: u64) -> io::Result<Self> {
+        let mut reader = Reader(data);
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a DBC cache"));
+        }
+        let version = reader.u32()?;
+        if version != CACHE_VERSION {
+            return Err(invalid(format!("unsupported cache version {}", version)));
+        }
+        let crate_version = reader.string()?;
+        if crate_version != CRATE_VERSION {
+            return Err(invalid(format!(
+                "cache written by fastcan {}",
+                crate_version
+            )));
+        }
+        if reader.u64()? != source_hash {
+            return Err(invalid("cache of a different DBC source"));
+        }
+
+        let mut lib = DbcLibrary::default();
+        *lib.nodes_mut() = reader.list(Reader::string)?;
+        for frame in reader.list(Reader::frame)? {
+            lib.frames_mut().insert(frame.get_can_id(), frame);
+        }
+        *lib.redefined_frames_mut() = reader.list(|reader| Ok((reader.id()?, reader.string()?)))?;
+        *lib.dangling_signals_mut() = reader.list(|reader| Ok((reader.id()?, reader.string()?)))?;
+
+        if !reader.0.is_empty() {
+            return Err(invalid("trailing data after DBC cache"));
+        }
+
+        Ok(lib)
+    }
+
+    /// Loads a DBC file like `from_dbc_file`, using a cache file if it was written for the
+    /// current contents of the DBC file.  Otherwise the DBC file is parsed and the cache file
+    /// written, failing to write it is not an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_file_cached("powertrain.dbc", "powertrain.dbc.cache")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_dbc_file_cached<P, C>(path: P, cache: C) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        C: AsRef<Path>,
+    {
+        let mut contents = Vec::new();
+        File::open(path)?.read_to_end(&mut contents)?;
+        let hash = Self::source_hash(&contents);
+
+        if let Ok(lib) = fs::read(&cache).and_then(|data| Self::from_cache(&data, hash)) {
+            return Ok(lib);
+        }
+
+        let data = ISO_8859_1
+            .decode(&contents, DecoderTrap::Replace)
+            .map_err(io::Error::other)?;
+        let lib = Self::from_dbc_str(&data);
+
+        // written next to the cache and renamed, so readers never see a partial cache
+        let cache = cache.as_ref();
+        let mut partial = cache.as_os_str().to_owned();
+        partial.push(".partial");
+        if fs::write(&partial, lib.to_cache(hash)).is_ok() && fs::rename(&partial, cache).is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+
+        Ok(lib)
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                write(self, value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn list<T>(&mut self, values: &[T], mut write: impl FnMut(&mut Self, &T)) {
+        self.u32(values.len() as u32);
+        for value in values {
+            write(self, value);
+        }
+    }
+
+    fn attributes(&mut self, attributes: &HashMap<String, String>) {
+        let mut attributes: Vec<_> = attributes.iter().collect();
+        attributes.sort();
+        self.list(&attributes, |writer, (name, value)| {
+            writer.string(name);
+            writer.string(value);
+        });
+    }
+
+    fn id(&mut self, id: CanId) {
+        self.u32(id.to_dbc());
+    }
+
+    fn frame(&mut self, frame: &DbcFrame) {
+        self.string(frame.get_name());
+        self.id(frame.get_can_id());
+        self.u32(frame.get_message_len());
+        self.string(frame.get_sending_node());
+        self.option(frame.get_description(), Self::string);
+        self.attributes(frame.get_attributes());
+
+        let mut signals: Vec<(&String, &DbcSignal)> = frame.signal_map().iter().collect();
+        signals.sort_by_key(|(name, _)| *name);
+        self.list(&signals, |writer, (name, signal)| {
+            writer.string(name);
+            writer.signal(signal);
+        });
+        self.list(frame.redefined_signals(), |writer, name| {
+            writer.string(name)
+        });
+    }
+
+    fn signal(&mut self, signal: &DbcSignal) {
+        self.option(signal.definition(), Self::definition);
+        self.option(signal.get_description(), Self::string);
+        self.attributes(signal.get_attributes());
+        self.option(signal.get_value_definition(), |writer, values| {
+            writer.list(values.get_values(), |writer, (value, label)| {
+                writer.u64(*value as u64);
+                writer.string(label);
+            })
+        });
+    }
+
+    fn definition(&mut self, definition: &DbcSignalDefinition) {
+        self.string(&definition.name);
+        self.u32(definition.start_bit as u32);
+        self.u32(definition.bit_len as u32);
+        self.u8(u8::from(definition.little_endian) | u8::from(definition.signed) << 1);
+        self.f32(definition.scale);
+        self.f32(definition.offset);
+        self.f32(definition.min_value);
+        self.f32(definition.max_value);
+        self.string(&definition.units);
+        self.string(&definition.receiving_node);
+        match definition.multiplexing {
+            Multiplexing::None => self.u8(0),
+            Multiplexing::Multiplexor => self.u8(1),
+            Multiplexing::Multiplexed(value) => {
+                self.u8(2);
+                self.u32(value);
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid("truncated DBC cache"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(LittleEndian::read_u32(self.bytes(4)?))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(LittleEndian::read_u64(self.bytes(8)?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(LittleEndian::read_f32(self.bytes(4)?))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(invalid)
+    }
+
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> io::Result<T>,
+    ) -> io::Result<Option<T>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => read(self).map(Some),
+            flag => Err(invalid(format!("invalid option flag {}", flag))),
+        }
+    }
+
+    fn list<T>(&mut self, mut read: impl FnMut(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let len = self.u32()? as usize;
+        // every element takes at least one byte, which bounds the allocation for corrupt data
+        let mut values = Vec::with_capacity(len.min(self.0.len()));
+        for _ in 0..len {
+            values.push(read(self)?);
+        }
+        Ok(values)
+    }
+
+    fn attributes(&mut self) -> io::Result<HashMap<String, String>> {
+        Ok(self
+            .list(|reader| Ok((reader.string()?, reader.string()?)))?
+            .into_iter()
+            .collect())
+    }
+
+    fn id(&mut self) -> io::Result<CanId> {
+        Ok(CanId::from_dbc(self.u32()?))
+    }
+
+    fn frame(&mut self) -> io::Result<DbcFrame> {
+        let name = self.string()?;
+        let id = self.id()?;
+        let message_len = self.u32()?;
+        let sending_node = self.string()?;
+        let description = self.option(Self::string)?;
+        let attributes = self.attributes()?;
+        let signals = self
+            .list(|reader| Ok((reader.string()?, reader.signal()?)))?
+            .into_iter()
+            .collect();
+
+        let mut frame = DbcFrame::new(
+            name,
+            id.to_dbc(),
+            message_len,
+            sending_node,
+            attributes,
+            description,
+            signals,
+        );
+        *frame.redefined_signals_mut() = self.list(Self::string)?;
+
+        Ok(frame)
+    }
+
+    fn signal(&mut self) -> io::Result<DbcSignal> {
+        let definition = self.option(Self::definition)?;
+        let description = self.option(Self::string)?;
+        let attributes = self.attributes()?;
+        let value_definition = self.option(|reader| {
+            let values = reader.list(|reader| Ok((reader.u64()? as i64, reader.string()?)))?;
+            Ok(ValueDefinition::new(values))
+        })?;
+
+        Ok(DbcSignal::new(
+            definition,
+            description,
+            attributes,
+            value_definition,
+        ))
+    }
+
+    fn definition(&mut self) -> io::Result<DbcSignalDefinition> {
+        let name = self.string()?;
+        let start_bit = self.u32()? as usize;
+        let bit_len = self.u32()? as usize;
+        let flags = self.u8()?;
+
+        Ok(DbcSignalDefinition {
+            name,
+            start_bit,
+            bit_len,
+            little_endian: flags & 1 != 0,
+            signed: flags & 2 != 0,
+            scale: self.f32()?,
+            offset: self.f32()?,
+            min_value: self.f32()?,
+            max_value: self.f32()?,
+            units: self.string()?,
+            receiving_node: self.string()?,
+            multiplexing: match self.u8()? {
+                0 => Multiplexing::None,
+                1 => Multiplexing::Multiplexor,
+                2 => Multiplexing::Multiplexed(self.u32()?),
+                tag => return Err(invalid(format!("invalid multiplexing {}", tag))),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same(lib: &DbcLibrary, cached: &DbcLibrary) {
+        assert!(lib.diff(cached).is_empty());
+        assert_eq!(cached.get_nodes(), lib.get_nodes());
+        assert_eq!(cached.lint(), lib.lint());
+        for frame in lib.get_frames() {
+            let copy = cached.get_frame_by_id(frame.get_can_id()).unwrap();
+            assert_eq!(copy.signal_map(), frame.signal_map());
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for file in &["sample", "ff", "j1939", "codegen"] {
+            let path = format!("./tests/data/{}.dbc", file);
+            let hash = DbcLibrary::source_hash(&fs::read(&path).unwrap());
+            let lib = DbcLibrary::from_dbc_file(&path).unwrap();
+
+            let cache = lib.to_cache(hash);
+            assert_eq!(cache, lib.to_cache(hash));
+            assert_same(&lib, &DbcLibrary::from_cache(&cache, hash).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_redefinitions() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"
+BO_ 100 Status: 1 Engine
+ SG_ Running : 0|1@1+ (1,0) [0|1] "" X
+ SG_ Running : 1|1@1+ (1,0) [0|1] "" X
+BO_ 100 State: 1 Engine
+CM_ SG_ 100 Missing "only referenced";
+CM_ SG_ 200 Missing "frame only referenced";
+"#,
+        );
+        let cached = DbcLibrary::from_cache(&lib.to_cache(0), 0).unwrap();
+
+        assert!(!lib.lint().is_empty());
+        assert_same(&lib, &cached);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let cache = lib.to_cache(42);
+
+        let error = |data: &[u8]| DbcLibrary::from_cache(data, 42).unwrap_err().to_string();
+        assert_eq!(error(b"VERSION"), "truncated DBC cache");
+        assert_eq!(error(b"VERSION \"\"\n\nNS_ :\n"), "not a DBC cache");
+        assert_eq!(
+            DbcLibrary::from_cache(&cache, 43).unwrap_err().to_string(),
+            "cache of a different DBC source"
+        );
+
+        let mut other_version = cache.clone();
+        other_version[8] = 0xFF;
+        assert_eq!(error(&other_version), "unsupported cache version 255");
+
+        let mut other_crate = cache.clone();
+        other_crate.splice(16..16 + CRATE_VERSION.len(), b"0.0.0".iter().copied());
+        other_crate[12..16].copy_from_slice(&5u32.to_le_bytes());
+        assert_eq!(error(&other_crate), "cache written by fastcan 0.0.0");
+
+        for len in 0..cache.len() {
+            let error = DbcLibrary::from_cache(&cache[..len], 42).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        let mut trailing = cache;
+        trailing.push(0);
+        assert_eq!(error(&trailing), "trailing data after DBC cache");
+    }
+
+    #[test]
+    fn test_from_dbc_file_cached() {
+        let dir = std::env::temp_dir().join(format!("fastcan-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dbc = dir.join("status.dbc");
+        let cache = dir.join("status.dbc.cache");
+
+        fs::write(&dbc, "BO_ 100 Status: 1 Engine\n").unwrap();
+        let lib = DbcLibrary::from_dbc_file_cached(&dbc, &cache).unwrap();
+        assert_eq!(lib.get_frame(100).unwrap().get_name(), "Status");
+        assert!(cache.exists());
+
+        // a valid cache is used instead of the DBC file
+        let hash = DbcLibrary::source_hash(&fs::read(&dbc).unwrap());
+        let other = DbcLibrary::from_dbc_str("BO_ 100 Other: 1 Engine\n");
+        fs::write(&cache, other.to_cache(hash)).unwrap();
+        let lib = DbcLibrary::from_dbc_file_cached(&dbc, &cache).unwrap();
+        assert_eq!(lib.get_frame(100).unwrap().get_name(), "Other");
+
+        // changing the DBC file invalidates the cache
+        fs::write(&dbc, "BO_ 100 Changed: 1 Engine\n").unwrap();
+        let lib = DbcLibrary::from_dbc_file_cached(&dbc, &cache).unwrap();
+        assert_eq!(lib.get_frame(100).unwrap().get_name(), "Changed");
+        let lib = DbcLibrary::from_dbc_file_cached(&dbc, &cache).unwrap();
+        assert_eq!(lib.get_frame(100).unwrap().get_name(), "Changed");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
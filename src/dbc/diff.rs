@@ -0,0 +1,643 @@
+//! Semantic comparison of DBC libraries
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use super::{DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, Multiplexing};
+use crate::id::CanId;
+
+/// Whether a change affects existing users of a library
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Impact {
+    /// Frames and signals decode to the same values under the same names
+    NonBreaking,
+    /// Frames or signals are gone, renamed or decode to different values
+    Breaking,
+}
+
+impl Display for Impact {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Impact::NonBreaking => write!(f, "non-breaking"),
+            Impact::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+/// What changed between two libraries
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ChangeKind {
+    /// A frame was added
+    FrameAdded,
+    /// A frame was removed
+    FrameRemoved,
+    /// A frame kept its ID but got a new name
+    FrameRenamed,
+    /// A frame kept its name but got a new ID
+    FrameIdChanged,
+    /// The length of a frame changed
+    FrameLengthChanged,
+    /// The sending node of a frame changed
+    FrameSenderChanged,
+    /// The `CM_` comment of a frame changed
+    FrameDescriptionChanged,
+    /// A `BA_` attribute of a frame was added, removed or changed
+    FrameAttributeChanged,
+    /// A signal was added to a frame
+    SignalAdded,
+    /// A signal was removed from a frame
+    SignalRemoved,
+    /// A signal kept its layout but got a new name
+    SignalRenamed,
+    /// The start bit, length, byte order, signedness or multiplexing of a signal changed
+    SignalLayoutChanged,
+    /// The scale or offset of a signal changed
+    SignalScalingChanged,
+    /// The minimum or maximum of a signal changed
+    SignalRangeChanged,
+    /// The unit of a signal changed
+    SignalUnitChanged,
+    /// The receiving nodes of a signal changed
+    SignalReceiversChanged,
+    /// The `VAL_` table of a signal changed
+    SignalValueTableChanged,
+    /// The `CM_` comment of a signal changed
+    SignalDescriptionChanged,
+    /// A `BA_` attribute of a signal was added, removed or changed
+    SignalAttributeChanged,
+}
+
+impl ChangeKind {
+    /// Returns whether changes of this kind break existing users.  Changes to the bit layout,
+    /// scaling, IDs and names are breaking, changes to documentation like comments, ranges,
+    /// units and value tables are not.
+    pub fn impact(self) -> Impact {
+        match self {
+            ChangeKind::FrameRemoved
+            | ChangeKind::FrameRenamed
+            | ChangeKind::FrameIdChanged
+            | ChangeKind::FrameLengthChanged
+            | ChangeKind::SignalRemoved
+            | ChangeKind::SignalRenamed
+            | ChangeKind::SignalLayoutChanged
+            | ChangeKind::SignalScalingChanged => Impact::Breaking,
+            _ => Impact::NonBreaking,
+        }
+    }
+
+    /// Returns a short description of the change, e.g. `scaling changed`
+    pub fn description(self) -> &'static str {
+        match self {
+            ChangeKind::FrameAdded => "frame added",
+            ChangeKind::FrameRemoved => "frame removed",
+            ChangeKind::FrameRenamed => "frame renamed",
+            ChangeKind::FrameIdChanged => "ID changed",
+            ChangeKind::FrameLengthChanged => "length changed",
+            ChangeKind::FrameSenderChanged => "sender changed",
+            ChangeKind::FrameDescriptionChanged => "comment changed",
+            ChangeKind::FrameAttributeChanged => "attribute changed",
+            ChangeKind::SignalAdded => "signal added",
+            ChangeKind::SignalRemoved => "signal removed",
+            ChangeKind::SignalRenamed => "signal renamed",
+            ChangeKind::SignalLayoutChanged => "layout changed",
+            ChangeKind::SignalScalingChanged => "scaling changed",
+            ChangeKind::SignalRangeChanged => "range changed",
+            ChangeKind::SignalUnitChanged => "unit changed",
+            ChangeKind::SignalReceiversChanged => "receivers changed",
+            ChangeKind::SignalValueTableChanged => "value table changed",
+            ChangeKind::SignalDescriptionChanged => "comment changed",
+            ChangeKind::SignalAttributeChanged => "attribute changed",
+        }
+    }
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+/// Difference found by `DbcLibrary::diff`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Change {
+    /// What changed
+    pub kind: ChangeKind,
+    /// ID of the frame as encoded in the DBC file, the new ID unless the frame was removed
+    pub frame_id: u32,
+    /// Name of the frame, the new name unless the frame was removed
+    pub frame: String,
+    /// Name of the signal, the new name unless the signal was removed
+    pub signal: Option<String>,
+    /// Old value in DBC notation, `None` if there was none.  Attributes are given as
+    /// `name = value`.
+    pub old: Option<String>,
+    /// New value in DBC notation, `None` if there is none
+    pub new: Option<String>,
+}
+
+impl Change {
+    /// Returns whether the change breaks existing users
+    pub fn impact(&self) -> Impact {
+        self.kind.impact()
+    }
+
+    fn frame(kind: ChangeKind, frame: &DbcFrame, old: Option<String>, new: Option<String>) -> Self {
+        Change {
+            kind,
+            frame_id: frame.get_id(),
+            frame: frame.get_name().to_string(),
+            signal: None,
+            old,
+            new,
+        }
+    }
+
+    fn signal(
+        kind: ChangeKind,
+        frame: &DbcFrame,
+        signal: &str,
+        old: Option<String>,
+        new: Option<String>,
+    ) -> Self {
+        Change {
+            signal: Some(signal.to_string()),
+            ..Change::frame(kind, frame, old, new)
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.impact(), self.frame)?;
+        if let Some(ref signal) = self.signal {
+            write!(f, ".{}", signal)?;
+        }
+        write!(f, ": {}", self.kind)?;
+        if self.old.is_some() || self.new.is_some() {
+            write!(
+                f,
+                " from {} to {}",
+                self.old.as_deref().unwrap_or("none"),
+                self.new.as_deref().unwrap_or("none")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DbcLibrary {
+    /// Compares the library with a newer version of it.  Changes are ordered by frame ID.
+    ///
+    /// Frames are matched by ID, then by name, so a frame that keeps its ID but not its name is
+    /// reported as renamed and one that keeps its name but not its ID as moved.  Signals are
+    /// matched by name, a removed and an added signal with the same layout are reported as
+    /// renamed.  Frames referred to by `CM_` or `BA_` entries only are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::dbc::{DbcLibrary, Impact};
+    ///
+    /// let old = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    /// let new = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let changes = old.diff(&new);
+    /// assert!(changes.iter().all(|change| change.impact() == Impact::NonBreaking));
+    /// ```
+    pub fn diff(&self, new: &DbcLibrary) -> Vec<Change> {
+        let old_frames = defined_frames(self);
+        let new_frames = defined_frames(new);
+        let mut changes = Vec::new();
+
+        let mut pairs = Vec::new();
+        let mut matched_old = HashSet::new();
+        let mut matched_new = HashSet::new();
+        for (id, old_frame) in &old_frames {
+            if let Some(new_frame) = new_frames.get(id) {
+                pairs.push((*old_frame, *new_frame));
+                matched_old.insert(*id);
+                matched_new.insert(*id);
+            }
+        }
+
+        let mut new_by_name: HashMap<&str, &DbcFrame> = HashMap::new();
+        for (id, frame) in &new_frames {
+            if !matched_new.contains(id) {
+                new_by_name.entry(frame.get_name()).or_insert(frame);
+            }
+        }
+        for (id, old_frame) in &old_frames {
+            if matched_old.contains(id) {
+                continue;
+            }
+            if let Some(new_frame) = new_by_name.remove(old_frame.get_name()) {
+                pairs.push((*old_frame, new_frame));
+                matched_old.insert(*id);
+                matched_new.insert(new_frame.get_can_id());
+            }
+        }
+
+        for (id, frame) in &old_frames {
+            if !matched_old.contains(id) {
+                changes.push(Change::frame(ChangeKind::FrameRemoved, frame, None, None));
+            }
+        }
+        for (id, frame) in &new_frames {
+            if !matched_new.contains(id) {
+                changes.push(Change::frame(ChangeKind::FrameAdded, frame, None, None));
+            }
+        }
+        for (old_frame, new_frame) in pairs {
+            diff_frame(old_frame, new_frame, &mut changes);
+        }
+
+        changes.sort_by(|a, b| {
+            (a.frame_id, &a.frame, a.signal.is_some(), &a.signal, a.kind).cmp(&(
+                b.frame_id,
+                &b.frame,
+                b.signal.is_some(),
+                &b.signal,
+                b.kind,
+            ))
+        });
+        changes
+    }
+}
+
+/// Returns the frames with a `BO_` definition by ID
+fn defined_frames(lib: &DbcLibrary) -> HashMap<CanId, &DbcFrame> {
+    lib.get_frames()
+        .into_iter()
+        .filter(|frame| !frame.get_name().is_empty())
+        .map(|frame| (frame.get_can_id(), frame))
+        .collect()
+}
+
+/// Appends the changes between two definitions of the same frame
+pub(super) fn diff_frame(old: &DbcFrame, new: &DbcFrame, changes: &mut Vec<Change>) {
+    let mut push = |kind, old: Option<String>, new_value: Option<String>| {
+        if old != new_value {
+            changes.push(Change::frame(kind, new, old, new_value));
+        }
+    };
+
+    push(
+        ChangeKind::FrameRenamed,
+        Some(old.get_name().to_string()),
+        Some(new.get_name().to_string()),
+    );
+    push(
+        ChangeKind::FrameIdChanged,
+        Some(format!("0x{}", old.get_can_id())),
+        Some(format!("0x{}", new.get_can_id())),
+    );
+    push(
+        ChangeKind::FrameLengthChanged,
+        Some(old.get_message_len().to_string()),
+        Some(new.get_message_len().to_string()),
+    );
+    push(
+        ChangeKind::FrameSenderChanged,
+        Some(old.get_sending_node().to_string()),
+        Some(new.get_sending_node().to_string()),
+    );
+    push(
+        ChangeKind::FrameDescriptionChanged,
+        old.get_description().map(quote),
+        new.get_description().map(quote),
+    );
+    for (old_attribute, new_attribute) in
+        diff_attributes(old.get_attributes(), new.get_attributes())
+    {
+        push(
+            ChangeKind::FrameAttributeChanged,
+            old_attribute,
+            new_attribute,
+        );
+    }
+
+    let old_signals = defined_signals(old);
+    let new_signals = defined_signals(new);
+
+    let mut removed: Vec<&DbcSignalDefinition> = old_signals
+        .iter()
+        .filter(|(name, _)| !new_signals.contains_key(*name))
+        .map(|(_, (definition, _))| *definition)
+        .collect();
+    let mut added: Vec<&DbcSignalDefinition> = new_signals
+        .iter()
+        .filter(|(name, _)| !old_signals.contains_key(*name))
+        .map(|(_, (definition, _))| *definition)
+        .collect();
+    removed.sort_by(|a, b| a.name.cmp(&b.name));
+    added.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut pairs: Vec<(&str, &str)> = old_signals
+        .keys()
+        .filter(|name| new_signals.contains_key(*name))
+        .map(|name| (*name, *name))
+        .collect();
+    removed.retain(|old_signal| {
+        match added
+            .iter()
+            .position(|new_signal| layout(old_signal) == layout(new_signal))
+        {
+            Some(index) => {
+                pairs.push((&old_signal.name, &added.remove(index).name));
+                false
+            }
+            None => true,
+        }
+    });
+
+    for signal in removed {
+        changes.push(Change::signal(
+            ChangeKind::SignalRemoved,
+            new,
+            &signal.name,
+            None,
+            None,
+        ));
+    }
+    for signal in added {
+        changes.push(Change::signal(
+            ChangeKind::SignalAdded,
+            new,
+            &signal.name,
+            None,
+            None,
+        ));
+    }
+    for (old_name, new_name) in pairs {
+        diff_signal(old_signals[old_name], new_signals[new_name], new, changes);
+    }
+}
+
+/// Returns the signals with an `SG_` definition by name
+fn defined_signals(frame: &DbcFrame) -> HashMap<&str, (&DbcSignalDefinition, &DbcSignal)> {
+    frame
+        .signal_map()
+        .values()
+        .filter_map(|signal| {
+            signal
+                .definition()
+                .map(|definition| (definition.name.as_str(), (definition, signal)))
+        })
+        .collect()
+}
+
+fn diff_signal(
+    (old, old_signal): (&DbcSignalDefinition, &DbcSignal),
+    (new, new_signal): (&DbcSignalDefinition, &DbcSignal),
+    frame: &DbcFrame,
+    changes: &mut Vec<Change>,
+) {
+    let name = &new.name;
+    let mut push = |kind, old: Option<String>, new: Option<String>| {
+        if old != new {
+            changes.push(Change::signal(kind, frame, name, old, new));
+        }
+    };
+
+    push(
+        ChangeKind::SignalRenamed,
+        Some(old.name.clone()),
+        Some(new.name.clone()),
+    );
+    push(
+        ChangeKind::SignalLayoutChanged,
+        Some(layout(old)),
+        Some(layout(new)),
+    );
+    push(
+        ChangeKind::SignalScalingChanged,
+        Some(format!("({},{})", old.scale, old.offset)),
+        Some(format!("({},{})", new.scale, new.offset)),
+    );
+    push(
+        ChangeKind::SignalRangeChanged,
+        Some(format!("[{}|{}]", old.min_value, old.max_value)),
+        Some(format!("[{}|{}]", new.min_value, new.max_value)),
+    );
+    push(
+        ChangeKind::SignalUnitChanged,
+        Some(quote(&old.units)),
+        Some(quote(&new.units)),
+    );
+    push(
+        ChangeKind::SignalReceiversChanged,
+        Some(old.receiving_node.clone()),
+        Some(new.receiving_node.clone()),
+    );
+    push(
+        ChangeKind::SignalValueTableChanged,
+        value_table(old_signal),
+        value_table(new_signal),
+    );
+    push(
+        ChangeKind::SignalDescriptionChanged,
+        old_signal.get_description().map(quote),
+        new_signal.get_description().map(quote),
+    );
+    for (old_attribute, new_attribute) in
+        diff_attributes(old_signal.get_attributes(), new_signal.get_attributes())
+    {
+        push(
+            ChangeKind::SignalAttributeChanged,
+            old_attribute,
+            new_attribute,
+        );
+    }
+}
+
+/// Formats the bit layout of a signal like an `SG_` entry, e.g. `24|16@1+` or `m1 8|8@0-`
+fn layout(signal: &DbcSignalDefinition) -> String {
+    let multiplexing = match signal.multiplexing {
+        Multiplexing::None => String::new(),
+        Multiplexing::Multiplexor => "M ".to_string(),
+        Multiplexing::Multiplexed(value) => format!("m{} ", value),
+    };
+    format!(
+        "{}{}|{}@{}{}",
+        multiplexing,
+        signal.start_bit,
+        signal.bit_len,
+        if signal.little_endian { 1 } else { 0 },
+        if signal.signed { '-' } else { '+' }
+    )
+}
+
+/// Formats the value table of a signal like a `VAL_` entry, e.g. `0 "Off" 1 "On"`
+fn value_table(signal: &DbcSignal) -> Option<String> {
+    signal.get_value_definition().map(|definition| {
+        let mut values = definition.get_values().to_vec();
+        values.sort();
+        values
+            .iter()
+            .map(|(value, label)| format!("{} {}", value, quote(label)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
+}
+
+/// Returns the added, removed and changed attributes as `name = value` pairs ordered by name
+fn diff_attributes(
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+) -> Vec<(Option<String>, Option<String>)> {
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| {
+            let format = |value: &String| format!("{} = {}", name, value);
+            (old.get(name).map(format), new.get(name).map(format))
+        })
+        .collect()
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"
+BO_ 100 Status : 8 Engine
+ SG_ Running : 0|1@1+ (1,0) [0|1] "" Dashboard
+ SG_ Speed : 8|16@1+ (0.125,0) [0|8031.88] "rpm" Dashboard
+ SG_ Temp : 24|8@1- (1,-40) [-40|210] "degC" Dashboard
+
+BO_ 200 Gear : 2 Transmission
+ SG_ Selected : 0|4@1+ (1,0) [0|15] "" Dashboard
+
+BO_ 300 Legacy : 1 Engine
+ SG_ Flag : 0|1@1+ (1,0) [0|1] "" Dashboard
+
+CM_ SG_ 100 Speed "Engine speed";
+VAL_ 100 Running 0 "Off" 1 "On" ;
+"#;
+
+    fn kinds(changes: &[Change]) -> Vec<(ChangeKind, Option<&str>)> {
+        changes
+            .iter()
+            .map(|change| (change.kind, change.signal.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_libraries() {
+        let old = DbcLibrary::from_dbc_str(OLD);
+        let new = DbcLibrary::from_dbc_str(OLD);
+
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn test_frame_changes() {
+        let old = DbcLibrary::from_dbc_str(OLD);
+        let new = DbcLibrary::from_dbc_str(
+            &OLD.replace("BO_ 100 Status : 8", "BO_ 100 EngineStatus : 8")
+                .replace("BO_ 200 Gear : 2", "BO_ 201 Gear : 1")
+                .replace("BO_ 300 Legacy", "BO_ 400 Brake"),
+        );
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            kinds(&changes),
+            vec![
+                (ChangeKind::FrameRenamed, None),
+                (ChangeKind::FrameIdChanged, None),
+                (ChangeKind::FrameLengthChanged, None),
+                (ChangeKind::FrameRemoved, None),
+                (ChangeKind::FrameAdded, None),
+            ]
+        );
+        assert_eq!(changes[1].frame, "Gear");
+        assert_eq!(changes[1].old.as_deref(), Some("0x0C8"));
+        assert_eq!(changes[1].new.as_deref(), Some("0x0C9"));
+        assert_eq!(changes[3].frame, "Legacy");
+        assert!(changes
+            .iter()
+            .all(|c| c.impact() == Impact::Breaking || c.kind == ChangeKind::FrameAdded));
+    }
+
+    #[test]
+    fn test_signal_changes() {
+        let old = DbcLibrary::from_dbc_str(OLD);
+        let new = DbcLibrary::from_dbc_str(
+            &OLD.replace("Running : 0|1@1+", "Active : 0|1@1+")
+                .replace("VAL_ 100 Running", "VAL_ 100 Active")
+                .replace("8|16@1+ (0.125,0)", "8|16@1+ (0.25,0)")
+                .replace("\"rpm\"", "\"1/min\"")
+                .replace("Temp : 24|8@1-", "Temp : 24|12@1-")
+                .replace("Engine speed", "Crankshaft speed"),
+        );
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            kinds(&changes),
+            vec![
+                (ChangeKind::SignalRenamed, Some("Active")),
+                (ChangeKind::SignalScalingChanged, Some("Speed")),
+                (ChangeKind::SignalUnitChanged, Some("Speed")),
+                (ChangeKind::SignalDescriptionChanged, Some("Speed")),
+                (ChangeKind::SignalLayoutChanged, Some("Temp")),
+            ]
+        );
+        assert_eq!(
+            changes[4].to_string(),
+            "breaking: Status.Temp: layout changed from 24|8@1- to 24|12@1-"
+        );
+        assert_eq!(changes[2].impact(), Impact::NonBreaking);
+        assert_eq!(changes[3].impact(), Impact::NonBreaking);
+    }
+
+    #[test]
+    fn test_added_and_removed_signals() {
+        let old = DbcLibrary::from_dbc_str(OLD);
+        let new = DbcLibrary::from_dbc_str(&OLD.replace(
+            " SG_ Temp : 24|8@1- (1,-40) [-40|210] \"degC\" Dashboard",
+            " SG_ Load : 32|8@1+ (1,0) [0|100] \"%\" Dashboard",
+        ));
+
+        assert_eq!(
+            kinds(&old.diff(&new)),
+            vec![
+                (ChangeKind::SignalAdded, Some("Load")),
+                (ChangeKind::SignalRemoved, Some("Temp")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_table_and_attribute_changes() {
+        let old = DbcLibrary::from_dbc_str(OLD);
+        let new = DbcLibrary::from_dbc_str(&format!(
+            "{}\nBA_ \"GenMsgCycleTime\" BO_ 200 100;\n",
+            OLD.replace("1 \"On\"", "1 \"On\" 2 \"Error\"")
+        ));
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            kinds(&changes),
+            vec![
+                (ChangeKind::SignalValueTableChanged, Some("Running")),
+                (ChangeKind::FrameAttributeChanged, None),
+            ]
+        );
+        assert_eq!(
+            changes[0].new.as_deref(),
+            Some("0 \"Off\" 1 \"On\" 2 \"Error\"")
+        );
+        assert_eq!(changes[1].old, None);
+        assert_eq!(changes[1].new.as_deref(), Some("GenMsgCycleTime = 100"));
+        assert!(changes.iter().all(|c| c.impact() == Impact::NonBreaking));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::dbc;
+use crate::id::CanId;
 use std::collections::HashMap;
 
 /// Trait for converting `Entry` values into a library's own entries.
@@ -60,7 +61,35 @@ impl DbcSignal {
         }
     }
 
+    /// Returns the definition of the signal, `None` if the signal is only referenced by `CM_` or
+    /// `BA_` entries
+    pub(crate) fn definition(&self) -> Option<&DbcSignalDefinition> {
+        self.definition.as_ref()
+    }
+
+    /// Adds the definition, description, attributes and value table of another definition of the
+    /// signal where this one lacks them
+    fn fill_in(&mut self, other: DbcSignal) {
+        if self.definition.is_none() {
+            self.definition = other.definition;
+        }
+        if self.description.is_none() {
+            self.description = other.description;
+        }
+        for (name, value) in other.attributes {
+            self.attributes.entry(name).or_insert(value);
+        }
+        if self.value_definition.is_none() {
+            self.value_definition = other.value_definition;
+        }
+    }
+
     /// Returns the definition of the signal
+    ///
+    /// # Panics
+    ///
+    /// Panics for signals created without definition, which `DbcFrame::get_signals` and
+    /// `DbcFrame::get_signal` never return
     pub fn get_definition(&self) -> &DbcSignalDefinition {
         self.definition.as_ref().unwrap() // if this fails, there is a bug either in the error management of the library or in the lib itself
     }
@@ -69,6 +98,21 @@ impl DbcSignal {
     pub fn get_attribute(&self, identifier: &str) -> Option<&String> {
         self.attributes.get(identifier)
     }
+
+    /// Returns the description of the signal (`CM_ SG_`)
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns all attributes of the signal (`BA_ ... SG_`)
+    pub fn get_attributes(&self) -> &HashMap<String, SignalAttribute> {
+        &self.attributes
+    }
+
+    /// Returns the value table of the signal, if the DBC file defines one
+    pub fn get_value_definition(&self) -> Option<&ValueDefinition> {
+        self.value_definition.as_ref()
+    }
 }
 
 type MessageAttribute = String;
@@ -78,7 +122,7 @@ type MessageAttribute = String;
 /// Container datatype for holding all informations about a CAN frame from a DBC file
 pub struct DbcFrame {
     name: String,
-    id: u32,
+    id: CanId,
     message_len: u32,
     sending_node: String,
 
@@ -87,10 +131,12 @@ pub struct DbcFrame {
     /// e.g., CM_ BO_ 2364540158 "Electronic Engine Controller 1";
     description: Option<String>,
     signals: HashMap<String, DbcSignal>,
+    /// Names of signals defined more than once
+    redefined_signals: Vec<String>,
 }
 
 impl DbcFrame {
-    /// Returns new DBCFrame
+    /// Returns new DBCFrame, `id` is the message ID as encoded in the DBC file
     pub fn new(
         name: String,
         id: u32,
@@ -102,30 +148,105 @@ impl DbcFrame {
     ) -> Self {
         Self {
             name,
-            id,
+            id: CanId::from_dbc(id),
             message_len,
             sending_node,
             attributes,
             description,
             signals,
+            redefined_signals: Vec::new(),
         }
     }
 
-    /// Returns Vec of borrowed signal objects
+    /// Returns Vec of borrowed signal objects.  Signals only referenced by `CM_`, `BA_` or `VAL_`
+    /// entries, without `SG_` definition, are left out.
     pub fn get_signals(&self) -> Vec<&DbcSignal> {
-        self.signals.values().collect()
+        self.signals
+            .values()
+            .filter(|signal| signal.definition.is_some())
+            .collect()
     }
 
-    /// Query signal with signal name
+    /// Query signal with signal name, `None` for signals without `SG_` definition
     pub fn get_signal(&self, name: &str) -> Option<&DbcSignal> {
-        self.signals.get(name)
+        self.signals
+            .get(name)
+            .filter(|signal| signal.definition.is_some())
     }
 
-    /// Returns arbitration ID of CAN frame
+    /// Returns name of CAN frame
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns length of CAN frame in bytes
+    pub fn get_message_len(&self) -> u32 {
+        self.message_len
+    }
+
+    /// Returns arbitration ID of CAN frame as encoded in the DBC file
     pub fn get_id(&self) -> u32 {
+        self.id.to_dbc()
+    }
+
+    /// Returns arbitration ID of CAN frame
+    pub fn get_can_id(&self) -> CanId {
         self.id
     }
 
+    /// Returns the node sending the CAN frame
+    pub fn get_sending_node(&self) -> &str {
+        &self.sending_node
+    }
+
+    /// Returns the description of the CAN frame (`CM_ BO_`)
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns all attributes of the CAN frame (`BA_ ... BO_`)
+    pub fn get_attributes(&self) -> &HashMap<String, MessageAttribute> {
+        &self.attributes
+    }
+
+    /// Returns all signals by name, including signals only referenced by `CM_` or `BA_` entries
+    pub(crate) fn signal_map(&self) -> &HashMap<String, DbcSignal> {
+        &self.signals
+    }
+
+    /// Returns the names of signals defined more than once
+    pub(crate) fn redefined_signals(&self) -> &[String] {
+        &self.redefined_signals
+    }
+
+    pub(crate) fn redefined_signals_mut(&mut self) -> &mut Vec<String> {
+        &mut self.redefined_signals
+    }
+
+    /// Returns true for frames only referenced by `CM_` or `BA_` entries, without `BO_` definition
+    pub(crate) fn is_placeholder(&self) -> bool {
+        self.name.is_empty()
+    }
+
+    /// Adds the description, attributes and signals of another definition of the frame where
+    /// this one lacks them
+    pub(crate) fn fill_in(&mut self, other: DbcFrame) {
+        if self.description.is_none() {
+            self.description = other.description;
+        }
+        for (name, value) in other.attributes {
+            self.attributes.entry(name).or_insert(value);
+        }
+        for (name, signal) in other.signals {
+            match self.signals.get_mut(&name) {
+                Some(existing) => existing.fill_in(signal),
+                None => {
+                    self.signals.insert(name, signal);
+                }
+            }
+        }
+    }
+
     /// Query frame attribute with an identifier
     pub fn get_attribute(&self, identifier: &str) -> &String {
         self.attributes.get(identifier).unwrap()
@@ -141,32 +262,30 @@ impl FromDbc for DbcFrame {
     {
         match entry {
             Entry::MessageDefinition(dbc::DbcFrameDefinition {
-                id: _id,
+                id,
                 name,
                 message_len,
                 sending_node,
             }) => Ok(DbcFrame {
                 name,
+                id: CanId::from_dbc(id),
                 message_len,
                 sending_node,
                 ..Default::default()
             }),
-            Entry::MessageDescription(dbc::DbcMessageDescription {
-                id: _id,
-                description,
-            }) => Ok(DbcFrame {
-                description: Some(description),
-                ..Default::default()
-            }),
-            Entry::MessageAttribute(dbc::DbcMessageAttribute {
-                name,
-                id: _id,
-                value,
-            }) => {
+            Entry::MessageDescription(dbc::DbcMessageDescription { id, description }) => {
+                Ok(DbcFrame {
+                    id: CanId::from_dbc(id),
+                    description: Some(description),
+                    ..Default::default()
+                })
+            }
+            Entry::MessageAttribute(dbc::DbcMessageAttribute { name, id, value }) => {
                 let mut attributes = HashMap::new();
                 attributes.insert(name, value);
 
                 Ok(DbcFrame {
+                    id: CanId::from_dbc(id),
                     attributes,
                     ..Default::default()
                 })
@@ -179,11 +298,12 @@ impl FromDbc for DbcFrame {
     fn merge_entry(&mut self, entry: dbc::Entry) -> Result<(), Self::Err> {
         match entry {
             Entry::MessageDefinition(dbc::DbcFrameDefinition {
-                id: _id,
+                id,
                 name,
                 message_len,
                 sending_node,
             }) => {
+                self.id = CanId::from_dbc(id);
                 self.name = name;
                 self.message_len = message_len;
                 self.sending_node = sending_node;
@@ -208,6 +328,9 @@ impl FromDbc for DbcFrame {
             }
             Entry::SignalDefinition(inner) => {
                 if self.signals.contains_key(&inner.name) {
+                    if self.signals[&inner.name].definition.is_some() {
+                        self.redefined_signals.push(inner.name.clone());
+                    }
                     (*self
                         .signals
                         .get_mut(&inner.name)
@@ -248,6 +371,20 @@ impl FromDbc for DbcFrame {
                     Ok(())
                 }
             }
+            Entry::ValueDescription(inner) => {
+                if self.signals.contains_key(&inner.signal_name) {
+                    (*self
+                        .signals
+                        .get_mut(&inner.signal_name)
+                        .expect("Already checked for Signal key"))
+                    .merge_entry(Entry::ValueDescription(inner))
+                } else {
+                    let name = inner.signal_name.clone();
+                    let signal = DbcSignal::from_entry(Entry::ValueDescription(inner))?;
+                    self.signals.insert(name, signal);
+                    Ok(())
+                }
+            }
             _ => Err(()),
         }
     }
@@ -292,6 +429,16 @@ impl FromDbc for DbcSignal {
                     value_definition: None,
                 })
             }
+            Entry::ValueDescription(dbc::DbcValueDescription {
+                id: _id,
+                signal_name: _signal_name,
+                values,
+            }) => Ok(DbcSignal {
+                attributes: HashMap::new(),
+                description: None,
+                definition: None,
+                value_definition: Some(ValueDefinition::new(values)),
+            }),
             _ => Err(()),
         }
     }
@@ -321,6 +468,14 @@ impl FromDbc for DbcSignal {
                 }
                 Ok(())
             }
+            Entry::ValueDescription(dbc::DbcValueDescription {
+                id: _id,
+                signal_name: _signal_name,
+                values,
+            }) => {
+                self.value_definition = Some(ValueDefinition::new(values));
+                Ok(())
+            }
             _ => Err(()),
         }
     }
@@ -330,16 +485,61 @@ impl FromDbc for DbcSignal {
 /// with CAN messages and signals.
 #[derive(Clone, Debug, Default)]
 pub struct DbcLibrary {
-    last_id: Option<u32>,
-    frames: HashMap<u32, DbcFrame>,
+    last_id: Option<CanId>,
+    /// Set while reading the signals of a pseudo frame, which are left out
+    in_pseudo_frame: bool,
+    frames: HashMap<CanId, DbcFrame>,
+    /// e.g., BU_: Engine Gateway
+    nodes: Vec<String>,
+    /// Names of frame definitions replaced by a later `BO_` with the same ID
+    redefined_frames: Vec<(CanId, String)>,
+    /// Signals that `CM_`, `BA_` or `VAL_` entries refer to in frames without definition
+    dangling_signals: Vec<(CanId, String)>,
 }
 
 impl DbcLibrary {
-    /// Query frames with frame ID
+    /// Query frames with frame ID as encoded in the DBC file.  IDs that are too large for a
+    /// standard identifier are looked up as extended IDs, so the bus ID works as well.  IDs of
+    /// pseudo frames, with bit 29 or 30 set, are never found.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// assert!(lib.get_frame(2364539904).is_some());
+    /// assert!(lib.get_frame(0x0CF00400).is_some());
+    /// ```
     pub fn get_frame(&self, id: u32) -> Option<&DbcFrame> {
+        CanId::try_from_dbc(id).and_then(|id| self.get_frame_by_id(id))
+    }
+
+    /// Query frames with a `CanId`
+    pub fn get_frame_by_id(&self, id: CanId) -> Option<&DbcFrame> {
         self.frames.get(&id)
     }
 
+    /// Query frames with the ID as seen on the bus and a flag for extended identifiers
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// assert!(lib.get_frame_raw(0x0CF00400, true).is_some());
+    /// assert!(lib.get_frame_raw(0x0CF00400, false).is_none());
+    /// ```
+    pub fn get_frame_raw(&self, id: u32, extended: bool) -> Option<&DbcFrame> {
+        CanId::new(id, extended).and_then(|id| self.get_frame_by_id(id))
+    }
+
+    /// Returns Vec of borrowed frame objects
+    pub fn get_frames(&self) -> Vec<&DbcFrame> {
+        self.frames.values().collect()
+    }
+
     /// Returns how many frames are contained in the DBC
     pub fn len(&self) -> usize {
         self.frames.len()
@@ -350,11 +550,44 @@ impl DbcLibrary {
         self.frames.len() == 0
     }
 
+    /// Returns the nodes of the network (`BU_`)
+    pub fn get_nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// Returns the ID and name of every frame definition that was replaced by a later definition
+    /// with the same ID
+    pub(crate) fn redefined_frames(&self) -> &[(CanId, String)] {
+        &self.redefined_frames
+    }
+
+    pub(crate) fn redefined_frames_mut(&mut self) -> &mut Vec<(CanId, String)> {
+        &mut self.redefined_frames
+    }
+
+    /// Returns the ID and name of every signal that entries refer to in a frame without
+    /// definition, as these entries are rejected
+    pub(crate) fn dangling_signals(&self) -> &[(CanId, String)] {
+        &self.dangling_signals
+    }
+
+    pub(crate) fn dangling_signals_mut(&mut self) -> &mut Vec<(CanId, String)> {
+        &mut self.dangling_signals
+    }
+
+    pub(crate) fn frames_mut(&mut self) -> &mut HashMap<CanId, DbcFrame> {
+        &mut self.frames
+    }
+
+    pub(crate) fn nodes_mut(&mut self) -> &mut Vec<String> {
+        &mut self.nodes
+    }
+
     /// Returns a `SpnDefinition` entry reference, if it exists.
     pub fn get_signal(&self, name: &str) -> Option<&DbcSignal> {
         self.frames
             .iter()
-            .find_map(|frame| frame.1.signals.get(name))
+            .find_map(|frame| frame.1.get_signal(name))
     }
 }
 
@@ -369,11 +602,15 @@ use super::{parser, DbcSignalDefinition, ValueDefinition};
 use crate::dbc::Entry;
 
 impl DbcLibrary {
-    /// Creates a new `DbcLibrary` instance given an existing lookup table.
+    /// Creates a new `DbcLibrary` instance given an existing lookup table.  The keys are message
+    /// IDs as encoded in the DBC file.
     pub fn new(messages: HashMap<u32, DbcFrame>) -> Self {
         DbcLibrary {
-            last_id: None,
-            frames: messages,
+            frames: messages
+                .into_iter()
+                .filter_map(|(id, frame)| CanId::try_from_dbc(id).map(|id| (id, frame)))
+                .collect(),
+            ..Default::default()
         }
     }
 
@@ -402,8 +639,6 @@ impl DbcLibrary {
         P: AsRef<Path>,
         E: Encoding,
     {
-        let mut lib = DbcLibrary::default();
-
         let data = File::open(path)
             .and_then(|mut f| {
                 let mut contents: Vec<u8> = Vec::new();
@@ -412,9 +647,30 @@ impl DbcLibrary {
             .and_then(|contents| {
                 encoding
                     .decode(contents.as_slice(), DecoderTrap::Replace)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+                    .map_err(io::Error::other)
             })?;
 
+        Ok(Self::from_dbc_str(&data))
+    }
+
+    /// Loads the contents of a DBC file into a returned `DbcLibrary`, ignoring unparseable lines
+    /// like `from_dbc_file`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_str(
+    ///     r#"BO_ 100 Status : 1 Engine
+    ///  SG_ Running : 0|1@1+ (1,0) [0|1] "" Dashboard"#,
+    /// );
+    ///
+    /// assert_eq!(lib.get_frame(100).unwrap().get_name(), "Status");
+    /// ```
+    pub fn from_dbc_str(data: &str) -> Self {
+        let mut lib = DbcLibrary::default();
+
         for line in data.lines() {
             if line.is_empty() {
                 continue;
@@ -426,43 +682,94 @@ impl DbcLibrary {
             }
         }
 
-        Ok(lib)
+        lib
     }
 }
 
 impl DbcLibrary {
     /// Add DBC `Entry` to DBC library
+    ///
+    /// Pseudo frames like `VECTOR__INDEPENDENT_SIG_MSG`, with bit 29 or 30 of the ID set, are left
+    /// out with their signals and the entries referring to them.
     pub fn add_entry(&mut self, entry: Entry) -> Result<(), String> {
-        let _id: u32 = *match entry {
-            Entry::MessageDefinition(dbc::DbcFrameDefinition { ref id, .. }) => id,
-            Entry::MessageDescription(dbc::DbcMessageDescription { ref id, .. }) => id,
-            Entry::MessageAttribute(dbc::DbcMessageAttribute { ref id, .. }) => id,
+        let _id: u32 = match entry {
+            Entry::CanNodes(dbc::DbcNodes(nodes)) => {
+                self.nodes = nodes;
+                return Ok(());
+            }
+            Entry::MessageDefinition(dbc::DbcFrameDefinition { id, .. }) => {
+                self.in_pseudo_frame = CanId::try_from_dbc(id).is_none();
+                if self.in_pseudo_frame {
+                    self.last_id = None;
+                    return Ok(());
+                }
+                if let Some(frame) = self.frames.get(&CanId::from_dbc(id)) {
+                    if !frame.name.is_empty() {
+                        self.redefined_frames
+                            .push((CanId::from_dbc(id), frame.name.clone()));
+                    }
+                }
+                id
+            }
+            Entry::MessageDescription(dbc::DbcMessageDescription { id, .. }) => id,
+            Entry::MessageAttribute(dbc::DbcMessageAttribute { id, .. }) => id,
             Entry::SignalDefinition(..) => {
                 // no id, and by definition must follow MessageDefinition
-                if let Some(last_id) = self.last_id.as_ref() {
-                    last_id
+                if self.in_pseudo_frame {
+                    return Ok(());
+                }
+                if let Some(last_id) = self.last_id {
+                    last_id.to_dbc()
                 } else {
                     return Err("Tried to add SignalDefinition without last ID.".to_string());
                 }
             }
-            Entry::SignalDescription(dbc::DbcSignalDescription { ref id, .. }) => id,
-            Entry::SignalAttribute(dbc::DbcSignalAttribute { ref id, .. }) => id,
+            Entry::SignalDescription(dbc::DbcSignalDescription {
+                id,
+                ref signal_name,
+                ..
+            })
+            | Entry::SignalAttribute(dbc::DbcSignalAttribute {
+                id,
+                ref signal_name,
+                ..
+            })
+            | Entry::ValueDescription(dbc::DbcValueDescription {
+                id,
+                ref signal_name,
+                ..
+            }) => {
+                let can_id = match CanId::try_from_dbc(id) {
+                    Some(can_id) => can_id,
+                    None => return Ok(()),
+                };
+                // signals can only be added to frames, which `CM_`, `BA_` and `VAL_` do not create
+                if !self.frames.contains_key(&can_id) {
+                    let dangling = (can_id, signal_name.clone());
+                    if !self.dangling_signals.contains(&dangling) {
+                        self.dangling_signals.push(dangling);
+                    }
+                    return Err(format!("{} for undefined frame {}.", entry.get_type(), id));
+                }
+                id
+            }
             _ => {
                 return Err(format!("Unsupported entry: {}.", entry));
             }
         };
 
-        self.frames
-            .entry(_id)
-            .and_modify(|cur_entry| {
-                cur_entry
-                    .merge_entry(entry.clone())
-                    .unwrap_or_else(|_| panic!("Already checked for Signal key: {:?}", entry))
-            })
-            .or_insert_with(|| {
-                DbcFrame::from_entry(entry.clone())
-                    .unwrap_or_else(|_| panic!("Some inserted a Signal for empty key: {:?}", _id))
-            });
+        let _id = match CanId::try_from_dbc(_id) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        match self.frames.get_mut(&_id) {
+            Some(frame) => frame.merge_entry(entry.clone()),
+            None => DbcFrame::from_entry(entry.clone()).map(|frame| {
+                self.frames.insert(_id, frame);
+            }),
+        }
+        .map_err(|_| format!("Unsupported entry: {}.", entry))?;
 
         self.last_id = Some(_id);
 
@@ -0,0 +1,566 @@
+//! Structural and semantic checks of DBC libraries
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use super::{DbcFrame, DbcLibrary, DbcSignalDefinition, Multiplexing, NO_NODE};
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    /// The definition is questionable, but decodes
+    Warning,
+    /// The definition is broken, frames or signals will not decode as intended
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The check that produced a diagnostic
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Check {
+    /// Two signals present in the same frame share bits
+    OverlappingSignals,
+    /// A signal has bits beyond the frame length
+    SignalOutOfFrame,
+    /// Two frame definitions have the same ID
+    DuplicateFrameId,
+    /// Two frames have the same name
+    DuplicateFrameName,
+    /// A signal is defined twice in a frame
+    DuplicateSignal,
+    /// The minimum and maximum of a signal can not be represented with its length, scale and
+    /// offset
+    UnrepresentableRange,
+    /// The minimum of a signal is larger than its maximum
+    InvertedRange,
+    /// A `CM_` or `BA_` entry refers to a frame or signal that is not defined
+    DanglingReference,
+    /// A sending or receiving node is not listed in `BU_`
+    UndefinedNode,
+}
+
+impl Check {
+    /// Returns the kebab-case name of the check, e.g. `overlapping-signals`
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::OverlappingSignals => "overlapping-signals",
+            Check::SignalOutOfFrame => "signal-out-of-frame",
+            Check::DuplicateFrameId => "duplicate-frame-id",
+            Check::DuplicateFrameName => "duplicate-frame-name",
+            Check::DuplicateSignal => "duplicate-signal",
+            Check::UnrepresentableRange => "unrepresentable-range",
+            Check::InvertedRange => "inverted-range",
+            Check::DanglingReference => "dangling-reference",
+            Check::UndefinedNode => "undefined-node",
+        }
+    }
+
+    /// Returns the severity of diagnostics of the check
+    pub fn severity(self) -> Severity {
+        match self {
+            Check::UnrepresentableRange | Check::InvertedRange | Check::UndefinedNode => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Problem found by `DbcLibrary::lint`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// How serious the problem is
+    pub severity: Severity,
+    /// The check that found the problem
+    pub check: Check,
+    /// ID of the frame the problem was found in, as encoded in the DBC file
+    pub frame_id: Option<u32>,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(check: Check, frame_id: Option<u32>, message: String) -> Self {
+        Diagnostic {
+            severity: check.severity(),
+            check,
+            frame_id,
+            message,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.check, self.message)
+    }
+}
+
+impl DbcLibrary {
+    /// Checks the library for broken or questionable definitions.  Diagnostics are ordered by
+    /// frame ID, diagnostics not specific to a frame come last.
+    ///
+    /// Signal layouts are checked with the bit numbering of the `codec` module, where the start
+    /// bit of big endian signals is their least significant bit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// for diagnostic in lib.lint() {
+    ///     println!("{}", diagnostic);
+    /// }
+    /// ```
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        let mut frames = self.get_frames();
+        frames.sort_by_key(|frame| frame.get_id());
+
+        let mut diagnostics = Vec::new();
+        for (id, name) in self.redefined_frames() {
+            let frame = self
+                .get_frame_by_id(*id)
+                .map_or("", |frame| frame.get_name());
+            diagnostics.push(Diagnostic::new(
+                Check::DuplicateFrameId,
+                Some(id.to_dbc()),
+                format!("frames {} and {} have the same ID 0x{}", name, frame, id),
+            ));
+        }
+        for (id, signal) in self.dangling_signals() {
+            diagnostics.push(Diagnostic::new(
+                Check::DanglingReference,
+                Some(id.to_dbc()),
+                format!(
+                    "CM_, BA_ or VAL_ entries refer to signal {} of frame 0x{} without definition",
+                    signal, id
+                ),
+            ));
+        }
+        for frame in &frames {
+            lint_frame(self, frame, &mut diagnostics);
+        }
+
+        let mut names: HashMap<&str, Vec<&DbcFrame>> = HashMap::new();
+        for frame in frames.iter().filter(|frame| !frame.get_name().is_empty()) {
+            names.entry(frame.get_name()).or_default().push(frame);
+        }
+        let mut duplicates: Vec<_> = names.into_iter().filter(|(_, f)| f.len() > 1).collect();
+        duplicates.sort_by_key(|(name, _)| *name);
+        for (name, frames) in duplicates {
+            let ids: Vec<String> = frames
+                .iter()
+                .map(|frame| format!("0x{}", frame.get_can_id()))
+                .collect();
+            diagnostics.push(Diagnostic::new(
+                Check::DuplicateFrameName,
+                None,
+                format!("frame name {} is used by IDs {}", name, ids.join(", ")),
+            ));
+        }
+
+        diagnostics.sort_by_key(|diagnostic| (diagnostic.frame_id.is_none(), diagnostic.frame_id));
+        diagnostics
+    }
+}
+
+fn lint_frame(lib: &DbcLibrary, frame: &DbcFrame, diagnostics: &mut Vec<Diagnostic>) {
+    let id = Some(frame.get_id());
+
+    if frame.get_name().is_empty() {
+        diagnostics.push(Diagnostic::new(
+            Check::DanglingReference,
+            id,
+            format!(
+                "CM_ or BA_ entries refer to frame 0x{} without definition",
+                frame.get_can_id()
+            ),
+        ));
+        return;
+    }
+
+    let name = frame.get_name();
+    let mut signals: Vec<(&String, Option<&DbcSignalDefinition>)> = frame
+        .signal_map()
+        .iter()
+        .map(|(signal, definition)| (signal, definition.definition()))
+        .collect();
+    signals.sort_by_key(|(signal, _)| *signal);
+
+    for signal in frame.redefined_signals() {
+        diagnostics.push(Diagnostic::new(
+            Check::DuplicateSignal,
+            id,
+            format!("signal {}.{} is defined more than once", name, signal),
+        ));
+    }
+
+    check_node(lib, frame.get_sending_node(), name, id, diagnostics);
+
+    let mut layouts = Vec::new();
+    for (signal, definition) in signals {
+        let definition = match definition {
+            Some(definition) => definition,
+            None => {
+                diagnostics.push(Diagnostic::new(
+                    Check::DanglingReference,
+                    id,
+                    format!(
+                        "CM_, BA_ or VAL_ entries refer to signal {}.{} without definition",
+                        name, signal
+                    ),
+                ));
+                continue;
+            }
+        };
+
+        let (bits, beyond) = signal_bits(definition, frame.get_message_len() as usize);
+        if beyond {
+            diagnostics.push(Diagnostic::new(
+                Check::SignalOutOfFrame,
+                id,
+                format!(
+                    "signal {}.{} extends beyond the {} bytes of the frame",
+                    name,
+                    signal,
+                    frame.get_message_len()
+                ),
+            ));
+        }
+
+        check_range(name, definition, id, diagnostics);
+        for node in definition.receiving_node.split(',') {
+            check_node(lib, node, &format!("{}.{}", name, signal), id, diagnostics);
+        }
+
+        layouts.push((definition, bits));
+    }
+
+    for (i, (a, a_bits)) in layouts.iter().enumerate() {
+        for (b, b_bits) in &layouts[i + 1..] {
+            if coexist(a.multiplexing, b.multiplexing) && !a_bits.is_disjoint(b_bits) {
+                diagnostics.push(Diagnostic::new(
+                    Check::OverlappingSignals,
+                    id,
+                    format!("signals {}.{} and {} overlap", name, a.name, b.name),
+                ));
+            }
+        }
+    }
+}
+
+/// Returns the positions of the bits of a signal within a frame of `len` bytes, numbered
+/// `byte * 8 + bit` as by the `codec` module, and whether bits lie beyond the frame
+fn signal_bits(definition: &DbcSignalDefinition, len: usize) -> (HashSet<usize>, bool) {
+    let descriptor = definition.descriptor();
+    let start = definition.start_bit;
+    let mut beyond = false;
+
+    let bits = (start..start + definition.bit_len)
+        .filter_map(|bit| match descriptor.byte(len.max(8), bit) {
+            Some(byte) if byte < len => Some(byte * 8 + bit % 8),
+            _ => {
+                beyond = true;
+                None
+            }
+        })
+        .collect();
+
+    (bits, beyond)
+}
+
+/// Returns true if two signals can be part of the same frame
+fn coexist(a: Multiplexing, b: Multiplexing) -> bool {
+    match (a, b) {
+        (Multiplexing::Multiplexed(a), Multiplexing::Multiplexed(b)) => a == b,
+        _ => true,
+    }
+}
+
+fn check_range(
+    frame: &str,
+    definition: &DbcSignalDefinition,
+    id: Option<u32>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let (min, max) = (
+        f64::from(definition.min_value),
+        f64::from(definition.max_value),
+    );
+    // a range of 0 to 0 means the range is not specified
+    if min == 0.0 && max == 0.0 {
+        return;
+    }
+    if min > max {
+        diagnostics.push(Diagnostic::new(
+            Check::InvertedRange,
+            id,
+            format!(
+                "signal {}.{} has a minimum of {} above its maximum of {}",
+                frame, definition.name, definition.min_value, definition.max_value
+            ),
+        ));
+        return;
+    }
+
+    let len = definition.bit_len.min(64) as i32;
+    let (raw_min, raw_max) = if definition.signed && len > 0 {
+        (-(2f64.powi(len - 1)), 2f64.powi(len - 1) - 1.0)
+    } else {
+        (0.0, 2f64.powi(len) - 1.0)
+    };
+    let (scale, offset) = (f64::from(definition.scale), f64::from(definition.offset));
+    let (a, b) = (raw_min * scale + offset, raw_max * scale + offset);
+    let (lowest, highest) = (a.min(b), a.max(b));
+    // ranges are often rounded, allow half a step
+    let tolerance = scale.abs() / 2.0 + 1e-6 * highest.abs().max(lowest.abs()).max(1.0);
+
+    if min < lowest - tolerance || max > highest + tolerance {
+        diagnostics.push(Diagnostic::new(
+            Check::UnrepresentableRange,
+            id,
+            format!(
+                "signal {}.{} has a range of [{}|{}], but can only represent [{}|{}]",
+                frame,
+                definition.name,
+                definition.min_value,
+                definition.max_value,
+                lowest as f32,
+                highest as f32
+            ),
+        ));
+    }
+}
+
+fn check_node(
+    lib: &DbcLibrary,
+    node: &str,
+    user: &str,
+    id: Option<u32>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let node = node.trim();
+    if node.is_empty() || node == NO_NODE || lib.get_nodes().iter().any(|n| n == node) {
+        return;
+    }
+
+    diagnostics.push(Diagnostic::new(
+        Check::UndefinedNode,
+        id,
+        format!("node {} of {} is not defined in BU_", node, user),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checks(lib: &DbcLibrary) -> Vec<Check> {
+        lib.lint().into_iter().map(|d| d.check).collect()
+    }
+
+    #[test]
+    fn test_sample_is_clean() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        assert_eq!(lib.lint(), Vec::new());
+    }
+
+    #[test]
+    fn test_overlapping_signals() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 Frame : 8 Vector__XXX
+ SG_ A : 0|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ B : 4|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ C : 36|8@0+ (1,0) [0|255] "" Vector__XXX
+ SG_ D : 16|8@1+ (1,0) [0|255] "" Vector__XXX"#,
+        );
+
+        let diagnostics = lib.lint();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].check, Check::OverlappingSignals);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].frame_id, Some(100));
+        assert_eq!(diagnostics[0].message, "signals Frame.A and B overlap");
+        assert_eq!(diagnostics[1].message, "signals Frame.C and D overlap");
+    }
+
+    #[test]
+    fn test_big_endian_overlap() {
+        // Output_Torque occupies bytes 1 and 2 in the numbering of the `codec` module
+        let lib = DbcLibrary::from_dbc_file("./tests/data/codegen.dbc").unwrap();
+        assert_eq!(lib.lint(), Vec::new());
+
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 291 Transmission : 8 Vector__XXX
+ SG_ Output_Torque : 40|16@0- (0.5,0) [-16384|16383.5] "Nm" Vector__XXX
+ SG_ ShiftCount : 32|32@1+ (1,0) [0|4294967295] "" Vector__XXX
+ SG_ Torque_Low : 16|4@1+ (1,0) [0|15] "" Vector__XXX"#,
+        );
+        let diagnostics = lib.lint();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "signals Transmission.Output_Torque and Torque_Low overlap"
+        );
+    }
+
+    #[test]
+    fn test_multiplexed_signals_do_not_overlap() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 Frame : 2 Vector__XXX
+ SG_ Mux M : 0|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ A m0 : 8|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ B m1 : 8|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ C m1 : 12|4@1+ (1,0) [0|15] "" Vector__XXX"#,
+        );
+
+        assert_eq!(checks(&lib), vec![Check::OverlappingSignals]);
+        assert!(lib.lint()[0].message.contains("B and C"));
+    }
+
+    #[test]
+    fn test_signal_out_of_frame() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 Frame : 2 Vector__XXX
+ SG_ A : 8|16@1+ (1,0) [0|65535] "" Vector__XXX
+ SG_ B : 48|16@0+ (1,0) [0|65535] "" Vector__XXX
+ SG_ C : 40|16@0+ (1,0) [0|65535] "" Vector__XXX"#,
+        );
+
+        let diagnostics = lib.lint();
+        let out_of_frame: Vec<&str> = diagnostics
+            .iter()
+            .filter(|d| d.check == Check::SignalOutOfFrame)
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            out_of_frame,
+            vec![
+                "signal Frame.A extends beyond the 2 bytes of the frame",
+                "signal Frame.C extends beyond the 2 bytes of the frame",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 First : 8 Vector__XXX
+ SG_ A : 0|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ A : 8|8@1+ (1,0) [0|255] "" Vector__XXX
+BO_ 100 Second : 8 Vector__XXX
+BO_ 200 Second : 8 Vector__XXX"#,
+        );
+
+        let diagnostics = lib.lint();
+        assert_eq!(
+            checks(&lib),
+            vec![
+                Check::DuplicateFrameId,
+                Check::DuplicateSignal,
+                Check::DuplicateFrameName
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "frames First and Second have the same ID 0x064"
+        );
+        assert_eq!(diagnostics[2].frame_id, None);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 Frame : 8 Vector__XXX
+ SG_ Speed : 0|16@1+ (0.125,0) [0|8031.88] "rpm" Vector__XXX
+ SG_ Temp : 16|8@1+ (1,-40) [-40|300] "degC" Vector__XXX
+ SG_ Torque : 24|8@1- (1,0) [-128|127] "%" Vector__XXX
+ SG_ Level : 32|8@1+ (1,0) [10|5] "" Vector__XXX"#,
+        );
+
+        let diagnostics = lib.lint();
+        assert_eq!(
+            checks(&lib),
+            vec![Check::InvertedRange, Check::UnrepresentableRange]
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert!(diagnostics[1].message.contains("Frame.Temp"));
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BO_ 100 Frame : 8 Vector__XXX
+ SG_ A : 0|8@1+ (1,0) [0|255] "" Vector__XXX
+CM_ SG_ 100 B "No such signal";
+CM_ BO_ 200 "No such frame";
+CM_ SG_ 300 C "No such frame";
+VAL_ 300 C 1 "On" ;"#,
+        );
+
+        let diagnostics = lib.lint();
+        assert_eq!(
+            checks(&lib),
+            vec![
+                Check::DanglingReference,
+                Check::DanglingReference,
+                Check::DanglingReference
+            ]
+        );
+        let ids: Vec<_> = diagnostics.iter().map(|d| d.frame_id).collect();
+        assert_eq!(ids, vec![Some(100), Some(200), Some(300)]);
+        assert_eq!(
+            diagnostics[0].message,
+            "CM_, BA_ or VAL_ entries refer to signal Frame.B without definition"
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "CM_ or BA_ entries refer to frame 0x0C8 without definition"
+        );
+        assert_eq!(
+            diagnostics[2].message,
+            "CM_, BA_ or VAL_ entries refer to signal C of frame 0x12C without definition"
+        );
+    }
+
+    #[test]
+    fn test_undefined_nodes() {
+        let lib = DbcLibrary::from_dbc_str(
+            r#"BU_: Engine Gateway
+BO_ 100 Frame : 8 Engine
+ SG_ A : 0|8@1+ (1,0) [0|255] "" Gateway,Dashboard
+BO_ 200 Other : 8 Brakes"#,
+        );
+
+        let diagnostics = lib.lint();
+        assert_eq!(
+            checks(&lib),
+            vec![Check::UndefinedNode, Check::UndefinedNode]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "node Dashboard of Frame.A is not defined in BU_"
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "warning[undefined-node]: node Dashboard of Frame.A is not defined in BU_"
+        );
+    }
+}
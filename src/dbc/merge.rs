@@ -0,0 +1,317 @@
+//! Combining DBC libraries of several buses or suppliers
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use super::diff::diff_frame;
+use super::{Change, DbcLibrary, Impact};
+use crate::id::CanId;
+
+/// Decides which definition `DbcLibrary::merge` keeps for a frame ID defined in both libraries
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MergePolicy {
+    /// Fail on conflicting definitions and keep the left one of compatible definitions
+    Error,
+    /// Keep the definition of the library merged into
+    PreferLeft,
+    /// Keep the definition of the library merged from
+    PreferRight,
+}
+
+/// Frame ID defined with different names or layouts in two merged libraries
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Conflict {
+    /// ID of the frame as encoded in the DBC file
+    pub frame_id: u32,
+    /// Name of the frame in the library merged into
+    pub left: String,
+    /// Name of the frame in the library merged from
+    pub right: String,
+    /// Breaking changes from the left to the right definition
+    pub changes: Vec<Change>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let changes: Vec<String> = self
+            .changes
+            .iter()
+            .map(|change| match change.signal {
+                Some(ref signal) => format!("{} {}", signal, change.kind),
+                None => change.kind.to_string(),
+            })
+            .collect();
+        write!(
+            f,
+            "conflicting definitions {} and {} of frame 0x{}: {}",
+            self.left,
+            self.right,
+            CanId::from_dbc(self.frame_id),
+            changes.join(", ")
+        )
+    }
+}
+
+/// Error of `DbcLibrary::merge` with `MergePolicy::Error`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MergeError {
+    /// All conflicts found, ordered by frame ID
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.conflicts.as_slice() {
+            [conflict] => write!(f, "{}", conflict),
+            conflicts => write!(f, "{} conflicting frame definitions", conflicts.len()),
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+impl DbcLibrary {
+    /// Adds the frames and nodes of another library.  Frames defined in both libraries conflict
+    /// if their names or layouts differ, see `Impact::Breaking`, and are resolved by the policy.
+    /// Frames only referenced by `CM_ BO_` or `BA_ BO_` entries in one library never conflict,
+    /// their descriptions, attributes and signal entries are added to the definition of the other.
+    /// Returns the conflicts ordered by frame ID.
+    ///
+    /// Frame redefinitions and entries of undefined signals found while reading either library
+    /// are kept, so `DbcLibrary::lint` still reports them after merging.
+    ///
+    /// With `MergePolicy::Error` the library is left unchanged if there are any conflicts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::dbc::{DbcLibrary, MergePolicy};
+    ///
+    /// let mut lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    /// let other = DbcLibrary::from_dbc_file("./tests/data/ff.dbc").unwrap();
+    ///
+    /// let conflicts = lib.merge(other, MergePolicy::Error).unwrap();
+    /// assert!(conflicts.is_empty());
+    /// assert!(lib.get_frame(0x0CF00400).is_some());
+    /// ```
+    pub fn merge(
+        &mut self,
+        mut other: DbcLibrary,
+        policy: MergePolicy,
+    ) -> Result<Vec<Conflict>, MergeError> {
+        let mut conflicts = Vec::new();
+        for right in other.get_frames() {
+            let left = match self.get_frame_by_id(right.get_can_id()) {
+                Some(left) if !left.is_placeholder() && !right.is_placeholder() => left,
+                _ => continue,
+            };
+
+            let mut changes = Vec::new();
+            diff_frame(left, right, &mut changes);
+            changes.retain(|change| change.impact() == Impact::Breaking);
+            if !changes.is_empty() {
+                conflicts.push(Conflict {
+                    frame_id: right.get_id(),
+                    left: left.get_name().to_string(),
+                    right: right.get_name().to_string(),
+                    changes,
+                });
+            }
+        }
+        conflicts.sort_by_key(|conflict| conflict.frame_id);
+
+        if policy == MergePolicy::Error && !conflicts.is_empty() {
+            return Err(MergeError { conflicts });
+        }
+
+        for node in other.nodes_mut().drain(..) {
+            if !self.get_nodes().contains(&node) {
+                self.nodes_mut().push(node);
+            }
+        }
+
+        let redefined = other.redefined_frames_mut().drain(..);
+        self.redefined_frames_mut().extend(redefined);
+        for dangling in other.dangling_signals_mut().drain(..) {
+            if !self.dangling_signals().contains(&dangling) {
+                self.dangling_signals_mut().push(dangling);
+            }
+        }
+
+        let frames = self.frames_mut();
+        for (id, mut frame) in other.frames_mut().drain() {
+            let merged = match frames.remove(&id) {
+                None => frame,
+                // frames only referenced by `CM_ BO_` or `BA_ BO_` complete the other definition
+                Some(mut left) if frame.is_placeholder() => {
+                    left.fill_in(frame);
+                    left
+                }
+                Some(left) if left.is_placeholder() => {
+                    frame.fill_in(left);
+                    frame
+                }
+                Some(_) if policy == MergePolicy::PreferRight => frame,
+                Some(left) => left,
+            };
+            frames.insert(id, merged);
+        }
+
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbc::{ChangeKind, Check};
+
+    const POWERTRAIN: &str = r#"
+BU_: Engine Dashboard
+BO_ 100 Status : 8 Engine
+ SG_ Speed : 8|16@1+ (0.125,0) [0|8031.88] "rpm" Dashboard
+BO_ 200 Gear : 2 Engine
+ SG_ Selected : 0|4@1+ (1,0) [0|15] "" Dashboard
+"#;
+
+    const BODY: &str = r#"
+BU_: Dashboard Door
+BO_ 100 Status : 8 Engine
+ SG_ Speed : 8|16@1+ (0.125,0) [0|8031.88] "1/min" Dashboard
+BO_ 200 GearLever : 2 Engine
+ SG_ Selected : 0|8@1+ (1,0) [0|15] "" Dashboard
+BO_ 300 Door : 1 Door
+ SG_ Open : 0|1@1+ (1,0) [0|1] "" Dashboard
+"#;
+
+    #[test]
+    fn test_merge_prefer_left() {
+        let mut lib = DbcLibrary::from_dbc_str(POWERTRAIN);
+        let conflicts = lib
+            .merge(DbcLibrary::from_dbc_str(BODY), MergePolicy::PreferLeft)
+            .unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].frame_id, 200);
+        assert_eq!(conflicts[0].left, "Gear");
+        assert_eq!(conflicts[0].right, "GearLever");
+        let kinds: Vec<ChangeKind> = conflicts[0].changes.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ChangeKind::FrameRenamed, ChangeKind::SignalLayoutChanged]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "conflicting definitions Gear and GearLever of frame 0x0C8: frame renamed, \
+             Selected layout changed"
+        );
+
+        assert_eq!(lib.len(), 3);
+        assert_eq!(lib.get_frame(200).unwrap().get_name(), "Gear");
+        assert_eq!(lib.get_frame(300).unwrap().get_name(), "Door");
+        let unit = &lib.get_signal("Speed").unwrap().get_definition().units;
+        assert_eq!(unit, "rpm");
+        assert_eq!(lib.get_nodes(), ["Engine", "Dashboard", "Door"]);
+    }
+
+    #[test]
+    fn test_merge_prefer_right() {
+        let mut lib = DbcLibrary::from_dbc_str(POWERTRAIN);
+        let conflicts = lib
+            .merge(DbcLibrary::from_dbc_str(BODY), MergePolicy::PreferRight)
+            .unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(lib.get_frame(200).unwrap().get_name(), "GearLever");
+        let unit = &lib.get_signal("Speed").unwrap().get_definition().units;
+        assert_eq!(unit, "1/min");
+    }
+
+    #[test]
+    fn test_merge_error() {
+        let mut lib = DbcLibrary::from_dbc_str(POWERTRAIN);
+        let error = lib
+            .merge(DbcLibrary::from_dbc_str(BODY), MergePolicy::Error)
+            .unwrap_err();
+
+        assert_eq!(error.conflicts.len(), 1);
+        assert_eq!(lib.len(), 2);
+        assert_eq!(lib.get_nodes(), ["Engine", "Dashboard"]);
+
+        // compatible definitions of a frame do not conflict
+        let body = BODY
+            .replace("GearLever", "Gear")
+            .replace("0|8@1+", "0|4@1+");
+        let conflicts = lib
+            .merge(DbcLibrary::from_dbc_str(&body), MergePolicy::Error)
+            .unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(lib.len(), 3);
+    }
+
+    #[test]
+    fn test_merge_placeholder_frames() {
+        let comments = r#"
+CM_ BO_ 100 "Engine status";
+CM_ SG_ 100 Speed "Crankshaft speed";
+BA_ "GenMsgCycleTime" BO_ 100 10;
+CM_ BO_ 400 "Not defined anywhere";
+"#;
+        let mut lib = DbcLibrary::from_dbc_str(POWERTRAIN);
+        let conflicts = lib
+            .merge(DbcLibrary::from_dbc_str(comments), MergePolicy::Error)
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        let status = lib.get_frame(100).unwrap();
+        assert_eq!(status.get_name(), "Status");
+        assert_eq!(status.get_description(), Some("Engine status"));
+        assert_eq!(status.get_attribute("GenMsgCycleTime"), "10");
+        let speed = status.get_signal("Speed").unwrap();
+        assert_eq!(speed.get_definition().units, "rpm");
+        assert_eq!(speed.get_description(), Some("Crankshaft speed"));
+        assert_eq!(lib.get_frame(400).unwrap().get_name(), "");
+
+        // the same with the placeholder in the library merged into
+        let mut lib = DbcLibrary::from_dbc_str(comments);
+        let conflicts = lib
+            .merge(DbcLibrary::from_dbc_str(POWERTRAIN), MergePolicy::Error)
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        let status = lib.get_frame(100).unwrap();
+        assert_eq!(status.get_name(), "Status");
+        assert_eq!(status.get_description(), Some("Engine status"));
+        assert_eq!(
+            status.get_signal("Speed").unwrap().get_description(),
+            Some("Crankshaft speed")
+        );
+    }
+
+    #[test]
+    fn test_merge_then_lint() {
+        let mut lib = DbcLibrary::from_dbc_str(POWERTRAIN);
+        let other = DbcLibrary::from_dbc_str(
+            r#"BO_ 300 Door : 1 Engine
+ SG_ Open : 0|1@1+ (1,0) [0|1] "" Dashboard
+BO_ 300 Hatch : 1 Engine
+ SG_ Locked : 1|1@1+ (1,0) [0|1] "" Dashboard
+CM_ SG_ 400 Closed "No such frame";
+"#,
+        );
+        lib.merge(other, MergePolicy::Error).unwrap();
+
+        let diagnostics = lib.lint();
+        let checks: Vec<Check> = diagnostics.iter().map(|d| d.check).collect();
+        assert_eq!(
+            checks,
+            vec![Check::DuplicateFrameId, Check::DanglingReference]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "frames Door and Hatch have the same ID 0x12C"
+        );
+        assert_eq!(diagnostics[1].frame_id, Some(400));
+    }
+}
@@ -5,12 +5,38 @@
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::io;
 use std::str::FromStr;
 
+use crate::codec::SignalDescriptor;
+
+pub mod cache;
+mod diff;
 mod library;
+mod lint;
+mod merge;
 mod parser;
+#[cfg(feature = "serde")]
+pub mod schema;
 
+pub use self::diff::{Change, ChangeKind, Impact};
 pub use self::library::{DbcFrame, DbcLibrary, DbcSignal};
+pub use self::lint::{Check, Diagnostic, Severity};
+pub use self::merge::{Conflict, MergeError, MergePolicy};
+
+/// Node name DBC files use for frames without sender and signals without receiver
+pub(crate) const NO_NODE: &str = "Vector__XXX";
+
+/// Attribute holding the cycle time of a frame in DBC files
+pub(crate) const CYCLE_TIME: &str = "GenMsgCycleTime";
+
+/// Returns an `io::Error` for malformed input
+pub(crate) fn invalid<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[doc(hidden)]
@@ -20,6 +46,10 @@ pub struct DbcVersion(pub String);
 #[doc(hidden)]
 pub struct BusConfiguration(pub f32);
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[doc(hidden)]
+pub struct DbcNodes(pub Vec<String>);
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 /// Container for CAN frame definition from DBC
 pub struct DbcFrameDefinition {
@@ -49,7 +79,10 @@ pub struct DbcMessageAttribute {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-/// Container for CAN signal definition from DBC
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+/// Container for CAN signal definition from DBC, created with `DbcSignalDefinition::new` outside
+/// of this crate
 pub struct DbcSignalDefinition {
     /// Signal name
     pub name: String,
@@ -73,6 +106,78 @@ pub struct DbcSignalDefinition {
     pub units: String,
     /// Nodes that receive the signal, seperated by commas
     pub receiving_node: String,
+    /// Role of the signal in a multiplexed frame
+    pub multiplexing: Multiplexing,
+}
+
+impl DbcSignalDefinition {
+    /// Creates the definition of a signal without range, unit and multiplexing, received by no
+    /// node
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::dbc::DbcSignalDefinition;
+    ///
+    /// let mut definition =
+    ///     DbcSignalDefinition::new("Engine_Speed", 24, 16, true, false, 0.125, 0.0);
+    /// definition.max_value = 8031.88;
+    /// definition.units = "rpm".to_string();
+    /// ```
+    pub fn new(
+        name: &str,
+        start_bit: usize,
+        bit_len: usize,
+        little_endian: bool,
+        signed: bool,
+        scale: f32,
+        offset: f32,
+    ) -> Self {
+        DbcSignalDefinition {
+            name: name.to_string(),
+            start_bit,
+            bit_len,
+            little_endian,
+            signed,
+            scale,
+            offset,
+            min_value: 0.0,
+            max_value: 0.0,
+            units: String::new(),
+            receiving_node: NO_NODE.to_string(),
+            multiplexing: Multiplexing::None,
+        }
+    }
+
+    /// Returns the position, byte order and scaling of the signal for the functions of the
+    /// `codec` module
+    pub fn descriptor(&self) -> SignalDescriptor<'_> {
+        SignalDescriptor::new(
+            &self.name,
+            self.start_bit,
+            self.bit_len,
+            self.little_endian,
+            self.signed,
+            self.scale,
+            self.offset,
+        )
+    }
+}
+
+/// Role of a signal in a multiplexed frame
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Multiplexing {
+    /// Signal is part of every frame
+    #[default]
+    None,
+    /// Signal selects which multiplexed signals a frame contains (`M`)
+    Multiplexor,
+    /// Signal is only part of frames whose multiplexor has the given value (`m<value>`)
+    Multiplexed(u32),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -92,6 +197,14 @@ pub struct DbcSignalAttribute {
     pub value: String,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[doc(hidden)]
+pub struct DbcValueDescription {
+    pub id: u32,
+    pub signal_name: String,
+    pub values: Vec<(i64, String)>,
+}
+
 /// Composed DBC entry.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Entry {
@@ -101,8 +214,9 @@ pub enum Entry {
     /// `BS_: <Speed>`
     BusConfiguration(BusConfiguration),
 
+    /// `BU_: [node] [node] ...`
+    CanNodes(DbcNodes),
     // TODO: ??
-    // CanNodes,
     // `CM_ BU_ [can id] [signal name] "[description]"`
     // CanNodesDescription,
     // CanNodesAttribute,
@@ -119,6 +233,8 @@ pub enum Entry {
     SignalDescription(DbcSignalDescription),
     /// `BA_ "[attribute name]" SG_ [node|can id] [signal name] [attribute value];`
     SignalAttribute(DbcSignalAttribute),
+    /// `VAL_ [can id] [signal name] [value] "[label]" ... ;`
+    ValueDescription(DbcValueDescription),
 
     // `CM_ [BU_|BO_|SG_] [can id] [signal name] "[description]"`
     // Description, -- flatten subtypes instead
@@ -142,12 +258,14 @@ impl Entry {
         match self {
             Entry::Version(_) => EntryType::Version,
             Entry::BusConfiguration(_) => EntryType::BusConfiguration,
+            Entry::CanNodes(_) => EntryType::CanNodes,
             Entry::MessageDefinition(_) => EntryType::MessageDefinition,
             Entry::MessageDescription(_) => EntryType::MessageDescription,
             Entry::MessageAttribute(_) => EntryType::MessageAttribute,
             Entry::SignalDefinition(_) => EntryType::SignalDefinition,
             Entry::SignalDescription(_) => EntryType::SignalDescription,
             Entry::SignalAttribute(_) => EntryType::SignalAttribute,
+            Entry::ValueDescription(_) => EntryType::ValueDescription,
             Entry::Unknown(_) => EntryType::Unknown,
         }
     }
@@ -168,7 +286,7 @@ pub enum EntryType {
 
     BusConfiguration,
 
-    // CanNodes,
+    CanNodes,
     // CanNodesDescription,
     // CanNodesAttribute
 
@@ -183,6 +301,8 @@ pub enum EntryType {
     SignalLongName,
 //    SignalAttributeDefinition,
 
+    ValueDescription,
+
     // AttributeDefinition,
     // AttributeDefault,
     // Attribute
@@ -196,12 +316,14 @@ impl Display for EntryType {
         let entry_str = match *self {
             EntryType::Version => "Version",
             EntryType::BusConfiguration => "BusConfiguration",
+            EntryType::CanNodes => "CanNodes",
             EntryType::MessageDefinition => "MessageDefinition",
             EntryType::MessageDescription => "MessageDescription",
             EntryType::MessageAttribute => "MessageAttribute",
             EntryType::SignalDefinition => "SignalDefinition",
             EntryType::SignalDescription => "SignalDescription",
             EntryType::SignalAttribute => "SignalAttribute",
+            EntryType::ValueDescription => "ValueDescription",
 
             EntryType::Unknown => "Unknown",
             EntryType::SignalLongName => "SignalLongName",
@@ -301,11 +423,30 @@ impl FromStr for Entry {
     }
 }
 
-/// Probably some spec to determine a type when generating structs
-/// Here an enum will be dispatched instead (e.g., VAL_)
+/// Value table of a signal, mapping raw values to labels (`VAL_`)
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct ValueDefinition {
-    values: Vec<String>,
+    values: Vec<(i64, String)>,
+}
+
+impl ValueDefinition {
+    /// Creates a value table from pairs of raw values and labels
+    pub fn new(values: Vec<(i64, String)>) -> Self {
+        ValueDefinition { values }
+    }
+
+    /// Returns the label of a raw value
+    pub fn get_label(&self, value: i64) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(raw, _)| *raw == value)
+            .map(|(_, label)| label.as_str())
+    }
+
+    /// Returns all pairs of raw values and labels in the order of the DBC file
+    pub fn get_values(&self) -> &[(i64, String)] {
+        &self.values
+    }
 }
 
 #[doc(hidden)]
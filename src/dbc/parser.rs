@@ -3,12 +3,15 @@
 use regex::Regex;
 
 use super::{
-    DbcFrameDefinition, DbcMessageAttribute, DbcMessageDescription, DbcSignalAttribute,
-    DbcSignalDefinition, DbcSignalDescription, Entry,
+    DbcFrameDefinition, DbcMessageAttribute, DbcMessageDescription, DbcNodes, DbcSignalAttribute,
+    DbcSignalDefinition, DbcSignalDescription, DbcValueDescription, Entry, Multiplexing,
 };
 type LazyRegex = once_cell::sync::Lazy<Regex>;
 
 pub fn parse_dbc(line: &str) -> Option<Entry> {
+    if let Some(entry) = parse_nodes(line) {
+        return Some(Entry::CanNodes(entry));
+    }
     if let Some(entry) = parse_message_definition(line) {
         return Some(Entry::MessageDefinition(entry));
     }
@@ -25,7 +28,26 @@ pub fn parse_dbc(line: &str) -> Option<Entry> {
         return Some(Entry::SignalDescription(entry));
     }
 
-    parse_signal_attribute(line).map(Entry::SignalAttribute)
+    if let Some(entry) = parse_signal_attribute(line) {
+        return Some(Entry::SignalAttribute(entry));
+    }
+
+    parse_value_description(line).map(Entry::ValueDescription)
+}
+
+fn parse_nodes(line: &str) -> Option<DbcNodes> {
+    static RE: LazyRegex = LazyRegex::new(|| Regex::new(r"^BU_\s*:(?P<nodes>.*)").unwrap());
+
+    RE.captures(line).map(|cap| {
+        DbcNodes(
+            cap.name("nodes")
+                .unwrap()
+                .as_str()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+        )
+    })
 }
 
 fn parse_message_definition(line: &str) -> Option<DbcFrameDefinition> {
@@ -93,7 +115,7 @@ fn parse_message_attribute(line: &str) -> Option<DbcMessageAttribute> {
 fn parse_signal_definition(line: &str) -> Option<DbcSignalDefinition> {
     static RE: LazyRegex = LazyRegex::new(|| {
         Regex::new(
-            r#" SG_ (?P<name>\S*)[ \t]((?P<multiplexed>m\d+)|(?P<multiplexor>M))? ?:[ ]?(?P<start_bit>\d+)\|(?P<bit_len>\d+)@(?P<little_endian>\d)(?P<is_signed>[+-]) \((?P<scale>-?\d+(\.\d+)?(e-?\d+)?),(?P<offset>-?\d+(\.\d+)?(e-?\d+)?)\) \[(?P<min_value>-?\d+(\.\d+)?(e-?\d+)?)\|(?P<max_value>-?\d+(\.\d+)?(e-?\d+)?)\] "(?P<units>.*)" (?P<receiving_node>.*)"#,
+            r#" SG_ (?P<name>\S*)[ \t]((?P<multiplexed>m\d+)M?|(?P<multiplexor>M))? ?:[ ]?(?P<start_bit>\d+)\|(?P<bit_len>\d+)@(?P<little_endian>\d)(?P<is_signed>[+-]) \((?P<scale>-?\d+(\.\d+)?([eE][-+]?\d+)?),(?P<offset>-?\d+(\.\d+)?([eE][-+]?\d+)?)\) \[(?P<min_value>-?\d+(\.\d+)?([eE][-+]?\d+)?)\|(?P<max_value>-?\d+(\.\d+)?([eE][-+]?\d+)?)\] "(?P<units>.*)" (?P<receiving_node>.*)"#,
         )
         .unwrap()
     });
@@ -143,6 +165,13 @@ fn parse_signal_definition(line: &str) -> Option<DbcSignalDefinition> {
             .name("receiving_node")
             .map(|receving_node| receving_node.as_str().to_string())
             .unwrap(),
+        multiplexing: match (cap.name("multiplexor"), cap.name("multiplexed")) {
+            (Some(_), _) => Multiplexing::Multiplexor,
+            (None, Some(value)) => value.as_str()[1..]
+                .parse::<u32>()
+                .map_or(Multiplexing::None, Multiplexing::Multiplexed),
+            (None, None) => Multiplexing::None,
+        },
     })
 }
 
@@ -190,6 +219,36 @@ fn parse_signal_attribute(line: &str) -> Option<DbcSignalAttribute> {
     })
 }
 
+fn parse_value_description(line: &str) -> Option<DbcValueDescription> {
+    static RE: LazyRegex = LazyRegex::new(|| {
+        Regex::new(r#"^VAL_ (?P<id>\d+) (?P<name>\w+)[ \t](?P<values>(\s*-?\d+\s+"[^"]*")*)\s*;"#)
+            .unwrap()
+    });
+    static VALUE_RE: LazyRegex =
+        LazyRegex::new(|| Regex::new(r#"(?P<value>-?\d+)\s+"(?P<label>[^"]*)""#).unwrap());
+
+    RE.captures(line).and_then(|cap| {
+        let values = VALUE_RE
+            .captures_iter(cap.name("values").unwrap().as_str())
+            .map(|value| {
+                value["value"]
+                    .parse::<i64>()
+                    .ok()
+                    .map(|raw| (raw, value["label"].to_string()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(DbcValueDescription {
+            id: cap["id"].parse::<u32>().ok()?,
+            signal_name: cap
+                .name("name")
+                .map(|name| name.as_str().to_string())
+                .unwrap(),
+            values,
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +268,7 @@ mod tests {
             max_value: 8031.88,
             units: "rpm".to_string(),
             receiving_node: "Vector__XXX".to_string(),
+            multiplexing: Multiplexing::None,
         };
 
         assert_eq!(
@@ -293,4 +353,94 @@ mod tests {
             attribute
         );
     }
+
+    #[test]
+    fn test_value_description() {
+        let description = DbcValueDescription {
+            id: 1282,
+            signal_name: "FSG_DV_Steering_state".to_string(),
+            values: vec![(1, "available".to_string()), (0, "unavailable".to_string())],
+        };
+
+        assert_eq!(
+            parse_value_description(
+                r#"VAL_ 1282 FSG_DV_Steering_state 1 "available" 0 "unavailable" ;"#
+            )
+            .unwrap(),
+            description
+        );
+        assert!(parse_value_description(r#"VAL_TABLE_ States 1 "On" 0 "Off" ;"#).is_none());
+    }
+
+    #[test]
+    fn test_value_description_edge_cases() {
+        assert_eq!(
+            parse_value_description(r#"VAL_ 100 Gear -1 "Reverse" 0 "Neutral";"#)
+                .unwrap()
+                .values,
+            vec![(-1, "Reverse".to_string()), (0, "Neutral".to_string())]
+        );
+        assert_eq!(
+            parse_value_description(r#"VAL_ 100 Gear ;"#)
+                .unwrap()
+                .values,
+            vec![]
+        );
+        assert_eq!(
+            parse_value_description(r#"VAL_ 100 Gear 1 "" ;"#)
+                .unwrap()
+                .values,
+            vec![(1, "".to_string())]
+        );
+
+        // environment variables have no frame ID
+        assert!(parse_value_description(r#"VAL_ EnvVar 0 "Off" 1 "On" ;"#).is_none());
+        assert!(parse_value_description(r#"VAL_ 4294967296 Gear 1 "On" ;"#).is_none());
+        assert!(parse_value_description(r#"VAL_ 100 Gear 99999999999999999999 "On" ;"#).is_none());
+        assert!(parse_value_description(r#"VAL_ 100 Gear 1 "On""#).is_none());
+    }
+
+    #[test]
+    fn test_multiplexed_signal_definition() {
+        let multiplexor = parse_signal_definition(
+            r#" SG_ FF_BMS_Control_mux M : 0|8@1+ (1,0) [0|256] "mux"  FF_BMS"#,
+        )
+        .unwrap();
+        assert_eq!(multiplexor.multiplexing, Multiplexing::Multiplexor);
+
+        let multiplexed = parse_signal_definition(
+            r#" SG_ FF_BMS_Control_TS m101 : 8|8@1+ (1,0) [0|256] ""  FF_BMS"#,
+        )
+        .unwrap();
+        assert_eq!(multiplexed.name, "FF_BMS_Control_TS");
+        assert_eq!(multiplexed.multiplexing, Multiplexing::Multiplexed(101));
+
+        // extended multiplexing, only the multiplexor value of the signal itself is kept
+        let nested = parse_signal_definition(r#" SG_ A m1M : 8|8@1+ (1,0) [0|255] "" X"#).unwrap();
+        assert_eq!(nested.multiplexing, Multiplexing::Multiplexed(1));
+    }
+
+    #[test]
+    fn test_signal_definition_exponents() {
+        let signal = parse_signal_definition(
+            r#" SG_ Current : 0|16@1- (1E-005,0) [-1.5E+2|1.5e2] "A" Vector__XXX"#,
+        )
+        .unwrap();
+        assert_eq!(signal.scale, 1e-5);
+        assert_eq!(signal.min_value, -150.0);
+        assert_eq!(signal.max_value, 150.0);
+    }
+
+    #[test]
+    fn test_nodes() {
+        assert_eq!(
+            parse_nodes("BU_: FF_BMS FF_DB RES").unwrap(),
+            DbcNodes(vec![
+                "FF_BMS".to_string(),
+                "FF_DB".to_string(),
+                "RES".to_string()
+            ])
+        );
+        assert_eq!(parse_nodes("BU_:").unwrap(), DbcNodes(Vec::new()));
+    }
 }
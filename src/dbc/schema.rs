@@ -0,0 +1,400 @@
+//! Serialization of the DBC model, behind the `serde` feature
+//!
+//! `DbcLibrary`, `DbcFrame`, `DbcSignal`, `DbcSignalDefinition`, `Multiplexing` and
+//! `ValueDefinition` implement `Serialize` and `Deserialize`.  The layout does not follow the
+//! private fields of the types but the schema below, which only changes with a new major version
+//! of the crate.  Frames are ordered by ID, signals by start bit and name, attributes by name, so
+//! serializing the same library twice gives the same output.
+//!
+//! In JSON, a library is an object with the node names and the frames:
+//!
+//! ```json
+//! {
+//!   "nodes": ["Engine", "Gateway"],
+//!   "frames": [
+//!     {
+//!       "id": 217056256,
+//!       "extended": true,
+//!       "name": "EEC1",
+//!       "length": 8,
+//!       "sender": "Engine",
+//!       "description": "Electronic Engine Controller 1",
+//!       "attributes": { "GenMsgCycleTime": "100" },
+//!       "signals": [
+//!         {
+//!           "name": "Engine_Speed",
+//!           "start_bit": 24,
+//!           "bit_len": 16,
+//!           "little_endian": true,
+//!           "signed": false,
+//!           "scale": 0.125,
+//!           "offset": 0.0,
+//!           "min_value": 0.0,
+//!           "max_value": 8031.875,
+//!           "units": "rpm",
+//!           "receiving_node": "Gateway",
+//!           "multiplexing": "none",
+//!           "description": "Actual engine speed",
+//!           "attributes": { "SPN": "190" },
+//!           "values": [{ "value": 65535, "label": "NotAvailable" }]
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! * `id` is the ID as seen on the bus, `extended` is true for 29-bit identifiers
+//! * `multiplexing` is `"none"`, `"multiplexor"` or `{ "multiplexed": <multiplexor value> }`
+//! * `description`, `attributes` and `values` are left out if the frame or signal has none,
+//!   and are optional when deserializing, as are `nodes` and `extended` (default `false`)
+//! * the signal fields up to `multiplexing` are the serialization of `DbcSignalDefinition`,
+//!   `values` the one of `ValueDefinition`
+//!
+//! Signals only referenced by `CM_` or `BA_` entries, without a `SG_` definition, are not
+//! serialized.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, ValueDefinition};
+use crate::id::CanId;
+
+#[derive(Serialize, Deserialize)]
+struct Library {
+    #[serde(default)]
+    nodes: Vec<String>,
+    frames: Vec<Frame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Frame {
+    id: u32,
+    #[serde(default)]
+    extended: bool,
+    name: String,
+    length: u32,
+    sender: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    attributes: BTreeMap<String, String>,
+    signals: Vec<Signal>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Signal {
+    #[serde(flatten)]
+    definition: DbcSignalDefinition,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    attributes: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    values: Option<ValueDefinition>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Value {
+    value: i64,
+    label: String,
+}
+
+impl From<&DbcLibrary> for Library {
+    fn from(lib: &DbcLibrary) -> Self {
+        let mut frames: Vec<&DbcFrame> = lib.get_frames();
+        frames.sort_by_key(|frame| frame.get_can_id());
+
+        Library {
+            nodes: lib.get_nodes().to_vec(),
+            frames: frames.into_iter().map(Frame::from).collect(),
+        }
+    }
+}
+
+impl From<&DbcFrame> for Frame {
+    fn from(frame: &DbcFrame) -> Self {
+        let mut signals: Vec<&DbcSignal> = frame
+            .get_signals()
+            .into_iter()
+            .filter(|signal| signal.definition().is_some())
+            .collect();
+        signals.sort_by(|a, b| {
+            let (a, b) = (a.get_definition(), b.get_definition());
+            (a.start_bit, &a.name).cmp(&(b.start_bit, &b.name))
+        });
+
+        let id = frame.get_can_id();
+        Frame {
+            id: id.raw(),
+            extended: matches!(id, CanId::Extended(_)),
+            name: frame.get_name().to_string(),
+            length: frame.get_message_len(),
+            sender: frame.get_sending_node().to_string(),
+            description: frame.get_description().map(str::to_string),
+            attributes: sorted(frame.get_attributes()),
+            signals: signals.into_iter().map(Signal::from).collect(),
+        }
+    }
+}
+
+impl From<&DbcSignal> for Signal {
+    fn from(signal: &DbcSignal) -> Self {
+        Signal {
+            definition: signal.get_definition().clone(),
+            description: signal.get_description().map(str::to_string),
+            attributes: sorted(signal.get_attributes()),
+            values: signal.get_value_definition().cloned(),
+        }
+    }
+}
+
+fn sorted(attributes: &HashMap<String, String>) -> BTreeMap<String, String> {
+    attributes
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+impl TryFrom<Library> for DbcLibrary {
+    type Error = String;
+
+    fn try_from(library: Library) -> Result<Self, String> {
+        let mut lib = DbcLibrary::default();
+        *lib.nodes_mut() = library.nodes;
+
+        for frame in library.frames {
+            let frame = DbcFrame::try_from(frame)?;
+            let id = frame.get_can_id();
+            if lib.frames_mut().insert(id, frame).is_some() {
+                return Err(format!("duplicate frame ID 0x{}", id));
+            }
+        }
+
+        Ok(lib)
+    }
+}
+
+impl TryFrom<Frame> for DbcFrame {
+    type Error = String;
+
+    fn try_from(frame: Frame) -> Result<Self, String> {
+        let id = match CanId::new(frame.id, frame.extended) {
+            Some(id) => id,
+            None => return Err(format!("invalid ID {} of frame {}", frame.id, frame.name)),
+        };
+
+        let mut signals = HashMap::new();
+        for signal in frame.signals {
+            let name = signal.definition.name.clone();
+            if signals
+                .insert(name.clone(), DbcSignal::from(signal))
+                .is_some()
+            {
+                return Err(format!("duplicate signal {} in frame {}", name, frame.name));
+            }
+        }
+
+        Ok(DbcFrame::new(
+            frame.name,
+            id.to_dbc(),
+            frame.length,
+            frame.sender,
+            frame.attributes.into_iter().collect(),
+            frame.description,
+            signals,
+        ))
+    }
+}
+
+impl From<Signal> for DbcSignal {
+    fn from(signal: Signal) -> Self {
+        DbcSignal::new(
+            Some(signal.definition),
+            signal.description,
+            signal.attributes.into_iter().collect(),
+            signal.values,
+        )
+    }
+}
+
+impl Serialize for DbcLibrary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Library::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DbcLibrary {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DbcLibrary::try_from(Library::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for DbcFrame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Frame::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DbcFrame {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DbcFrame::try_from(Frame::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for DbcSignal {
+    /// Fails for signals without definition
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.definition().is_none() {
+            return Err(serde::ser::Error::custom("signal without definition"));
+        }
+        Signal::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DbcSignal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(DbcSignal::from(Signal::deserialize(deserializer)?))
+    }
+}
+
+impl Serialize for ValueDefinition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.get_values().iter().map(|(value, label)| Value {
+            value: *value,
+            label: label.clone(),
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueDefinition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<Value>::deserialize(deserializer)?;
+        Ok(ValueDefinition::new(
+            values
+                .into_iter()
+                .map(|value| (value.value, value.label))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DBC: &str = r#"
+BU_: Engine Gateway
+
+BO_ 2364539904 EEC1: 8 Engine
+ SG_ Engine_Speed : 24|16@1+ (0.125,0) [0|8031.875] "rpm" Gateway
+ SG_ Torque_Mode : 0|4@1+ (1,0) [0|15] "" Gateway
+
+BO_ 291 Gear: 2 Gateway
+ SG_ Page M : 0|8@1+ (1,0) [0|255] "" Engine
+ SG_ Selected m1 : 8|8@1- (1,0) [-128|127] "" Engine
+
+CM_ BO_ 2364539904 "Electronic Engine Controller 1";
+CM_ SG_ 2364539904 Engine_Speed "Actual engine speed";
+BA_ "GenMsgCycleTime" BO_ 2364539904 100;
+BA_ "SPN" SG_ 2364539904 Engine_Speed 190;
+VAL_ 2364539904 Engine_Speed 65535 "NotAvailable" ;
+"#;
+
+    #[test]
+    fn test_schema() {
+        let lib = DbcLibrary::from_dbc_str(DBC);
+        let json = serde_json::to_value(&lib).unwrap();
+
+        assert_eq!(json["nodes"], serde_json::json!(["Engine", "Gateway"]));
+        let gear = &json["frames"][0];
+        assert_eq!(gear["id"], 0x123);
+        assert_eq!(gear["extended"], false);
+        assert_eq!(gear["signals"][0]["multiplexing"], "multiplexor");
+        assert_eq!(
+            gear["signals"][1]["multiplexing"],
+            serde_json::json!({ "multiplexed": 1 })
+        );
+        assert!(gear.get("description").is_none());
+
+        assert_eq!(
+            json["frames"][1],
+            serde_json::json!({
+                "id": 0x0CF00400,
+                "extended": true,
+                "name": "EEC1",
+                "length": 8,
+                "sender": "Engine",
+                "description": "Electronic Engine Controller 1",
+                "attributes": { "GenMsgCycleTime": "100" },
+                "signals": [
+                    {
+                        "name": "Torque_Mode",
+                        "start_bit": 0,
+                        "bit_len": 4,
+                        "little_endian": true,
+                        "signed": false,
+                        "scale": 1.0,
+                        "offset": 0.0,
+                        "min_value": 0.0,
+                        "max_value": 15.0,
+                        "units": "",
+                        "receiving_node": "Gateway",
+                        "multiplexing": "none"
+                    },
+                    {
+                        "name": "Engine_Speed",
+                        "start_bit": 24,
+                        "bit_len": 16,
+                        "little_endian": true,
+                        "signed": false,
+                        "scale": 0.125,
+                        "offset": 0.0,
+                        "min_value": 0.0,
+                        "max_value": 8031.875,
+                        "units": "rpm",
+                        "receiving_node": "Gateway",
+                        "multiplexing": "none",
+                        "description": "Actual engine speed",
+                        "attributes": { "SPN": "190" },
+                        "values": [{ "value": 65535, "label": "NotAvailable" }]
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let json = serde_json::to_string(&lib).unwrap();
+        let copy: DbcLibrary = serde_json::from_str(&json).unwrap();
+
+        assert!(lib.diff(&copy).is_empty());
+        assert_eq!(copy.get_nodes(), lib.get_nodes());
+        assert_eq!(serde_json::to_string(&copy).unwrap(), json);
+
+        let signal = copy.get_signal("Engine_Speed").unwrap();
+        assert_eq!(
+            signal.get_value_definition(),
+            lib.get_signal("Engine_Speed")
+                .unwrap()
+                .get_value_definition()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let frame = r#"{ "id": 2048, "name": "Status", "length": 1, "sender": "", "signals": [] }"#;
+        let error = serde_json::from_str::<DbcFrame>(frame).unwrap_err();
+        assert_eq!(error.to_string(), "invalid ID 2048 of frame Status");
+
+        let frame: DbcFrame = serde_json::from_str(&frame.replace("2048", "256")).unwrap();
+        let json = serde_json::json!({ "frames": [frame, frame] });
+        let error = serde_json::from_value::<DbcLibrary>(json).unwrap_err();
+        assert_eq!(error.to_string(), "duplicate frame ID 0x100");
+    }
+}
@@ -0,0 +1,214 @@
+//! CSV output of decoded traces
+
+use std::io;
+use std::io::prelude::*;
+
+use super::{Decoder, Layout, Sample};
+use crate::dbc::DbcLibrary;
+use crate::trace::TraceFrame;
+
+/// Decodes a trace and writes it as a CSV table with a header row, returns the underlying
+/// writer.  Columns of the wide layout are named `<frame>.<signal>`, followed by the unit in
+/// brackets if the signal has one.  Values without a label leave the label column empty.  Rows
+/// are written as the frames are read.
+///
+/// Remote and error frames as well as IDs not contained in the library are skipped, read errors
+/// are passed on.
+pub fn write_csv<W, I>(mut writer: W, lib: &DbcLibrary, frames: I, layout: Layout) -> io::Result<W>
+where
+    W: Write,
+    I: IntoIterator<Item = io::Result<TraceFrame>>,
+{
+    let mut decoder = Decoder::new(lib, layout);
+
+    match layout {
+        Layout::Wide => {
+            let mut header = vec!["timestamp".to_string()];
+            for column in &decoder.columns {
+                header.push(match column.unit() {
+                    "" => column.name(),
+                    unit => format!("{} [{}]", column.name(), unit),
+                });
+                if column.has_labels() {
+                    header.push(format!("{}.label", column.name()));
+                }
+            }
+            write_record(&mut writer, &header)?;
+
+            let mut current = vec![None; decoder.columns.len()];
+            for frame in frames {
+                let row = match decoder.decode(&frame?) {
+                    Some(row) => row,
+                    None => continue,
+                };
+                row.fill(&mut current);
+
+                let mut record = vec![format_timestamp(row.timestamp)];
+                for (column, sample) in decoder.columns.iter().zip(&current) {
+                    record.push(sample.map(|s| s.value.to_string()).unwrap_or_default());
+                    if column.has_labels() {
+                        record.push(label(*sample).to_string());
+                    }
+                }
+                write_record(&mut writer, &record)?;
+            }
+        }
+        Layout::Long => {
+            write_record(
+                &mut writer,
+                &["timestamp", "frame", "signal", "value", "unit", "label"],
+            )?;
+
+            for frame in frames {
+                let row = match decoder.decode(&frame?) {
+                    Some(row) => row,
+                    None => continue,
+                };
+                let timestamp = format_timestamp(row.timestamp);
+                for (column, sample) in &row.samples {
+                    let column = &decoder.columns[*column];
+                    write_record(
+                        &mut writer,
+                        &[
+                            timestamp.as_str(),
+                            column.frame.get_name(),
+                            &column.signal.get_definition().name,
+                            &sample.value.to_string(),
+                            column.unit(),
+                            label(Some(*sample)),
+                        ],
+                    )?;
+                }
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(writer)
+}
+
+fn label(sample: Option<Sample<'_>>) -> &str {
+    sample.and_then(|sample| sample.label).unwrap_or_default()
+}
+
+/// Timestamps keep microsecond resolution, as most trace formats do
+fn format_timestamp(timestamp: f64) -> String {
+    format!("{:.6}", timestamp)
+}
+
+/// Writes a line of fields, quoting fields that contain separators, quotes or line breaks
+fn write_record<W, S>(writer: &mut W, fields: &[S]) -> io::Result<()>
+where
+    W: Write,
+    S: AsRef<str>,
+{
+    let line = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    writeln!(writer, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::CanId;
+    use crate::trace::candump::CandumpReader;
+    use crate::trace::Channel;
+
+    fn frame(timestamp: f64, data: &[u8]) -> io::Result<TraceFrame> {
+        Ok(TraceFrame::new(
+            timestamp,
+            Channel::Index(1),
+            CanId::Extended(0x0CF00400),
+            data.to_vec(),
+        ))
+    }
+
+    #[test]
+    fn test_write_wide() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let frames = vec![
+            frame(1.0, &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]),
+            frame(1.5, &[0x11, 0x22, 0x33, 0xFF, 0xFF, 0x66, 0x77, 0x88]),
+        ];
+
+        let csv = write_csv(Vec::new(), &lib, frames, Layout::Wide).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "timestamp,EEC1.Engine_Speed [rpm],EEC1.Engine_Speed.label\n\
+             1.000000,2728.5,\n\
+             1.500000,8191.875,NotAvailable\n"
+        );
+    }
+
+    #[test]
+    fn test_write_long() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+
+        let csv = write_csv(Vec::new(), &lib, reader, Layout::Long).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "timestamp,frame,signal,value,unit,label\n\
+             1436509052.249713,EEC1,Engine_Speed,2728.5,rpm,\n\
+             1436509052.289713,EEC1,Engine_Speed,2741,rpm,\n"
+        );
+    }
+
+    #[test]
+    fn test_write_wide_forward_filled() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+        let frames = vec![
+            Ok(TraceFrame::new(
+                1.0,
+                Channel::Index(1),
+                CanId::Standard(0x700),
+                vec![0x01, 0x05, 0, 0, 0, 0, 0, 0],
+            )),
+            Ok(TraceFrame::new(
+                2.0,
+                Channel::Index(1),
+                CanId::Standard(0x500),
+                vec![0x00, 0x03],
+            )),
+            Ok(TraceFrame::new(
+                3.0,
+                Channel::Index(1),
+                CanId::Standard(0x700),
+                vec![0x00, 0x00, 0x64, 0, 0, 0, 0, 0],
+            )),
+        ];
+
+        let csv = write_csv(Vec::new(), &lib, frames, Layout::Wide).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].ends_with(
+            "Muxed.Mode,Muxed.Mode.label,Muxed.Pressure [kPa],Muxed.Valve,Muxed.Speed [km/h]"
+        ));
+        // Pressure keeps its value of mode 1 while mode 0 selects Speed
+        assert!(lines[1].starts_with("1.000000,,"));
+        assert!(lines[1].ends_with(",1,Service,10,0,"));
+        assert!(lines[2].starts_with("2.000000,3,"));
+        assert!(lines[2].ends_with(",1,Service,10,0,"));
+        assert!(lines[3].starts_with("3.000000,3,"));
+        assert!(lines[3].ends_with(",0,Drive,10,0,1"));
+    }
+
+    #[test]
+    fn test_quote_fields() {
+        let mut csv = Vec::new();
+        write_record(&mut csv, &["a,b", "say \"hi\"", "plain"]).unwrap();
+        assert_eq!(csv, b"\"a,b\",\"say \"\"hi\"\"\",plain\n");
+    }
+}
@@ -0,0 +1,243 @@
+//! Columnar export of decoded traces
+//!
+//! The signals of a trace are decoded with a `DbcLibrary` and written as a table in one of two
+//! layouts:
+//!
+//! * `Layout::Wide` has a `timestamp` column and one column per signal of the library, ordered
+//!   by frame ID and start bit.  Every decoded frame adds a row, the values of all other signals
+//!   are forward-filled from their last frame and left empty until a signal was first seen.
+//!   Signals with a value table get an additional `<frame>.<signal>.label` column.
+//! * `Layout::Long` has one row per decoded signal value with the columns `timestamp`, `frame`,
+//!   `signal`, `value`, `unit` and `label`.
+//!
+//! Tables are written to CSV with `write_csv`.  With the `parquet` feature, `to_record_batch`
+//! converts them into an Apache Arrow `RecordBatch` and `write_parquet` writes Apache Parquet
+//! files, with the units of the signals stored in the metadata of the wide layout's fields.
+//! `write_csv` and `write_parquet` write the rows while reading the trace, so traces of any
+//! length are exported without holding them in memory.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::export::{write_csv, Layout};
+//! use fastcan::trace::candump::CandumpReader;
+//!
+//! let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+//! let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+//!
+//! let csv = write_csv(Vec::new(), &lib, reader, Layout::Long).unwrap();
+//! let csv = String::from_utf8(csv).unwrap();
+//!
+//! assert!(csv.starts_with("timestamp,frame,signal,value,unit,label\n"));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal};
+use crate::id::CanId;
+use crate::mapper::DecodeMessage;
+use crate::trace::TraceFrame;
+
+mod csv;
+#[cfg(feature = "parquet")]
+mod parquet;
+
+pub use self::csv::write_csv;
+#[cfg(feature = "parquet")]
+pub use self::parquet::{to_record_batch, write_parquet};
+
+/// Shape of an exported table
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Layout {
+    /// One column per signal, forward-filled
+    Wide,
+    /// One row per signal value
+    Long,
+}
+
+/// A signal column of the wide layout
+struct Column<'a> {
+    frame: &'a DbcFrame,
+    signal: &'a DbcSignal,
+}
+
+impl Column<'_> {
+    fn name(&self) -> String {
+        format!(
+            "{}.{}",
+            self.frame.get_name(),
+            self.signal.get_definition().name
+        )
+    }
+
+    fn unit(&self) -> &str {
+        &self.signal.get_definition().units
+    }
+
+    fn has_labels(&self) -> bool {
+        self.signal.get_value_definition().is_some()
+    }
+}
+
+/// A decoded signal value
+#[derive(Clone, Copy)]
+struct Sample<'a> {
+    value: f32,
+    label: Option<&'a str>,
+}
+
+/// Signal values decoded from a single frame, indexed by column
+struct Row<'a> {
+    timestamp: f64,
+    samples: Vec<(usize, Sample<'a>)>,
+}
+
+impl<'a> Row<'a> {
+    /// Updates the last value of every column of the wide layout with the values of this row
+    fn fill(&self, current: &mut [Option<Sample<'a>>]) {
+        for (column, sample) in &self.samples {
+            current[*column] = Some(*sample);
+        }
+    }
+}
+
+/// Decodes the data frames of a trace one at a time.  Signals of a frame are ordered by start
+/// bit.
+struct Decoder<'a> {
+    lib: &'a DbcLibrary,
+    columns: Vec<Column<'a>>,
+    index: HashMap<CanId, Vec<usize>>,
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a decoder for a layout.  The wide layout needs all columns before the first row,
+    /// which are the signals of every frame of the library ordered by frame ID.  The long layout
+    /// adds the columns of a frame when it first appears in the trace.
+    fn new(lib: &'a DbcLibrary, layout: Layout) -> Self {
+        let mut decoder = Decoder {
+            lib,
+            columns: Vec::new(),
+            index: HashMap::new(),
+        };
+        if layout == Layout::Wide {
+            let mut frames = lib.get_frames();
+            frames.sort_by_key(|frame| frame.get_id());
+            for frame in frames {
+                decoder.frame_columns(frame);
+            }
+        }
+        decoder
+    }
+
+    /// Returns the columns of a frame, adding them on first use
+    fn frame_columns(&mut self, frame: &'a DbcFrame) -> &[usize] {
+        let columns = &mut self.columns;
+        self.index.entry(frame.get_can_id()).or_insert_with(|| {
+            let mut signals = frame.get_signals();
+            signals.sort_by(|a, b| {
+                let (a, b) = (a.get_definition(), b.get_definition());
+                (a.start_bit, &a.name).cmp(&(b.start_bit, &b.name))
+            });
+            signals
+                .into_iter()
+                .map(|signal| {
+                    columns.push(Column { frame, signal });
+                    columns.len() - 1
+                })
+                .collect()
+        })
+    }
+
+    /// Decodes a frame of a trace.  Returns `None` for remote and error frames as well as IDs not
+    /// contained in the library, which are skipped.
+    fn decode(&mut self, frame: &TraceFrame) -> Option<Row<'a>> {
+        if !frame.is_data() || frame.data.is_empty() {
+            return None;
+        }
+        let definition = self.lib.get_frame_by_id(frame.id)?;
+        let indices = self.frame_columns(definition).to_vec();
+
+        // signals of other multiplexor values keep their last value
+        let present = definition.get_signals_in_message(&frame.data);
+        let samples = indices
+            .into_iter()
+            .filter(|&column| {
+                present
+                    .iter()
+                    .any(|signal| std::ptr::eq(*signal, self.columns[column].signal))
+            })
+            .filter_map(|column| {
+                let signal = self.columns[column].signal;
+                signal.decode_message(&frame.data[..]).map(|value| {
+                    let label = signal.decode_label(&frame.data);
+                    (column, Sample { value, label })
+                })
+            })
+            .collect();
+
+        Some(Row {
+            timestamp: frame.timestamp,
+            samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::candump::CandumpReader;
+    use crate::trace::Channel;
+
+    #[test]
+    fn test_decode_rows() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+
+        let mut decoder = Decoder::new(&lib, Layout::Wide);
+        assert_eq!(decoder.columns.len(), 1);
+        assert_eq!(decoder.columns[0].name(), "EEC1.Engine_Speed");
+        assert_eq!(decoder.columns[0].unit(), "rpm");
+        assert!(decoder.columns[0].has_labels());
+
+        let rows: Vec<_> = reader
+            .filter_map(|frame| decoder.decode(&frame.unwrap()))
+            .collect();
+        assert_eq!(rows.len(), 2);
+        let mut current = vec![None; decoder.columns.len()];
+        rows[0].fill(&mut current);
+        assert_eq!(current[0].unwrap().value, 2728.5);
+        rows[1].fill(&mut current);
+        assert_eq!(current[0].unwrap().value, 2741.0);
+    }
+
+    #[test]
+    fn test_columns_of_layouts() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+        let frame = TraceFrame::new(
+            0.0,
+            Channel::Index(1),
+            CanId::Standard(0x700),
+            vec![0x01, 0x05, 0, 0, 0, 0, 0, 0],
+        );
+
+        // the wide layout has the columns of all frames from the start
+        let mut decoder = Decoder::new(&lib, Layout::Wide);
+        let names: Vec<_> = decoder.columns.iter().map(Column::name).collect();
+        assert_eq!(names[0], "Short.Flags");
+        assert_eq!(
+            names[names.len() - 4..],
+            ["Muxed.Mode", "Muxed.Pressure", "Muxed.Valve", "Muxed.Speed"]
+        );
+        let row = decoder.decode(&frame).unwrap();
+        let columns: Vec<_> = row.samples.iter().map(|(column, _)| *column).collect();
+        assert_eq!(columns, [names.len() - 4, names.len() - 3, names.len() - 2]);
+
+        // the long layout only has the columns of frames seen so far
+        let mut decoder = Decoder::new(&lib, Layout::Long);
+        assert!(decoder.columns.is_empty());
+        let row = decoder.decode(&frame).unwrap();
+        assert_eq!(decoder.columns.len(), 4);
+        assert_eq!(row.samples.len(), 3);
+    }
+}
@@ -0,0 +1,310 @@
+//! Apache Arrow and Parquet output of decoded traces
+
+use std::collections::HashMap;
+use std::io;
+use std::io::prelude::*;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float32Array, Float64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+
+use super::{Column, Decoder, Layout, Row, Sample};
+use crate::dbc::DbcLibrary;
+use crate::trace::TraceFrame;
+
+/// Number of table rows after which `write_parquet` writes a record batch
+const BATCH_ROWS: usize = 8192;
+
+/// Decodes a trace into an Arrow `RecordBatch`.  Timestamps are `Float64` seconds and values
+/// are `Float32`, labels and names are `Utf8`.  The signal fields of the wide layout are named
+/// `<frame>.<signal>` and carry the unit of the signal in their `unit` metadata.
+///
+/// Remote and error frames as well as IDs not contained in the library are skipped, read errors
+/// are passed on.
+pub fn to_record_batch<I>(lib: &DbcLibrary, frames: I, layout: Layout) -> io::Result<RecordBatch>
+where
+    I: IntoIterator<Item = io::Result<TraceFrame>>,
+{
+    let mut decoder = Decoder::new(lib, layout);
+    let mut rows = Vec::new();
+    for frame in frames {
+        rows.extend(decoder.decode(&frame?));
+    }
+
+    let schema = Arc::new(schema(&decoder.columns, layout));
+    let mut current = vec![None; decoder.columns.len()];
+    record_batch(&decoder.columns, &rows, &mut current, schema, layout)
+}
+
+/// Decodes a trace and writes it as a Parquet file with the schema of `to_record_batch`,
+/// returns the underlying writer.  The rows are written in record batches of fixed size as the
+/// frames are read.
+pub fn write_parquet<W, I>(writer: W, lib: &DbcLibrary, frames: I, layout: Layout) -> io::Result<W>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = io::Result<TraceFrame>>,
+{
+    write_batches(writer, lib, frames, layout, BATCH_ROWS)
+}
+
+fn write_batches<W, I>(
+    writer: W,
+    lib: &DbcLibrary,
+    frames: I,
+    layout: Layout,
+    batch_rows: usize,
+) -> io::Result<W>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = io::Result<TraceFrame>>,
+{
+    let mut decoder = Decoder::new(lib, layout);
+    // the long layout may add columns later, but its schema does not depend on them
+    let schema = Arc::new(schema(&decoder.columns, layout));
+    let mut writer =
+        ArrowWriter::try_new(writer, schema.clone(), None).map_err(io::Error::other)?;
+
+    let mut current = vec![None; decoder.columns.len()];
+    let mut rows = Vec::new();
+    let mut len = 0;
+    for frame in frames {
+        let row = match decoder.decode(&frame?) {
+            Some(row) => row,
+            None => continue,
+        };
+        len += match layout {
+            Layout::Wide => 1,
+            Layout::Long => row.samples.len(),
+        };
+        rows.push(row);
+
+        if len >= batch_rows {
+            let batch = record_batch(
+                &decoder.columns,
+                &rows,
+                &mut current,
+                schema.clone(),
+                layout,
+            )?;
+            writer.write(&batch).map_err(io::Error::other)?;
+            rows.clear();
+            len = 0;
+        }
+    }
+    if !rows.is_empty() {
+        let batch = record_batch(&decoder.columns, &rows, &mut current, schema, layout)?;
+        writer.write(&batch).map_err(io::Error::other)?;
+    }
+
+    writer.into_inner().map_err(io::Error::other)
+}
+
+/// Returns the schema of a layout
+fn schema(columns: &[Column], layout: Layout) -> Schema {
+    let mut fields = vec![Field::new("timestamp", DataType::Float64, false)];
+
+    match layout {
+        Layout::Wide => {
+            for column in columns {
+                let mut metadata = HashMap::new();
+                metadata.insert("unit".to_string(), column.unit().to_string());
+                fields.push(
+                    Field::new(column.name(), DataType::Float32, true).with_metadata(metadata),
+                );
+                if column.has_labels() {
+                    fields.push(Field::new(
+                        format!("{}.label", column.name()),
+                        DataType::Utf8,
+                        true,
+                    ));
+                }
+            }
+        }
+        Layout::Long => {
+            fields.extend(vec![
+                Field::new("frame", DataType::Utf8, false),
+                Field::new("signal", DataType::Utf8, false),
+                Field::new("value", DataType::Float32, false),
+                Field::new("unit", DataType::Utf8, false),
+                Field::new("label", DataType::Utf8, true),
+            ]);
+        }
+    }
+
+    Schema::new(fields)
+}
+
+/// Converts decoded rows into a record batch.  `current` holds the last value of every column
+/// of the wide layout, carried over from the previous batch.
+fn record_batch<'a>(
+    columns: &[Column<'a>],
+    rows: &[Row<'a>],
+    current: &mut [Option<Sample<'a>>],
+    schema: Arc<Schema>,
+    layout: Layout,
+) -> io::Result<RecordBatch> {
+    let mut arrays: Vec<ArrayRef> = Vec::new();
+
+    match layout {
+        Layout::Wide => {
+            arrays.push(Arc::new(Float64Array::from(
+                rows.iter().map(|row| row.timestamp).collect::<Vec<_>>(),
+            )));
+
+            let mut values = vec![Vec::with_capacity(rows.len()); columns.len()];
+            for row in rows {
+                row.fill(current);
+                for (values, sample) in values.iter_mut().zip(current.iter()) {
+                    values.push(*sample);
+                }
+            }
+
+            for (column, values) in columns.iter().zip(values) {
+                arrays.push(Arc::new(Float32Array::from(
+                    values
+                        .iter()
+                        .map(|sample| sample.map(|sample| sample.value))
+                        .collect::<Vec<_>>(),
+                )));
+
+                if column.has_labels() {
+                    arrays.push(Arc::new(StringArray::from(
+                        values
+                            .iter()
+                            .map(|sample| sample.and_then(|sample| sample.label))
+                            .collect::<Vec<_>>(),
+                    )));
+                }
+            }
+        }
+        Layout::Long => {
+            let samples = rows
+                .iter()
+                .flat_map(|row| {
+                    row.samples
+                        .iter()
+                        .map(move |(column, sample)| (row.timestamp, *column, *sample))
+                })
+                .collect::<Vec<_>>();
+
+            arrays.push(Arc::new(Float64Array::from(
+                samples.iter().map(|s| s.0).collect::<Vec<_>>(),
+            )));
+            arrays.push(Arc::new(StringArray::from(
+                samples
+                    .iter()
+                    .map(|s| columns[s.1].frame.get_name())
+                    .collect::<Vec<_>>(),
+            )));
+            arrays.push(Arc::new(StringArray::from(
+                samples
+                    .iter()
+                    .map(|s| columns[s.1].signal.get_definition().name.as_str())
+                    .collect::<Vec<_>>(),
+            )));
+            arrays.push(Arc::new(Float32Array::from(
+                samples.iter().map(|s| s.2.value).collect::<Vec<_>>(),
+            )));
+            arrays.push(Arc::new(StringArray::from(
+                samples
+                    .iter()
+                    .map(|s| columns[s.1].unit())
+                    .collect::<Vec<_>>(),
+            )));
+            arrays.push(Arc::new(StringArray::from(
+                samples.iter().map(|s| s.2.label).collect::<Vec<_>>(),
+            )));
+        }
+    }
+
+    RecordBatch::try_new(schema, arrays).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::candump::CandumpReader;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn test_wide_record_batch() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+
+        let batch = to_record_batch(&lib, reader, Layout::Wide).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 3);
+
+        let schema = batch.schema();
+        let field = schema.field_with_name("EEC1.Engine_Speed").unwrap();
+        assert_eq!(field.metadata()["unit"], "rpm");
+
+        let speed = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .unwrap();
+        assert_eq!(speed.value(0), 2728.5);
+        assert_eq!(speed.value(1), 2741.0);
+        assert_eq!(batch.column(2).null_count(), 2);
+    }
+
+    #[test]
+    fn test_long_record_batch() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+
+        let batch = to_record_batch(&lib, reader, Layout::Long).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let units = batch
+            .column_by_name("unit")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(units.value(1), "rpm");
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+
+        let file = write_parquet(Vec::new(), &lib, reader, Layout::Long).unwrap();
+        assert_eq!(&file[..4], b"PAR1");
+        assert_eq!(&file[file.len() - 4..], b"PAR1");
+    }
+
+    #[test]
+    fn test_write_batches() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+
+        for layout in [Layout::Wide, Layout::Long] {
+            let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+            let expected = to_record_batch(&lib, reader, layout).unwrap();
+
+            // one batch per row, with the forward-filled values carried across batches
+            let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+            let file = write_batches(Vec::new(), &lib, reader, layout, 1).unwrap();
+            let path = std::env::temp_dir().join(format!(
+                "fastcan-export-{}-{:?}.parquet",
+                std::process::id(),
+                layout
+            ));
+            std::fs::write(&path, file).unwrap();
+            let batches: Vec<_> =
+                ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+                    .unwrap()
+                    .build()
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(batches, vec![expected]);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! CAN identifier handling
+//!
+//! DBC files store 29-bit extended identifiers with bit 31 set (e.g. `BO_ 2364539904` for the
+//! bus ID `0x0CF00400`).  `CanId` keeps track of the identifier type so that lookups work with
+//! either representation.
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+/// Flag set in DBC message IDs to mark an extended (29-bit) identifier
+pub const DBC_EXTENDED_FLAG: u32 = 0x8000_0000;
+
+/// Bits set in DBC message IDs of pseudo frames, e.g. `VECTOR__INDEPENDENT_SIG_MSG` holding
+/// signals that are not sent in any frame
+pub const DBC_PSEUDO_FLAGS: u32 = 0x6000_0000;
+
+/// Highest valid standard (11-bit) identifier
+pub const STANDARD_ID_MAX: u32 = 0x7FF;
+
+/// Highest valid extended (29-bit) identifier
+pub const EXTENDED_ID_MAX: u32 = 0x1FFF_FFFF;
+
+/// CAN arbitration ID, either standard (11-bit) or extended (29-bit)
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum CanId {
+    /// Standard 11-bit identifier
+    Standard(u16),
+    /// Extended 29-bit identifier
+    Extended(u32),
+}
+
+impl CanId {
+    /// Creates a `CanId` from the raw bus ID and the extended flag.  Returns `None` if the ID
+    /// does not fit into the identifier type.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::id::CanId;
+    ///
+    /// assert_eq!(CanId::new(0x0CF00400, true), Some(CanId::Extended(0x0CF00400)));
+    /// assert_eq!(CanId::new(0x123, false), Some(CanId::Standard(0x123)));
+    /// assert_eq!(CanId::new(0x800, false), None);
+    /// ```
+    pub fn new(id: u32, extended: bool) -> Option<CanId> {
+        match extended {
+            true if id <= EXTENDED_ID_MAX => Some(CanId::Extended(id)),
+            false if id <= STANDARD_ID_MAX => Some(CanId::Standard(id as u16)),
+            _ => None,
+        }
+    }
+
+    /// Creates a `CanId` from a DBC message ID.  IDs with bit 31 set, as well as IDs too large
+    /// for a standard identifier, are treated as extended.  Bits 29 and 30 are dropped, use
+    /// `CanId::try_from_dbc` to tell pseudo frames apart.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::id::CanId;
+    ///
+    /// assert_eq!(CanId::from_dbc(2364539904), CanId::Extended(0x0CF00400));
+    /// assert_eq!(CanId::from_dbc(1297), CanId::Standard(1297));
+    /// ```
+    pub fn from_dbc(id: u32) -> CanId {
+        if id & DBC_EXTENDED_FLAG != 0 || id > STANDARD_ID_MAX {
+            CanId::Extended(id & EXTENDED_ID_MAX)
+        } else {
+            CanId::Standard(id as u16)
+        }
+    }
+
+    /// Creates a `CanId` from a DBC message ID like `CanId::from_dbc`.  Returns `None` for the
+    /// IDs of pseudo frames, which have bit 29 or 30 set.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::id::CanId;
+    ///
+    /// assert_eq!(CanId::try_from_dbc(2364539904), Some(CanId::Extended(0x0CF00400)));
+    /// // VECTOR__INDEPENDENT_SIG_MSG
+    /// assert_eq!(CanId::try_from_dbc(3221225472), None);
+    /// ```
+    pub fn try_from_dbc(id: u32) -> Option<CanId> {
+        if id & DBC_PSEUDO_FLAGS != 0 {
+            None
+        } else {
+            Some(CanId::from_dbc(id))
+        }
+    }
+
+    /// Returns the ID as encoded in DBC files, with bit 31 set for extended identifiers
+    pub fn to_dbc(self) -> u32 {
+        match self {
+            CanId::Standard(id) => u32::from(id),
+            CanId::Extended(id) => id | DBC_EXTENDED_FLAG,
+        }
+    }
+
+    /// Returns the ID as seen on the bus
+    pub fn raw(self) -> u32 {
+        match self {
+            CanId::Standard(id) => u32::from(id),
+            CanId::Extended(id) => id,
+        }
+    }
+
+    /// Returns true for extended (29-bit) identifiers
+    pub fn is_extended(self) -> bool {
+        match self {
+            CanId::Standard(_) => false,
+            CanId::Extended(_) => true,
+        }
+    }
+}
+
+impl Default for CanId {
+    fn default() -> Self {
+        CanId::Standard(0)
+    }
+}
+
+impl From<u32> for CanId {
+    /// Same as `CanId::from_dbc`
+    fn from(id: u32) -> Self {
+        CanId::from_dbc(id)
+    }
+}
+
+impl Display for CanId {
+    /// Formats the ID in hex the way `candump` does, three digits for standard and eight digits
+    /// for extended identifiers.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            CanId::Standard(id) => write!(f, "{:03X}", id),
+            CanId::Extended(id) => write!(f, "{:08X}", id),
+        }
+    }
+}
@@ -0,0 +1,731 @@
+//! ISO-TP (ISO 15765-2) segmentation and reassembly
+//!
+//! Diagnostic payloads longer than a single CAN frame are split into a first frame followed by
+//! consecutive frames, with the receiver answering with flow control frames.  Short payloads
+//! are sent as a single frame.  Both classic CAN and CAN FD framing are supported, as well as
+//! normal, extended and mixed addressing.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::id::CanId;
+//! use fastcan::isotp::{Addressing, IsoTpReassembler, IsoTpSegmenter};
+//!
+//! let payload: Vec<u8> = (0..20).collect();
+//! let frames = IsoTpSegmenter::new(Addressing::Normal).segment(&payload).unwrap();
+//!
+//! assert_eq!(frames.len(), 3);
+//!
+//! let mut reassembler = IsoTpReassembler::new(Addressing::Normal);
+//! let events: Vec<_> = frames
+//!     .iter()
+//!     .enumerate()
+//!     .flat_map(|(i, frame)| reassembler.push(i as f64 * 0.01, CanId::Standard(0x7E8), frame))
+//!     .collect();
+//!
+//! assert_eq!(events[0].as_ref().unwrap().data, payload);
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::dbc::DbcLibrary;
+use crate::id::CanId;
+use crate::mapper::DecodeFrame;
+
+const SINGLE_FRAME: u8 = 0x0;
+const FIRST_FRAME: u8 = 0x1;
+const CONSECUTIVE_FRAME: u8 = 0x2;
+const FLOW_CONTROL: u8 = 0x3;
+
+/// Frame length of classic CAN
+pub const CLASSIC_FRAME_LEN: usize = 8;
+
+/// Valid CAN FD frame lengths
+pub const FD_FRAME_LENS: [usize; 8] = [8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Padding byte recommended by ISO 15765-2
+pub const DEFAULT_PADDING: u8 = 0xCC;
+
+/// Maximum payload length with a 32-bit first frame length
+pub const MAX_PAYLOAD_LEN: usize = u32::MAX as usize;
+
+/// Addressing format, determines whether the first byte of each frame is an address
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Addressing {
+    /// The whole frame is used for protocol data
+    Normal,
+    /// The first byte is the target address
+    Extended(u8),
+    /// The first byte is the address extension
+    Mixed(u8),
+}
+
+impl Addressing {
+    /// Returns the address byte preceding the protocol data, if any
+    pub fn address(self) -> Option<u8> {
+        match self {
+            Addressing::Normal => None,
+            Addressing::Extended(address) | Addressing::Mixed(address) => Some(address),
+        }
+    }
+
+    fn offset(self) -> usize {
+        match self {
+            Addressing::Normal => 0,
+            _ => 1,
+        }
+    }
+}
+
+/// Flow status of a flow control frame
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FlowStatus {
+    /// Continue to send
+    ContinueToSend,
+    /// Wait for the next flow control frame
+    Wait,
+    /// The payload is too large for the receiver
+    Overflow,
+}
+
+/// Reassembled ISO-TP message
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsoTpMessage {
+    /// Timestamp of the last frame
+    pub timestamp: f64,
+    /// CAN ID the message was received on
+    pub id: CanId,
+    /// Address byte for extended and mixed addressing
+    pub address: Option<u8>,
+    /// Reassembled payload
+    pub data: Vec<u8>,
+}
+
+impl IsoTpMessage {
+    /// Decodes the payload with the frame defined for the message's CAN ID
+    ///
+    /// Returns a HashMap of signal names to signal values, `None` if the library does not
+    /// contain the ID
+    pub fn decode(&self, lib: &DbcLibrary) -> Option<HashMap<String, f32>> {
+        lib.get_frame_by_id(self.id)
+            .and_then(|frame| frame.decode_frame(&self.data[..]))
+    }
+}
+
+/// Reason for dropping a transfer or rejecting a payload
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IsoTpErrorKind {
+    /// No consecutive frame was received within the timeout
+    Timeout,
+    /// A consecutive frame arrived with an unexpected sequence number
+    SequenceError,
+    /// A new transfer was started before the previous one finished
+    Replaced,
+    /// The frame could not be interpreted as ISO-TP frame
+    InvalidFrame,
+    /// The payload is too long to be segmented
+    PayloadTooLong,
+    /// The frame length is not a valid classic CAN or CAN FD length
+    InvalidFrameLength,
+}
+
+/// Error for a transfer that could not be reassembled or a payload that could not be segmented
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsoTpError {
+    /// Timestamp of the frame which ended the transfer
+    pub timestamp: f64,
+    /// CAN ID of the transfer
+    pub id: Option<CanId>,
+    /// Reason for the error
+    pub kind: IsoTpErrorKind,
+}
+
+impl IsoTpError {
+    fn new(kind: IsoTpErrorKind) -> Self {
+        IsoTpError {
+            timestamp: 0.0,
+            id: None,
+            kind,
+        }
+    }
+}
+
+impl Display for IsoTpError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            IsoTpErrorKind::Timeout => "timed out",
+            IsoTpErrorKind::SequenceError => "received consecutive frame out of sequence",
+            IsoTpErrorKind::Replaced => "replaced by new transfer",
+            IsoTpErrorKind::InvalidFrame => "invalid ISO-TP frame",
+            IsoTpErrorKind::PayloadTooLong => "payload too long",
+            IsoTpErrorKind::InvalidFrameLength => "invalid frame length",
+        };
+        match self.id {
+            Some(id) => write!(f, "ISO-TP transfer on {}: {}", id, reason),
+            None => write!(f, "ISO-TP: {}", reason),
+        }
+    }
+}
+
+impl Error for IsoTpError {}
+
+#[derive(Debug, Clone)]
+struct Session {
+    size: usize,
+    next_sequence: u8,
+    data: Vec<u8>,
+    deadline: f64,
+}
+
+/// Passive reassembler for ISO-TP transfers
+#[derive(Debug, Clone)]
+pub struct IsoTpReassembler {
+    addressing: Addressing,
+    timeout: f64,
+    sessions: HashMap<CanId, Session>,
+}
+
+impl IsoTpReassembler {
+    /// Creates a reassembler with the N_Cr timeout of 1 second.  For extended and mixed
+    /// addressing only frames starting with the given address are considered.
+    pub fn new(addressing: Addressing) -> Self {
+        Self::with_timeout(addressing, 1.0)
+    }
+
+    /// Creates a reassembler with a custom timeout in seconds between consecutive frames
+    pub fn with_timeout(addressing: Addressing, timeout: f64) -> Self {
+        IsoTpReassembler {
+            addressing,
+            timeout,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of transfers in progress
+    pub fn pending(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Feeds a raw CAN frame into the reassembler.  Flow control frames and frames of other
+    /// addresses are ignored.
+    ///
+    /// Returns the messages completed and the transfers dropped by this frame, including
+    /// transfers that timed out before `timestamp`.
+    pub fn push(
+        &mut self,
+        timestamp: f64,
+        id: CanId,
+        data: &[u8],
+    ) -> Vec<Result<IsoTpMessage, IsoTpError>> {
+        let mut events = self.expire(timestamp);
+
+        let offset = self.addressing.offset();
+        if data.len() <= offset || self.addressing.address() != offset_address(data, offset) {
+            return events;
+        }
+        let pdu = &data[offset..];
+
+        if pdu[0] >> 4 == SINGLE_FRAME || pdu[0] >> 4 == FIRST_FRAME {
+            events.extend(
+                self.drop_session(timestamp, id, IsoTpErrorKind::Replaced)
+                    .map(Err),
+            );
+        }
+
+        let event = match pdu[0] >> 4 {
+            SINGLE_FRAME => self.single_frame(timestamp, id, pdu),
+            FIRST_FRAME => self.first_frame(timestamp, id, pdu),
+            CONSECUTIVE_FRAME => self.consecutive_frame(timestamp, id, pdu),
+            FLOW_CONTROL => None,
+            _ => Some(Err(IsoTpError {
+                timestamp,
+                id: Some(id),
+                kind: IsoTpErrorKind::InvalidFrame,
+            })),
+        };
+        events.extend(event);
+
+        events
+    }
+
+    /// Drops all transfers which timed out before `timestamp`.  Useful at the end of a trace.
+    pub fn expire(&mut self, timestamp: f64) -> Vec<Result<IsoTpMessage, IsoTpError>> {
+        let expired: Vec<CanId> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.deadline < timestamp)
+            .map(|(id, _)| *id)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|id| self.drop_session(timestamp, id, IsoTpErrorKind::Timeout))
+            .map(Err)
+            .collect()
+    }
+
+    fn single_frame(
+        &self,
+        timestamp: f64,
+        id: CanId,
+        pdu: &[u8],
+    ) -> Option<Result<IsoTpMessage, IsoTpError>> {
+        // CAN FD single frames longer than 8 bytes escape the length into the second byte
+        let (len, start) = match pdu[0] & 0x0F {
+            0 if pdu.len() > 1 => (usize::from(pdu[1]), 2),
+            len => (usize::from(len), 1),
+        };
+
+        if len == 0 || start + len > pdu.len() {
+            return Some(Err(IsoTpError {
+                timestamp,
+                id: Some(id),
+                kind: IsoTpErrorKind::InvalidFrame,
+            }));
+        }
+
+        Some(Ok(IsoTpMessage {
+            timestamp,
+            id,
+            address: self.addressing.address(),
+            data: pdu[start..start + len].to_vec(),
+        }))
+    }
+
+    fn first_frame(
+        &mut self,
+        timestamp: f64,
+        id: CanId,
+        pdu: &[u8],
+    ) -> Option<Result<IsoTpMessage, IsoTpError>> {
+        let invalid = Some(Err(IsoTpError {
+            timestamp,
+            id: Some(id),
+            kind: IsoTpErrorKind::InvalidFrame,
+        }));
+        if pdu.len() < 2 {
+            return invalid;
+        }
+
+        // payloads longer than 4095 bytes escape the length into the following 4 bytes
+        let (size, start) = match (usize::from(pdu[0] & 0x0F) << 8) | usize::from(pdu[1]) {
+            0 if pdu.len() >= 6 => (
+                u32::from_be_bytes([pdu[2], pdu[3], pdu[4], pdu[5]]) as usize,
+                6,
+            ),
+            0 => return invalid,
+            size => (size, 2),
+        };
+        // shorter payloads fit into a single frame, and the escape is only used when needed
+        if size <= 7 || (start == 6 && size <= 0xFFF) {
+            return invalid;
+        }
+
+        let mut data = Vec::with_capacity(size.min(0xFFF));
+        data.extend_from_slice(&pdu[start..]);
+
+        self.sessions.insert(
+            id,
+            Session {
+                size,
+                next_sequence: 1,
+                data,
+                deadline: timestamp + self.timeout,
+            },
+        );
+
+        None
+    }
+
+    fn consecutive_frame(
+        &mut self,
+        timestamp: f64,
+        id: CanId,
+        pdu: &[u8],
+    ) -> Option<Result<IsoTpMessage, IsoTpError>> {
+        let timeout = self.timeout;
+        let session = self.sessions.get_mut(&id)?;
+
+        if pdu[0] & 0x0F != session.next_sequence {
+            return self
+                .drop_session(timestamp, id, IsoTpErrorKind::SequenceError)
+                .map(Err);
+        }
+
+        session.data.extend_from_slice(&pdu[1..]);
+        session.next_sequence = (session.next_sequence + 1) & 0x0F;
+        session.deadline = timestamp + timeout;
+
+        if session.data.len() < session.size {
+            return None;
+        }
+
+        let mut session = self.sessions.remove(&id)?;
+        session.data.truncate(session.size);
+
+        Some(Ok(IsoTpMessage {
+            timestamp,
+            id,
+            address: self.addressing.address(),
+            data: session.data,
+        }))
+    }
+
+    fn drop_session(
+        &mut self,
+        timestamp: f64,
+        id: CanId,
+        kind: IsoTpErrorKind,
+    ) -> Option<IsoTpError> {
+        self.sessions.remove(&id).map(|_| IsoTpError {
+            timestamp,
+            id: Some(id),
+            kind,
+        })
+    }
+}
+
+fn offset_address(data: &[u8], offset: usize) -> Option<u8> {
+    match offset {
+        0 => None,
+        _ => Some(data[0]),
+    }
+}
+
+/// Splits payloads into ISO-TP frames
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IsoTpSegmenter {
+    addressing: Addressing,
+    frame_len: usize,
+    padding: Option<u8>,
+}
+
+impl IsoTpSegmenter {
+    /// Creates a segmenter for classic CAN frames without padding
+    pub fn new(addressing: Addressing) -> Self {
+        IsoTpSegmenter {
+            addressing,
+            frame_len: CLASSIC_FRAME_LEN,
+            padding: None,
+        }
+    }
+
+    /// Sets the maximum frame length, 8 for classic CAN or one of `FD_FRAME_LENS` for CAN FD
+    pub fn frame_len(mut self, frame_len: usize) -> Self {
+        self.frame_len = frame_len;
+        self
+    }
+
+    /// Pads classic frames to 8 bytes with the given byte.  CAN FD frames are always padded to
+    /// the next valid length, using `DEFAULT_PADDING` if no padding is set.
+    pub fn padding(mut self, padding: u8) -> Self {
+        self.padding = Some(padding);
+        self
+    }
+
+    /// Splits a payload into a single frame, or a first frame followed by consecutive frames.
+    /// Flow control is left to the caller.
+    pub fn segment(&self, payload: &[u8]) -> Result<Vec<Vec<u8>>, IsoTpError> {
+        if !FD_FRAME_LENS.contains(&self.frame_len) {
+            return Err(IsoTpError::new(IsoTpErrorKind::InvalidFrameLength));
+        }
+        if payload.is_empty() {
+            return Err(IsoTpError::new(IsoTpErrorKind::InvalidFrame));
+        }
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(IsoTpError::new(IsoTpErrorKind::PayloadTooLong));
+        }
+
+        let capacity = self.frame_len - self.addressing.offset();
+
+        // classic single frames and short CAN FD single frames carry the length in the PCI
+        if payload.len() < 8 - self.addressing.offset() {
+            let mut frame = vec![(SINGLE_FRAME << 4) | payload.len() as u8];
+            frame.extend_from_slice(payload);
+            return Ok(vec![self.finish(frame)]);
+        }
+        if self.frame_len > CLASSIC_FRAME_LEN && payload.len() <= capacity - 2 {
+            let mut frame = vec![SINGLE_FRAME << 4, payload.len() as u8];
+            frame.extend_from_slice(payload);
+            return Ok(vec![self.finish(frame)]);
+        }
+
+        let mut frames = Vec::new();
+        let mut first = if payload.len() <= 0xFFF {
+            vec![
+                (FIRST_FRAME << 4) | (payload.len() >> 8) as u8,
+                payload.len() as u8,
+            ]
+        } else {
+            let len = (payload.len() as u32).to_be_bytes();
+            vec![FIRST_FRAME << 4, 0x00, len[0], len[1], len[2], len[3]]
+        };
+        let first_len = capacity - first.len();
+        first.extend_from_slice(&payload[..first_len]);
+        frames.push(self.finish(first));
+
+        for (i, chunk) in payload[first_len..].chunks(capacity - 1).enumerate() {
+            let mut frame = vec![(CONSECUTIVE_FRAME << 4) | ((i + 1) & 0x0F) as u8];
+            frame.extend_from_slice(chunk);
+            frames.push(self.finish(frame));
+        }
+
+        Ok(frames)
+    }
+
+    /// Builds a flow control frame
+    pub fn flow_control(&self, status: FlowStatus, block_size: u8, st_min: u8) -> Vec<u8> {
+        let status = match status {
+            FlowStatus::ContinueToSend => 0,
+            FlowStatus::Wait => 1,
+            FlowStatus::Overflow => 2,
+        };
+        self.finish(vec![(FLOW_CONTROL << 4) | status, block_size, st_min])
+    }
+
+    /// Prepends the address byte and pads the frame
+    fn finish(&self, pdu: Vec<u8>) -> Vec<u8> {
+        let mut frame: Vec<u8> = self.addressing.address().into_iter().collect();
+        frame.extend(pdu);
+
+        let len = if self.frame_len > CLASSIC_FRAME_LEN {
+            FD_FRAME_LENS
+                .iter()
+                .copied()
+                .find(|len| *len >= frame.len())
+                .unwrap_or(self.frame_len)
+        } else if self.padding.is_some() {
+            CLASSIC_FRAME_LEN
+        } else {
+            frame.len()
+        };
+        frame.resize(len, self.padding.unwrap_or(DEFAULT_PADDING));
+
+        frame
+    }
+}
+
+/// Iterator adapter returned by `reassemble`
+pub struct Reassemble<I> {
+    frames: I,
+    reassembler: IsoTpReassembler,
+    pending: std::vec::IntoIter<Result<IsoTpMessage, IsoTpError>>,
+}
+
+impl<I> Iterator for Reassemble<I>
+where
+    I: Iterator<Item = (f64, CanId, Vec<u8>)>,
+{
+    type Item = Result<IsoTpMessage, IsoTpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.next() {
+                return Some(event);
+            }
+            let (timestamp, id, data) = self.frames.next()?;
+            self.pending = self.reassembler.push(timestamp, id, &data).into_iter();
+        }
+    }
+}
+
+/// Reassembles all ISO-TP messages in a stream of `(timestamp, id, data)` frames
+pub fn reassemble<I>(frames: I, addressing: Addressing) -> Reassemble<I::IntoIter>
+where
+    I: IntoIterator<Item = (f64, CanId, Vec<u8>)>,
+{
+    Reassemble {
+        frames: frames.into_iter(),
+        reassembler: IsoTpReassembler::new(addressing),
+        pending: Vec::new().into_iter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: CanId = CanId::Standard(0x7E8);
+
+    #[test]
+    fn test_single_frame() {
+        let mut reassembler = IsoTpReassembler::new(Addressing::Normal);
+        let events = reassembler.push(0.0, ID, &[0x03, 0x41, 0x0C, 0x1A, 0xAA, 0xAA, 0xAA, 0xAA]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].as_ref().unwrap().data, vec![0x41, 0x0C, 0x1A]);
+    }
+
+    #[test]
+    fn test_multi_frame() {
+        let mut reassembler = IsoTpReassembler::new(Addressing::Normal);
+
+        assert!(reassembler
+            .push(0.0, ID, &[0x10, 0x14, 0x49, 0x02, 0x01, b'1', b'M', b'8'])
+            .is_empty());
+        // flow control from the tester is ignored
+        assert!(reassembler
+            .push(0.01, CanId::Standard(0x7E0), &[0x30, 0, 0, 0, 0, 0, 0, 0])
+            .is_empty());
+        assert!(reassembler
+            .push(0.02, ID, &[0x21, b'G', b'D', b'M', b'9', b'A', b'X', b'K'])
+            .is_empty());
+        let events = reassembler.push(0.03, ID, &[0x22, b'P', b'0', b'4', b'2', b'7', b'8', b'8']);
+
+        assert_eq!(events.len(), 1);
+        let message = events[0].as_ref().unwrap();
+        assert_eq!(&message.data[..3], &[0x49, 0x02, 0x01]);
+        assert_eq!(&message.data[3..], b"1M8GDM9AXKP042788");
+    }
+
+    #[test]
+    fn test_sequence_error_and_timeout() {
+        let mut reassembler = IsoTpReassembler::new(Addressing::Normal);
+
+        reassembler.push(0.0, ID, &[0x10, 0x14, 0, 0, 0, 0, 0, 0]);
+        let events = reassembler.push(0.01, ID, &[0x22, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            events[0].as_ref().unwrap_err().kind,
+            IsoTpErrorKind::SequenceError
+        );
+
+        reassembler.push(1.0, ID, &[0x10, 0x14, 0, 0, 0, 0, 0, 0]);
+        let events = reassembler.push(2.5, ID, &[0x21, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_ref().unwrap_err().kind,
+            IsoTpErrorKind::Timeout
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_extended_addressing() {
+        let segmenter = IsoTpSegmenter::new(Addressing::Extended(0xF1)).padding(0x55);
+        let payload: Vec<u8> = (0..13).collect();
+        let frames = segmenter.segment(&payload).unwrap();
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(&frames[0][..3], &[0xF1, 0x10, 13]);
+        assert_eq!(&frames[2], &[0xF1, 0x22, 11, 12, 0x55, 0x55, 0x55, 0x55]);
+
+        // frames for another target address are ignored
+        let mut other = IsoTpReassembler::new(Addressing::Extended(0xF2));
+        assert!(frames
+            .iter()
+            .flat_map(|frame| other.push(0.0, ID, frame))
+            .next()
+            .is_none());
+
+        let mut reassembler = IsoTpReassembler::new(Addressing::Extended(0xF1));
+        let events: Vec<_> = frames
+            .iter()
+            .flat_map(|frame| reassembler.push(0.0, ID, frame))
+            .collect();
+        assert_eq!(events[0].as_ref().unwrap().data, payload);
+        assert_eq!(events[0].as_ref().unwrap().address, Some(0xF1));
+    }
+
+    #[test]
+    fn test_fd_frames() {
+        let segmenter = IsoTpSegmenter::new(Addressing::Normal).frame_len(64);
+
+        let single = segmenter.segment(&[0x11; 20]).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].len(), 24);
+        assert_eq!(&single[0][..2], &[0x00, 20]);
+
+        let payload: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let frames = segmenter.segment(&payload).unwrap();
+        assert_eq!(frames.len(), 4);
+        assert!(frames
+            .iter()
+            .all(|frame| FD_FRAME_LENS.contains(&frame.len())));
+
+        let mut reassembler = IsoTpReassembler::new(Addressing::Normal);
+        let events: Vec<_> = single
+            .iter()
+            .chain(frames.iter())
+            .flat_map(|frame| reassembler.push(0.0, ID, frame))
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap().data, vec![0x11; 20]);
+        assert_eq!(events[1].as_ref().unwrap().data, payload);
+    }
+
+    #[test]
+    fn test_long_first_frame() {
+        let segmenter = IsoTpSegmenter::new(Addressing::Normal).frame_len(64);
+        let payload = vec![0xAB; 5000];
+        let frames = segmenter.segment(&payload).unwrap();
+
+        assert_eq!(&frames[0][..6], &[0x10, 0x00, 0x00, 0x00, 0x13, 0x88]);
+
+        let messages: Vec<_> = reassemble(
+            frames.into_iter().map(|frame| (0.0, ID, frame)),
+            Addressing::Normal,
+        )
+        .collect();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].as_ref().unwrap().data, payload);
+    }
+
+    #[test]
+    fn test_invalid_first_frame() {
+        let mut reassembler = IsoTpReassembler::new(Addressing::Normal);
+        let first_frames: [&[u8]; 4] = [
+            &[0x10, 0x07, 0, 0, 0, 0, 0, 0],
+            &[0x10, 0x00, 0x00, 0x00, 0x00, 0x07, 0, 0],
+            &[0x10, 0x00, 0x00, 0x00, 0x0F, 0xFF, 0, 0],
+            &[0x10, 0x00, 0x00, 0x00],
+        ];
+
+        for first_frame in first_frames.iter() {
+            let events = reassembler.push(0.0, ID, first_frame);
+            assert_eq!(events.len(), 1);
+            assert_eq!(
+                events[0].as_ref().unwrap_err().kind,
+                IsoTpErrorKind::InvalidFrame
+            );
+        }
+        assert_eq!(reassembler.pending(), 0);
+
+        assert!(reassembler
+            .push(0.0, ID, &[0x10, 0x08, 0, 0, 0, 0, 0, 0])
+            .is_empty());
+        assert_eq!(reassembler.pending(), 1);
+    }
+
+    #[test]
+    fn test_decode() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let id = CanId::Extended(0x0CF00400);
+        let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA];
+
+        let messages: Vec<_> = reassemble(
+            IsoTpSegmenter::new(Addressing::Normal)
+                .segment(&payload)
+                .unwrap()
+                .into_iter()
+                .map(|frame| (0.0, id, frame)),
+            Addressing::Normal,
+        )
+        .collect();
+        let signals = messages[0].as_ref().unwrap().decode(&lib).unwrap();
+
+        assert_eq!(signals["Engine_Speed"], 2728.5);
+    }
+
+    #[test]
+    fn test_invalid_frame_len() {
+        let segmenter = IsoTpSegmenter::new(Addressing::Normal).frame_len(10);
+
+        assert_eq!(
+            segmenter.segment(&[0; 4]).unwrap_err().kind,
+            IsoTpErrorKind::InvalidFrameLength
+        );
+    }
+}
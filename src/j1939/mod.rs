@@ -0,0 +1,225 @@
+//! SAE J1939 identifier handling and PGN/SPN lookups
+//!
+//! J1939 uses 29-bit extended identifiers which are split into a priority, the parameter group
+//! number (PGN) and the source address.  For PDU1 messages (PDU format below 240) the PDU
+//! specific byte holds the destination address instead of being part of the PGN.
+
+use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal};
+use crate::id::CanId;
+
+pub mod transport;
+
+/// Signal attribute holding the suspect parameter number
+pub const SPN_ATTRIBUTE: &str = "SPN";
+
+/// Destination address used for broadcast messages
+pub const GLOBAL_ADDRESS: u8 = 0xFF;
+
+/// Components of a J1939 identifier
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct J1939Id {
+    /// Message priority, 0 (highest) to 7 (lowest)
+    pub priority: u8,
+    /// Parameter group number, with the PDU specific byte cleared for PDU1 messages
+    pub pgn: u32,
+    /// Address of the sending node
+    pub source_address: u8,
+    /// Address of the receiving node, `None` for PDU2 (broadcast only) messages
+    pub destination_address: Option<u8>,
+}
+
+impl J1939Id {
+    /// Splits an extended `CanId` into its J1939 components.  Returns `None` for standard
+    /// identifiers.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::id::CanId;
+    /// use fastcan::j1939::J1939Id;
+    ///
+    /// let id = J1939Id::from_can_id(CanId::Extended(0x0CF00400)).unwrap();
+    ///
+    /// assert_eq!(id.priority, 3);
+    /// assert_eq!(id.pgn, 61444);
+    /// assert_eq!(id.source_address, 0x00);
+    /// assert_eq!(id.destination_address, None);
+    /// ```
+    pub fn from_can_id(id: CanId) -> Option<J1939Id> {
+        match id {
+            CanId::Standard(_) => None,
+            CanId::Extended(id) => {
+                let pdu_format = (id >> 16) & 0xFF;
+                let pdu_specific = ((id >> 8) & 0xFF) as u8;
+                let (pgn, destination_address) = if pdu_format < 240 {
+                    ((id >> 8) & 0x3FF00, Some(pdu_specific))
+                } else {
+                    ((id >> 8) & 0x3FFFF, None)
+                };
+
+                Some(J1939Id {
+                    priority: ((id >> 26) & 0x7) as u8,
+                    pgn,
+                    source_address: (id & 0xFF) as u8,
+                    destination_address,
+                })
+            }
+        }
+    }
+
+    /// Returns the PDU format byte of the PGN
+    pub fn pdu_format(&self) -> u8 {
+        ((self.pgn >> 8) & 0xFF) as u8
+    }
+
+    /// Returns true for PDU1 (destination specific) messages
+    pub fn is_pdu1(&self) -> bool {
+        self.pdu_format() < 240
+    }
+
+    /// Assembles the extended `CanId` from the J1939 components
+    pub fn to_can_id(&self) -> CanId {
+        let pdu_specific = if self.is_pdu1() {
+            u32::from(self.destination_address.unwrap_or(GLOBAL_ADDRESS))
+        } else {
+            self.pgn & 0xFF
+        };
+
+        CanId::Extended(
+            (u32::from(self.priority & 0x7) << 26)
+                | ((self.pgn & 0x3FF00) << 8)
+                | (pdu_specific << 8)
+                | u32::from(self.source_address),
+        )
+    }
+}
+
+impl From<J1939Id> for CanId {
+    fn from(id: J1939Id) -> Self {
+        id.to_can_id()
+    }
+}
+
+impl DbcSignal {
+    /// Returns the suspect parameter number from the `SPN` attribute, if present
+    pub fn get_spn(&self) -> Option<u32> {
+        self.get_attribute(SPN_ATTRIBUTE)
+            .and_then(|spn| spn.parse::<u32>().ok())
+    }
+}
+
+impl DbcFrame {
+    /// Returns the J1939 components of the frame ID, `None` for standard identifiers
+    pub fn get_j1939_id(&self) -> Option<J1939Id> {
+        J1939Id::from_can_id(self.get_can_id())
+    }
+
+    /// Returns the parameter group number of the frame, `None` for standard identifiers
+    pub fn get_pgn(&self) -> Option<u32> {
+        self.get_j1939_id().map(|id| id.pgn)
+    }
+}
+
+impl DbcLibrary {
+    /// Returns all frames with the given parameter group number, regardless of their source
+    /// address, ordered by ID.
+    pub fn get_frames_by_pgn(&self, pgn: u32) -> Vec<&DbcFrame> {
+        let mut frames: Vec<&DbcFrame> = self
+            .get_frames()
+            .into_iter()
+            .filter(|frame| frame.get_pgn() == Some(pgn))
+            .collect();
+        frames.sort_by_key(|frame| frame.get_can_id());
+        frames
+    }
+
+    /// Query frame with a parameter group number, regardless of its source address.  If several
+    /// frames share the PGN the one with the lowest ID is returned.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let frame = lib.get_frame_by_pgn(61444).unwrap();
+    ///
+    /// assert_eq!(frame.get_id(), 2364539904);
+    /// ```
+    pub fn get_frame_by_pgn(&self, pgn: u32) -> Option<&DbcFrame> {
+        self.get_frames_by_pgn(pgn).into_iter().next()
+    }
+
+    /// Query frame for a J1939 message received on the bus.  An exact ID match is preferred,
+    /// otherwise the frame is looked up by its PGN so that messages from other source
+    /// addresses are found as well.
+    pub fn get_frame_j1939(&self, id: J1939Id) -> Option<&DbcFrame> {
+        self.get_frame_by_id(id.to_can_id())
+            .or_else(|| self.get_frame_by_pgn(id.pgn))
+    }
+
+    /// Query signal with a suspect parameter number
+    ///
+    /// # Example
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let signal = lib.get_signal_by_spn(190).unwrap();
+    ///
+    /// assert_eq!(signal.get_definition().name, "Engine_Speed");
+    /// ```
+    pub fn get_signal_by_spn(&self, spn: u32) -> Option<&DbcSignal> {
+        self.get_frames()
+            .into_iter()
+            .flat_map(|frame| frame.get_signals())
+            .find(|signal| signal.get_spn() == Some(spn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdu2_id() {
+        let id = J1939Id::from_can_id(CanId::Extended(0x18FEF100)).unwrap();
+
+        assert_eq!(id.priority, 6);
+        assert_eq!(id.pgn, 65265);
+        assert_eq!(id.source_address, 0x00);
+        assert_eq!(id.destination_address, None);
+        assert!(!id.is_pdu1());
+        assert_eq!(id.to_can_id(), CanId::Extended(0x18FEF100));
+    }
+
+    #[test]
+    fn test_pdu1_id() {
+        let id = J1939Id::from_can_id(CanId::Extended(0x18EAFF3D)).unwrap();
+
+        assert_eq!(id.priority, 6);
+        assert_eq!(id.pgn, 59904);
+        assert_eq!(id.source_address, 0x3D);
+        assert_eq!(id.destination_address, Some(0xFF));
+        assert!(id.is_pdu1());
+        assert_eq!(id.to_can_id(), CanId::Extended(0x18EAFF3D));
+    }
+
+    #[test]
+    fn test_standard_id() {
+        assert!(J1939Id::from_can_id(CanId::Standard(0x123)).is_none());
+    }
+
+    #[test]
+    fn test_lookup_other_source_address() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let id = J1939Id::from_can_id(CanId::Extended(0x0CF00417)).unwrap();
+
+        let frame = lib.get_frame_j1939(id).unwrap();
+
+        assert_eq!(frame.get_can_id(), CanId::Extended(0x0CF00400));
+        assert_eq!(frame.get_pgn(), Some(61444));
+        assert!(lib.get_frame_by_pgn(65265).is_none());
+        assert_eq!(lib.get_signal("Engine_Speed").unwrap().get_spn(), Some(190));
+    }
+}
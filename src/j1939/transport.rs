@@ -0,0 +1,566 @@
+//! J1939 transport protocol (J1939-21) reassembly
+//!
+//! Messages longer than 8 bytes, such as DM1 or the VIN, are split into a connection management
+//! (TP.CM) frame followed by data transfer (TP.DT) frames.  Broadcasts use a BAM announcement,
+//! destination specific transfers an RTS/CTS handshake.  `TransportReassembler` passively
+//! monitors both and rebuilds the original payload.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::id::CanId;
+//! use fastcan::j1939::transport::TransportReassembler;
+//!
+//! let mut reassembler = TransportReassembler::new();
+//!
+//! // BAM announcing 10 bytes of PGN 65226 (DM1) in 2 packets, followed by the data
+//! reassembler.push(0.00, CanId::Extended(0x1CECFF00), &[0x20, 10, 0, 2, 0xFF, 0xCA, 0xFE, 0x00]);
+//! reassembler.push(0.05, CanId::Extended(0x1CEBFF00), &[1, 0, 1, 2, 3, 4, 5, 6]);
+//! let events = reassembler.push(0.10, CanId::Extended(0x1CEBFF00), &[2, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]);
+//!
+//! let message = events[0].as_ref().unwrap();
+//!
+//! assert_eq!(message.pgn, 65226);
+//! assert_eq!(message.data, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use super::{J1939Id, GLOBAL_ADDRESS};
+use crate::dbc::DbcLibrary;
+use crate::id::CanId;
+use crate::mapper::DecodeFrame;
+
+/// PGN of connection management frames (TP.CM)
+pub const TP_CM_PGN: u32 = 0xEC00;
+/// PGN of data transfer frames (TP.DT)
+pub const TP_DT_PGN: u32 = 0xEB00;
+
+const CM_RTS: u8 = 16;
+const CM_CTS: u8 = 17;
+const CM_END_OF_MSG_ACK: u8 = 19;
+const CM_BAM: u8 = 32;
+const CM_ABORT: u8 = 255;
+
+const BYTES_PER_PACKET: usize = 7;
+
+/// Timeouts of the transport protocol in seconds, defaults as defined by J1939-21
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransportTimeouts {
+    /// Maximum time between two data transfer frames
+    pub t1: f64,
+    /// Maximum time between a CTS and the next data transfer frame
+    pub t2: f64,
+    /// Maximum time between an RTS or the last data transfer frame of a block and the next CTS
+    pub t3: f64,
+}
+
+impl Default for TransportTimeouts {
+    fn default() -> Self {
+        TransportTimeouts {
+            t1: 0.75,
+            t2: 1.25,
+            t3: 1.25,
+        }
+    }
+}
+
+/// Reassembled multi-packet message
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransportMessage {
+    /// Timestamp of the last data transfer frame
+    pub timestamp: f64,
+    /// Priority of the connection management frame
+    pub priority: u8,
+    /// Parameter group number of the transported message
+    pub pgn: u32,
+    /// Address of the sending node
+    pub source_address: u8,
+    /// Address of the receiving node, `GLOBAL_ADDRESS` for broadcasts
+    pub destination_address: u8,
+    /// Reassembled payload
+    pub data: Vec<u8>,
+}
+
+impl TransportMessage {
+    /// Returns true if the message was broadcast with BAM
+    pub fn is_broadcast(&self) -> bool {
+        self.destination_address == GLOBAL_ADDRESS
+    }
+
+    /// Decodes the payload with the frame defined for the message's PGN
+    ///
+    /// Returns a HashMap of signal names to signal values, `None` if the library does not
+    /// contain the PGN
+    pub fn decode(&self, lib: &DbcLibrary) -> Option<HashMap<String, f32>> {
+        lib.get_frame_by_pgn(self.pgn)
+            .and_then(|frame| frame.decode_frame(&self.data[..]))
+    }
+}
+
+/// Reason for dropping a transfer
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TransportErrorKind {
+    /// One of the nodes sent a TP.Conn_Abort with the given reason code
+    Aborted(u8),
+    /// No frame was received within the protocol timeout
+    Timeout,
+    /// A data transfer frame arrived out of sequence
+    SequenceError,
+    /// A new transfer was started before the previous one finished
+    Replaced,
+    /// The last announced packet arrived before the announced number of bytes
+    Incomplete,
+}
+
+/// Error for a transfer that could not be reassembled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransportError {
+    /// Timestamp of the frame which ended the transfer
+    pub timestamp: f64,
+    /// Parameter group number of the transported message
+    pub pgn: u32,
+    /// Address of the sending node
+    pub source_address: u8,
+    /// Address of the receiving node, `GLOBAL_ADDRESS` for broadcasts
+    pub destination_address: u8,
+    /// Reason for dropping the transfer
+    pub kind: TransportErrorKind,
+}
+
+impl Display for TransportError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            TransportErrorKind::Aborted(reason) => format!("aborted with reason {}", reason),
+            TransportErrorKind::Timeout => "timed out".to_string(),
+            TransportErrorKind::SequenceError => "received packet out of sequence".to_string(),
+            TransportErrorKind::Replaced => "replaced by new transfer".to_string(),
+            TransportErrorKind::Incomplete => "ended before the announced size".to_string(),
+        };
+        write!(
+            f,
+            "transfer of PGN {} from {:#04X} to {:#04X} {}",
+            self.pgn, self.source_address, self.destination_address, reason
+        )
+    }
+}
+
+impl Error for TransportError {}
+
+#[derive(Debug, Clone)]
+struct Session {
+    broadcast: bool,
+    priority: u8,
+    pgn: u32,
+    size: usize,
+    packets: u8,
+    next_sequence: u8,
+    data: Vec<u8>,
+    deadline: f64,
+}
+
+/// Passive reassembler for J1939 BAM and RTS/CTS transfers
+#[derive(Debug, Clone, Default)]
+pub struct TransportReassembler {
+    timeouts: TransportTimeouts,
+    /// Open transfers keyed by source and destination address
+    sessions: HashMap<(u8, u8), Session>,
+}
+
+impl TransportReassembler {
+    /// Creates a reassembler with the default J1939-21 timeouts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a reassembler with custom timeouts
+    pub fn with_timeouts(timeouts: TransportTimeouts) -> Self {
+        TransportReassembler {
+            timeouts,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of transfers in progress
+    pub fn pending(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Feeds a raw CAN frame into the reassembler.  Frames which are not part of the transport
+    /// protocol are ignored.
+    ///
+    /// Returns the messages completed and the transfers dropped by this frame, including
+    /// transfers that timed out before `timestamp`.
+    pub fn push(
+        &mut self,
+        timestamp: f64,
+        id: CanId,
+        data: &[u8],
+    ) -> Vec<Result<TransportMessage, TransportError>> {
+        let mut events = self.expire(timestamp);
+
+        let id = match J1939Id::from_can_id(id) {
+            Some(id) if data.len() >= 8 => id,
+            _ => return events,
+        };
+        let destination = id.destination_address.unwrap_or(GLOBAL_ADDRESS);
+
+        match id.pgn {
+            TP_CM_PGN => {
+                if let Some(event) = self.connection_management(timestamp, id, destination, data) {
+                    events.push(event);
+                }
+            }
+            TP_DT_PGN => {
+                if let Some(event) = self.data_transfer(timestamp, id, destination, data) {
+                    events.push(event);
+                }
+            }
+            _ => {}
+        }
+
+        events
+    }
+
+    /// Drops all transfers which timed out before `timestamp`.  Useful at the end of a trace.
+    pub fn expire(&mut self, timestamp: f64) -> Vec<Result<TransportMessage, TransportError>> {
+        let expired: Vec<(u8, u8)> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.deadline < timestamp)
+            .map(|(key, _)| *key)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|key| self.drop_session(timestamp, key, TransportErrorKind::Timeout))
+            .map(Err)
+            .collect()
+    }
+
+    fn connection_management(
+        &mut self,
+        timestamp: f64,
+        id: J1939Id,
+        destination: u8,
+        data: &[u8],
+    ) -> Option<Result<TransportMessage, TransportError>> {
+        let pgn = u32::from(data[5]) | (u32::from(data[6]) << 8) | (u32::from(data[7]) << 16);
+        let source = id.source_address;
+
+        match data[0] {
+            CM_RTS | CM_BAM => {
+                let broadcast = data[0] == CM_BAM;
+                let key = (
+                    source,
+                    if broadcast {
+                        GLOBAL_ADDRESS
+                    } else {
+                        destination
+                    },
+                );
+                let replaced = self.drop_session(timestamp, key, TransportErrorKind::Replaced);
+                let size = usize::from(u16::from(data[1]) | (u16::from(data[2]) << 8));
+                let deadline = if broadcast {
+                    self.timeouts.t1
+                } else {
+                    self.timeouts.t3
+                };
+
+                self.sessions.insert(
+                    key,
+                    Session {
+                        broadcast,
+                        priority: id.priority,
+                        pgn,
+                        size,
+                        packets: data[3],
+                        next_sequence: 1,
+                        data: Vec::with_capacity(size),
+                        deadline: timestamp + deadline,
+                    },
+                );
+
+                replaced.map(Err)
+            }
+            CM_CTS => {
+                // CTS is sent by the receiver of the transfer
+                let t2 = self.timeouts.t2;
+                if let Some(session) = self.sessions.get_mut(&(destination, source)) {
+                    if data[1] == 0 {
+                        // hold the connection open
+                        session.deadline = timestamp + t2;
+                    } else if data[2] >= 1 && data[2] <= session.next_sequence {
+                        session.next_sequence = data[2];
+                        session
+                            .data
+                            .truncate(usize::from(data[2] - 1) * BYTES_PER_PACKET);
+                        session.deadline = timestamp + t2;
+                    }
+                }
+                None
+            }
+            CM_END_OF_MSG_ACK => {
+                self.sessions.remove(&(destination, source));
+                None
+            }
+            CM_ABORT => {
+                let kind = TransportErrorKind::Aborted(data[1]);
+                self.drop_session(timestamp, (source, destination), kind)
+                    .or_else(|| self.drop_session(timestamp, (destination, source), kind))
+                    .map(Err)
+            }
+            _ => None,
+        }
+    }
+
+    fn data_transfer(
+        &mut self,
+        timestamp: f64,
+        id: J1939Id,
+        destination: u8,
+        data: &[u8],
+    ) -> Option<Result<TransportMessage, TransportError>> {
+        let key = (id.source_address, destination);
+        let timeouts = self.timeouts;
+        let session = self.sessions.get_mut(&key)?;
+
+        if data[0] != session.next_sequence {
+            if session.broadcast {
+                return self
+                    .drop_session(timestamp, key, TransportErrorKind::SequenceError)
+                    .map(Err);
+            }
+            // the receiver requests retransmission with a CTS
+            return None;
+        }
+
+        session.data.extend_from_slice(&data[1..8]);
+        session.next_sequence = session.next_sequence.wrapping_add(1);
+        session.deadline = timestamp
+            + if session.broadcast {
+                timeouts.t1
+            } else {
+                timeouts.t3.max(timeouts.t1)
+            };
+
+        if session.data.len() < session.size {
+            if data[0] < session.packets {
+                return None;
+            }
+            return self
+                .drop_session(timestamp, key, TransportErrorKind::Incomplete)
+                .map(Err);
+        }
+
+        let mut session = self.sessions.remove(&key)?;
+        session.data.truncate(session.size);
+
+        Some(Ok(TransportMessage {
+            timestamp,
+            priority: session.priority,
+            pgn: session.pgn,
+            source_address: key.0,
+            destination_address: key.1,
+            data: session.data,
+        }))
+    }
+
+    fn drop_session(
+        &mut self,
+        timestamp: f64,
+        key: (u8, u8),
+        kind: TransportErrorKind,
+    ) -> Option<TransportError> {
+        self.sessions.remove(&key).map(|session| TransportError {
+            timestamp,
+            pgn: session.pgn,
+            source_address: key.0,
+            destination_address: key.1,
+            kind,
+        })
+    }
+}
+
+/// Iterator adapter returned by `reassemble`
+pub struct Reassemble<I> {
+    frames: I,
+    reassembler: TransportReassembler,
+    pending: std::vec::IntoIter<Result<TransportMessage, TransportError>>,
+}
+
+impl<I> Iterator for Reassemble<I>
+where
+    I: Iterator<Item = (f64, CanId, Vec<u8>)>,
+{
+    type Item = Result<TransportMessage, TransportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.next() {
+                return Some(event);
+            }
+            let (timestamp, id, data) = self.frames.next()?;
+            self.pending = self.reassembler.push(timestamp, id, &data).into_iter();
+        }
+    }
+}
+
+/// Reassembles all transport protocol messages in a stream of `(timestamp, id, data)` frames
+pub fn reassemble<I>(frames: I) -> Reassemble<I::IntoIter>
+where
+    I: IntoIterator<Item = (f64, CanId, Vec<u8>)>,
+{
+    Reassemble {
+        frames: frames.into_iter(),
+        reassembler: TransportReassembler::new(),
+        pending: Vec::new().into_iter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BAM: CanId = CanId::Extended(0x1CECFF00);
+    const BAM_DT: CanId = CanId::Extended(0x1CEBFF00);
+    // node 0x00 to node 0xF9 and back
+    const RTS: CanId = CanId::Extended(0x1CECF900);
+    const CTS: CanId = CanId::Extended(0x1CEC00F9);
+    const DT: CanId = CanId::Extended(0x1CEBF900);
+
+    #[test]
+    fn test_bam() {
+        let mut reassembler = TransportReassembler::new();
+
+        assert!(reassembler
+            .push(0.0, BAM, &[0x20, 17, 0, 3, 0xFF, 0xEC, 0xFE, 0x00])
+            .is_empty());
+        assert!(reassembler
+            .push(0.05, BAM_DT, &[1, b'1', b'M', b'8', b'G', b'D', b'M', b'9'])
+            .is_empty());
+        assert!(reassembler
+            .push(0.10, BAM_DT, &[2, b'A', b'X', b'K', b'P', b'0', b'4', b'2'])
+            .is_empty());
+        let events = reassembler.push(0.15, BAM_DT, &[3, b'7', b'8', b'8', 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        assert_eq!(
+            events,
+            vec![Ok(TransportMessage {
+                timestamp: 0.15,
+                priority: 7,
+                pgn: 65260,
+                source_address: 0x00,
+                destination_address: GLOBAL_ADDRESS,
+                data: b"1M8GDM9AXKP042788".to_vec(),
+            })]
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_bam_sequence_error() {
+        let mut reassembler = TransportReassembler::new();
+
+        reassembler.push(0.0, BAM, &[0x20, 17, 0, 3, 0xFF, 0xEC, 0xFE, 0x00]);
+        let events = reassembler.push(0.05, BAM_DT, &[2, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_ref().unwrap_err().kind,
+            TransportErrorKind::SequenceError
+        );
+    }
+
+    #[test]
+    fn test_bam_incomplete() {
+        let mut reassembler = TransportReassembler::new();
+
+        // 17 bytes announced in 2 packets of at most 14 bytes
+        reassembler.push(0.0, BAM, &[0x20, 17, 0, 2, 0xFF, 0xEC, 0xFE, 0x00]);
+        reassembler.push(0.05, BAM_DT, &[1, 0, 0, 0, 0, 0, 0, 0]);
+        let events = reassembler.push(0.10, BAM_DT, &[2, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(events.len(), 1);
+        let error = events[0].as_ref().unwrap_err();
+        assert_eq!(error.kind, TransportErrorKind::Incomplete);
+        assert_eq!(
+            error.to_string(),
+            "transfer of PGN 65260 from 0x00 to 0xFF ended before the announced size"
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_bam_timeout() {
+        let mut reassembler = TransportReassembler::new();
+
+        reassembler.push(0.0, BAM, &[0x20, 17, 0, 3, 0xFF, 0xEC, 0xFE, 0x00]);
+        reassembler.push(0.05, BAM_DT, &[1, 0, 0, 0, 0, 0, 0, 0]);
+        let events = reassembler.push(1.0, BAM_DT, &[2, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_ref().unwrap_err().kind,
+            TransportErrorKind::Timeout
+        );
+        assert_eq!(events[0].as_ref().unwrap_err().pgn, 65260);
+    }
+
+    #[test]
+    fn test_rts_cts_with_retransmission() {
+        let mut reassembler = TransportReassembler::new();
+
+        reassembler.push(0.0, RTS, &[16, 9, 0, 2, 0xFF, 0xDA, 0xFE, 0x00]);
+        reassembler.push(0.01, CTS, &[17, 2, 1, 0xFF, 0xFF, 0xDA, 0xFE, 0x00]);
+        reassembler.push(0.02, DT, &[1, 1, 2, 3, 4, 5, 6, 7]);
+        // packet 2 lost, receiver requests it again
+        reassembler.push(0.80, CTS, &[17, 1, 2, 0xFF, 0xFF, 0xDA, 0xFE, 0x00]);
+        let events = reassembler.push(0.81, DT, &[2, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        assert_eq!(events.len(), 1);
+        let message = events[0].as_ref().unwrap();
+        assert_eq!(message.pgn, 65242);
+        assert_eq!(message.destination_address, 0xF9);
+        assert!(!message.is_broadcast());
+        assert_eq!(message.data, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert!(reassembler
+            .push(0.82, CTS, &[19, 9, 0, 2, 0xFF, 0xDA, 0xFE, 0x00])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_abort() {
+        let mut reassembler = TransportReassembler::new();
+
+        reassembler.push(0.0, RTS, &[16, 9, 0, 2, 0xFF, 0xDA, 0xFE, 0x00]);
+        let events = reassembler.push(0.01, CTS, &[255, 1, 0xFF, 0xFF, 0xFF, 0xDA, 0xFE, 0x00]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_ref().unwrap_err().kind,
+            TransportErrorKind::Aborted(1)
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_reassemble_stream() {
+        let frames = vec![
+            (0.0, CanId::Extended(0x0CF00400), vec![0; 8]),
+            (0.0, BAM, vec![0x20, 10, 0, 2, 0xFF, 0xCA, 0xFE, 0x00]),
+            (0.05, BAM_DT, vec![1, 0, 1, 2, 3, 4, 5, 6]),
+            (0.10, BAM_DT, vec![2, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]),
+        ];
+
+        let messages: Vec<_> = reassemble(frames).collect();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].as_ref().unwrap().pgn, 65226);
+    }
+}
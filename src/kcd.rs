@@ -0,0 +1,777 @@
+//! Reading and writing the KCD format of the Kayak project
+//!
+//! KCD files describe the `Message`s of one or several `Bus`es in XML.  A `Message` maps to a
+//! `DbcFrame`, its `Signal`s to `DbcSignal`s and `LabelSet`s to value tables.  The signal of a
+//! `Multiplex` element becomes the multiplexor of the frame, the signals of its `MuxGroup`s are
+//! multiplexed with the `count` of the group.  Notes are kept as descriptions, the `interval` of
+//! a message as `GenMsgCycleTime` attribute, producers and consumers as sending and receiving
+//! nodes.
+//!
+//! KCD numbers the bits of big endian signals from the most significant bit of the first byte
+//! and gives the position of the most significant bit of the signal, while `DbcSignalDefinition`
+//! holds the position of the least significant bit of the frame read as one big endian integer.
+//! Offsets are converted when reading and writing, so both formats decode the same values.
+//!
+//! Floating point signals (`single` and `double` values), label groups of more than 256 values
+//! and other attributes are not supported and skipped.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//!
+//! let lib = DbcLibrary::from_kcd_file("./tests/data/sample.kcd").unwrap();
+//!
+//! let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+//! let decoded = lib.decode(0.0, lib.get_frame(0x0CF00400).unwrap().get_can_id(), &payload);
+//! assert_eq!(decoded.unwrap().signals["Engine_Speed"], 2728.5);
+//!
+//! let kcd = lib.to_kcd("Powertrain");
+//! assert!(kcd.contains(r#"<Message id="0x0CF00400" name="EEC1" length="8" format="extended""#));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+use crate::bus::BusLibrary;
+use crate::dbc::{
+    invalid, DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, Multiplexing, ValueDefinition,
+    CYCLE_TIME, NO_NODE,
+};
+use crate::id::CanId;
+
+/// Namespace of KCD files
+pub const KCD_NAMESPACE: &str = "http://kayak.2codeornot2code.org/1.0";
+
+/// Label groups with more values are skipped
+const MAX_LABEL_GROUP: i64 = 256;
+
+impl DbcLibrary {
+    /// Reads the frames of a KCD file with a single bus, see `BusLibrary::from_kcd_file` for
+    /// files with several buses
+    pub fn from_kcd_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_kcd_str(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the frames of a KCD document with a single bus
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the document is not valid XML, has no or
+    /// several buses, or messages or signals with invalid attributes.
+    pub fn from_kcd_str(data: &str) -> io::Result<Self> {
+        let mut buses = read_kcd(data)?;
+        match buses.len() {
+            1 => Ok(buses.remove(0).1),
+            0 => Err(invalid("KCD document without bus")),
+            n => Err(invalid(format!(
+                "KCD document with {} buses, read it as BusLibrary",
+                n
+            ))),
+        }
+    }
+
+    /// Writes the library as KCD document with a single bus of the given name
+    pub fn to_kcd(&self, bus: &str) -> String {
+        write_kcd(&[(bus.to_string(), self)])
+    }
+
+    /// Writes the library to a KCD file with a single bus of the given name
+    pub fn write_kcd_file<P>(&self, path: P, bus: &str) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_kcd(bus))
+    }
+}
+
+impl BusLibrary {
+    /// Reads a KCD file with a library per bus, the channels are the bus names
+    pub fn from_kcd_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_kcd_str(&fs::read_to_string(path)?)
+    }
+
+    /// Reads a KCD document with a library per bus, the channels are the bus names
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::bus::BusLibrary;
+    /// use fastcan::trace::Channel;
+    ///
+    /// let buses = BusLibrary::from_kcd_file("./tests/data/sample.kcd").unwrap();
+    ///
+    /// assert!(buses.get(&Channel::from("Powertrain")).is_some());
+    /// ```
+    pub fn from_kcd_str(data: &str) -> io::Result<Self> {
+        let mut buses = BusLibrary::new();
+        for (name, lib) in read_kcd(data)? {
+            buses.insert(name.as_str(), lib);
+        }
+        Ok(buses)
+    }
+
+    /// Writes the libraries of all channels as KCD document, with a bus per channel.  The
+    /// fallback library is not written.
+    pub fn to_kcd(&self) -> String {
+        let buses: Vec<(String, &DbcLibrary)> = self
+            .channels()
+            .into_iter()
+            .filter_map(|channel| Some((channel.to_string(), self.get(channel)?)))
+            .collect();
+        write_kcd(&buses)
+    }
+}
+
+fn children<'a, 'i>(node: Node<'a, 'i>, name: &'a str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &'a str) -> Option<Node<'a, 'i>> {
+    children(node, name).next()
+}
+
+/// Parses an attribute, `None` if it is missing
+fn attribute<T>(node: Node, name: &str) -> io::Result<Option<T>>
+where
+    T: std::str::FromStr,
+{
+    match node.attribute(name) {
+        Some(value) => value.trim().parse().map(Some).map_err(|_| {
+            invalid(format!(
+                "invalid {} \"{}\" of {}",
+                name,
+                value,
+                node.tag_name().name()
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+fn read_kcd(data: &str) -> io::Result<Vec<(String, DbcLibrary)>> {
+    let document = Document::parse(data).map_err(invalid)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "NetworkDefinition" {
+        return Err(invalid("not a KCD document"));
+    }
+
+    let nodes: HashMap<&str, &str> = children(root, "Node")
+        .filter_map(|node| Some((node.attribute("id")?, node.attribute("name")?)))
+        .collect();
+    let node_names: Vec<String> = children(root, "Node")
+        .filter_map(|node| node.attribute("name"))
+        .map(str::to_string)
+        .collect();
+
+    let mut buses = Vec::new();
+    for bus in children(root, "Bus") {
+        let mut lib = DbcLibrary::default();
+        *lib.nodes_mut() = node_names.clone();
+
+        for message in children(bus, "Message") {
+            let frame = read_message(message, &nodes)?;
+            let id = frame.get_can_id();
+            if lib.frames_mut().insert(id, frame).is_some() {
+                return Err(invalid(format!("duplicate message ID 0x{}", id)));
+            }
+        }
+
+        buses.push((bus.attribute("name").unwrap_or_default().to_string(), lib));
+    }
+
+    Ok(buses)
+}
+
+/// Names of the nodes referenced by a `Producer` or `Consumer` element, `Vector__XXX` if there
+/// are none
+fn node_refs(node: Node, element: &str, nodes: &HashMap<&str, &str>) -> String {
+    let names: Vec<&str> = child(node, element)
+        .into_iter()
+        .flat_map(|element| children(element, "NodeRef"))
+        .filter_map(|node_ref| nodes.get(node_ref.attribute("id")?).copied())
+        .collect();
+
+    if names.is_empty() {
+        NO_NODE.to_string()
+    } else {
+        names.join(",")
+    }
+}
+
+fn notes(node: Node) -> Option<String> {
+    child(node, "Notes")
+        .and_then(|notes| notes.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Signal read from a `Signal` or `Multiplex` element, before the frame length is known
+struct KcdSignal {
+    offset: usize,
+    definition: DbcSignalDefinition,
+    description: Option<String>,
+    values: Option<ValueDefinition>,
+}
+
+fn read_message(message: Node, nodes: &HashMap<&str, &str>) -> io::Result<DbcFrame> {
+    let name = message.attribute("name").unwrap_or_default().to_string();
+    let raw_id = message.attribute("id").unwrap_or_default();
+    let extended = message.attribute("format") == Some("extended");
+    let id = u32::from_str_radix(raw_id.trim_start_matches("0x").trim_start_matches("0X"), 16)
+        .ok()
+        .and_then(|id| CanId::new(id, extended))
+        .ok_or_else(|| invalid(format!("invalid ID \"{}\" of message {}", raw_id, name)))?;
+
+    let mut signals = Vec::new();
+    for element in message.children().filter(Node::is_element) {
+        match element.tag_name().name() {
+            "Signal" => signals.extend(read_signal(element, Multiplexing::None, nodes)?),
+            "Multiplex" => {
+                signals.extend(read_signal(element, Multiplexing::Multiplexor, nodes)?);
+                for group in children(element, "MuxGroup") {
+                    let count = attribute(group, "count")?.unwrap_or(0);
+                    for signal in children(group, "Signal") {
+                        let multiplexing = Multiplexing::Multiplexed(count);
+                        signals.extend(read_signal(signal, multiplexing, nodes)?);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let message_len = match message.attribute("length") {
+        None | Some("auto") => signals
+            .iter()
+            .map(|signal| (signal.offset + signal.definition.bit_len).div_ceil(8))
+            .max()
+            .unwrap_or(0) as u32,
+        Some(_) => attribute(message, "length")?.unwrap_or(0),
+    };
+    let bits = 8 * (message_len as usize).max(8);
+
+    let mut signal_map = HashMap::new();
+    for signal in signals {
+        let mut definition = signal.definition;
+        if !definition.little_endian {
+            definition.start_bit = bits
+                .checked_sub(signal.offset + definition.bit_len)
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "signal {} beyond the end of message {}",
+                        definition.name, name
+                    ))
+                })?;
+        }
+        let signal = DbcSignal::new(
+            Some(definition),
+            signal.description,
+            HashMap::new(),
+            signal.values,
+        );
+        signal_map.insert(signal.get_definition().name.clone(), signal);
+    }
+
+    let mut attributes = HashMap::new();
+    if let Some(interval) = message.attribute("interval") {
+        attributes.insert(CYCLE_TIME.to_string(), interval.to_string());
+    }
+
+    Ok(DbcFrame::new(
+        name,
+        id.to_dbc(),
+        message_len,
+        node_refs(message, "Producer", nodes),
+        attributes,
+        notes(message),
+        signal_map,
+    ))
+}
+
+fn read_signal(
+    element: Node,
+    multiplexing: Multiplexing,
+    nodes: &HashMap<&str, &str>,
+) -> io::Result<Option<KcdSignal>> {
+    let name = element.attribute("name").unwrap_or_default().to_string();
+    let offset = attribute(element, "offset")?.unwrap_or(0);
+    let bit_len = attribute(element, "length")?.unwrap_or(1);
+    let little_endian = element.attribute("endianess") != Some("big");
+
+    let value = child(element, "Value");
+    let signed = match value.and_then(|value| value.attribute("type")) {
+        None | Some("unsigned") => false,
+        Some("signed") => true,
+        Some(_) => return Ok(None),
+    };
+    let number = |name: &str| -> io::Result<Option<f32>> {
+        match value {
+            Some(value) => attribute(value, name),
+            None => Ok(None),
+        }
+    };
+
+    let definition = DbcSignalDefinition {
+        name,
+        start_bit: offset,
+        bit_len,
+        little_endian,
+        signed,
+        scale: number("slope")?.unwrap_or(1.0),
+        offset: number("intercept")?.unwrap_or(0.0),
+        min_value: number("min")?.unwrap_or(0.0),
+        max_value: number("max")?.unwrap_or(0.0),
+        units: value
+            .and_then(|value| value.attribute("unit"))
+            .unwrap_or_default()
+            .to_string(),
+        receiving_node: node_refs(element, "Consumer", nodes),
+        multiplexing,
+    };
+
+    let mut labels = Vec::new();
+    for label_set in children(element, "LabelSet") {
+        for label in label_set.children().filter(Node::is_element) {
+            let name = label.attribute("name").unwrap_or_default().to_string();
+            match label.tag_name().name() {
+                "Label" => {
+                    if let Some(value) = attribute(label, "value")? {
+                        labels.push((value, name));
+                    }
+                }
+                "LabelGroup" => {
+                    let from: i64 = attribute(label, "from")?.unwrap_or(0);
+                    let to: i64 = attribute(label, "to")?.unwrap_or(from);
+                    if to >= from && to - from < MAX_LABEL_GROUP {
+                        labels.extend((from..=to).map(|value| (value, name.clone())));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(Some(KcdSignal {
+        offset,
+        definition,
+        description: notes(element),
+        values: Some(ValueDefinition::new(labels)).filter(|values| !values.get_values().is_empty()),
+    }))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_kcd(buses: &[(String, &DbcLibrary)]) -> String {
+    // nodes of all buses, including senders and receivers missing in the node lists
+    let mut nodes: Vec<&str> = Vec::new();
+    for (_, lib) in buses {
+        let senders = lib.get_frames().into_iter().map(DbcFrame::get_sending_node);
+        let receivers = lib.get_frames().into_iter().flat_map(|frame| {
+            frame
+                .get_signals()
+                .into_iter()
+                .map(|signal| signal.get_definition().receiving_node.as_str())
+        });
+        let names = lib.get_nodes().iter().map(String::as_str);
+        for name in names
+            .chain(senders)
+            .chain(receivers.flat_map(|r| r.split(',')))
+        {
+            let name = name.trim();
+            if !name.is_empty() && name != NO_NODE && !nodes.contains(&name) {
+                nodes.push(name);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<NetworkDefinition xmlns=\"{}\">\n",
+        KCD_NAMESPACE
+    ));
+    out.push_str("  <Document/>\n");
+    for (index, name) in nodes.iter().enumerate() {
+        out.push_str(&format!(
+            "  <Node id=\"{}\" name=\"{}\"/>\n",
+            index + 1,
+            escape(name)
+        ));
+    }
+
+    for (name, lib) in buses {
+        let mut frames: Vec<&DbcFrame> = lib
+            .get_frames()
+            .into_iter()
+            .filter(|frame| !frame.get_name().is_empty())
+            .collect();
+        frames.sort_by_key(|frame| frame.get_can_id());
+
+        out.push_str(&format!("  <Bus name=\"{}\">\n", escape(name)));
+        for frame in frames {
+            // writing to a `String` does not fail
+            let _ = write_message(&mut out, frame, &nodes);
+        }
+        out.push_str("  </Bus>\n");
+    }
+
+    out.push_str("</NetworkDefinition>\n");
+    out
+}
+
+/// `NodeRef` elements of comma separated node names
+fn write_node_refs(out: &mut String, indent: &str, element: &str, names: &str, nodes: &[&str]) {
+    let ids: Vec<usize> = names
+        .split(',')
+        .filter_map(|name| nodes.iter().position(|node| *node == name.trim()))
+        .collect();
+    if ids.is_empty() {
+        return;
+    }
+
+    out.push_str(&format!("{}<{}>\n", indent, element));
+    for id in ids {
+        out.push_str(&format!("{}  <NodeRef id=\"{}\"/>\n", indent, id + 1));
+    }
+    out.push_str(&format!("{}</{}>\n", indent, element));
+}
+
+fn write_message(out: &mut String, frame: &DbcFrame, nodes: &[&str]) -> std::fmt::Result {
+    let id = frame.get_can_id();
+    write!(
+        out,
+        "    <Message id=\"0x{}\" name=\"{}\" length=\"{}\"",
+        id,
+        escape(frame.get_name()),
+        frame.get_message_len()
+    )?;
+    if let CanId::Extended(_) = id {
+        out.push_str(" format=\"extended\"");
+    }
+    if let Some(interval) = frame.get_attributes().get(CYCLE_TIME) {
+        write!(out, " interval=\"{}\"", escape(interval))?;
+    }
+    out.push_str(">\n");
+
+    if let Some(description) = frame.get_description() {
+        writeln!(out, "      <Notes>{}</Notes>", escape(description))?;
+    }
+    write_node_refs(out, "      ", "Producer", frame.get_sending_node(), nodes);
+
+    let mut signals: Vec<&DbcSignalDefinition> = frame
+        .get_signals()
+        .into_iter()
+        .map(DbcSignal::get_definition)
+        .collect();
+    signals.sort_by(|a, b| (a.start_bit, &a.name).cmp(&(b.start_bit, &b.name)));
+    let bits = 8 * (frame.get_message_len() as usize).max(8);
+
+    let multiplexor = signals
+        .iter()
+        .find(|signal| signal.multiplexing == Multiplexing::Multiplexor);
+    if let Some(multiplexor) = multiplexor {
+        let signal = frame.get_signal(&multiplexor.name).unwrap();
+        write_signal(out, "      ", "Multiplex", signal, bits, nodes, false)?;
+
+        let mut counts: Vec<u32> = signals
+            .iter()
+            .filter_map(|signal| match signal.multiplexing {
+                Multiplexing::Multiplexed(count) => Some(count),
+                _ => None,
+            })
+            .collect();
+        counts.sort_unstable();
+        counts.dedup();
+        for count in counts {
+            writeln!(out, "        <MuxGroup count=\"{}\">", count)?;
+            for definition in &signals {
+                if definition.multiplexing == Multiplexing::Multiplexed(count) {
+                    let signal = frame.get_signal(&definition.name).unwrap();
+                    write_signal(out, "          ", "Signal", signal, bits, nodes, true)?;
+                }
+            }
+            writeln!(out, "        </MuxGroup>")?;
+        }
+        writeln!(out, "      </Multiplex>")?;
+    }
+
+    for definition in &signals {
+        // without multiplexor, multiplexed signals are written as plain signals
+        if multiplexor.is_none() || definition.multiplexing == Multiplexing::None {
+            let signal = frame.get_signal(&definition.name).unwrap();
+            write_signal(out, "      ", "Signal", signal, bits, nodes, true)?;
+        }
+    }
+
+    writeln!(out, "    </Message>")
+}
+
+fn write_signal(
+    out: &mut String,
+    indent: &str,
+    element: &str,
+    signal: &DbcSignal,
+    bits: usize,
+    nodes: &[&str],
+    close: bool,
+) -> std::fmt::Result {
+    let definition = signal.get_definition();
+    let offset = if definition.little_endian {
+        definition.start_bit
+    } else {
+        bits.saturating_sub(definition.start_bit + definition.bit_len)
+    };
+
+    write!(
+        out,
+        "{}<{} name=\"{}\" offset=\"{}\"",
+        indent,
+        element,
+        escape(&definition.name),
+        offset
+    )?;
+    if definition.bit_len != 1 {
+        write!(out, " length=\"{}\"", definition.bit_len)?;
+    }
+    if !definition.little_endian {
+        out.push_str(" endianess=\"big\"");
+    }
+    out.push_str(">\n");
+
+    let inner = format!("{}  ", indent);
+    if let Some(description) = signal.get_description() {
+        writeln!(out, "{}<Notes>{}</Notes>", inner, escape(description))?;
+    }
+    write_node_refs(out, &inner, "Consumer", &definition.receiving_node, nodes);
+
+    write!(out, "{}<Value", inner)?;
+    if definition.signed {
+        out.push_str(" type=\"signed\"");
+    }
+    if definition.scale != 1.0 {
+        write!(out, " slope=\"{}\"", definition.scale)?;
+    }
+    if definition.offset != 0.0 {
+        write!(out, " intercept=\"{}\"", definition.offset)?;
+    }
+    if !definition.units.is_empty() {
+        write!(out, " unit=\"{}\"", escape(&definition.units))?;
+    }
+    writeln!(
+        out,
+        " min=\"{}\" max=\"{}\"/>",
+        definition.min_value, definition.max_value
+    )?;
+
+    if let Some(values) = signal.get_value_definition() {
+        writeln!(out, "{}<LabelSet>", inner)?;
+        for (value, label) in values.get_values() {
+            writeln!(
+                out,
+                "{}  <Label name=\"{}\" value=\"{}\"/>",
+                inner,
+                escape(label),
+                value
+            )?;
+        }
+        writeln!(out, "{}</LabelSet>", inner)?;
+    }
+
+    if close {
+        writeln!(out, "{}</{}>", indent, element)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbc::ChangeKind;
+    use crate::mapper::DecodeMessage;
+    use crate::tests::helpers::assert_like_dbc;
+
+    const KCD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<NetworkDefinition xmlns="http://kayak.2codeornot2code.org/1.0">
+  <Document name="test"/>
+  <Node id="1" name="Engine"/>
+  <Node id="2" name="Dashboard"/>
+  <Bus name="Powertrain">
+    <Message id="0x123" name="Status" length="8" interval="50">
+      <Notes>Engine status</Notes>
+      <Producer><NodeRef id="1"/></Producer>
+      <Signal name="Speed" offset="0" length="16" endianess="big">
+        <Consumer><NodeRef id="1"/><NodeRef id="2"/></Consumer>
+        <Value slope="0.25" unit="rpm"/>
+      </Signal>
+      <Signal name="Temp" offset="16" length="8">
+        <Value type="signed" intercept="-40"/>
+      </Signal>
+      <Signal name="Running" offset="24"/>
+      <Signal name="Voltage" offset="32" length="32">
+        <Value type="single"/>
+      </Signal>
+    </Message>
+    <Message id="0x18FEF100" format="extended" name="Diagnostics">
+      <Multiplex name="Page" offset="0" length="8">
+        <MuxGroup count="0">
+          <Signal name="Mode" offset="8" length="4">
+            <LabelSet>
+              <Label name="Off" value="0"/>
+              <LabelGroup name="Reserved" from="2" to="4"/>
+            </LabelSet>
+          </Signal>
+        </MuxGroup>
+        <MuxGroup count="1">
+          <Signal name="Code" offset="12" length="12" endianess="big"/>
+        </MuxGroup>
+      </Multiplex>
+    </Message>
+  </Bus>
+</NetworkDefinition>
+"#;
+
+    #[test]
+    fn test_read() {
+        let lib = DbcLibrary::from_kcd_str(KCD).unwrap();
+        assert_eq!(lib.get_nodes(), ["Engine", "Dashboard"]);
+
+        let status = lib.get_frame_by_id(CanId::Standard(0x123)).unwrap();
+        assert_eq!(status.get_description(), Some("Engine status"));
+        assert_eq!(status.get_sending_node(), "Engine");
+        assert_eq!(status.get_attributes()[CYCLE_TIME], "50");
+        assert!(status.get_signal("Voltage").is_none());
+
+        let payload = [0x12, 0x34, 0xFE, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let speed = status.get_signal("Speed").unwrap();
+        assert_eq!(speed.get_definition().receiving_node, "Engine,Dashboard");
+        assert_eq!(speed.decode_raw(&payload), Some(0x1234));
+        assert_eq!(speed.decode_message(&payload), Some(1165.0));
+        let temp = status.get_signal("Temp").unwrap();
+        assert_eq!(temp.get_definition().receiving_node, NO_NODE);
+        assert_eq!(
+            temp.get_definition().descriptor().decode(&payload),
+            Some(-42.0)
+        );
+        let running = status.get_signal("Running").unwrap();
+        assert_eq!(running.decode_message(&payload), Some(1.0));
+
+        let diagnostics = lib.get_frame(0x18FEF100).unwrap();
+        assert_eq!(diagnostics.get_message_len(), 3);
+        let page = diagnostics.get_signal("Page").unwrap();
+        assert_eq!(
+            page.get_definition().multiplexing,
+            Multiplexing::Multiplexor
+        );
+        let mode = diagnostics.get_signal("Mode").unwrap();
+        assert_eq!(
+            mode.get_definition().multiplexing,
+            Multiplexing::Multiplexed(0)
+        );
+        assert_eq!(mode.get_value_definition().unwrap().get_values().len(), 4);
+        assert_eq!(mode.decode_label(&[0x00, 0x03, 0x00]), Some("Reserved"));
+
+        // bits 12 to 23 in network order, the low nibble of byte 1 and byte 2
+        let code = diagnostics.get_signal("Code").unwrap();
+        assert_eq!(code.decode_raw(&[0x01, 0x0A, 0xBC]), Some(0xABC));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lib = DbcLibrary::from_kcd_str(KCD).unwrap();
+        let kcd = lib.to_kcd("Powertrain");
+        assert!(kcd.contains(r#"<Signal name="Code" offset="12" length="12" endianess="big">"#));
+
+        let copy = DbcLibrary::from_kcd_str(&kcd).unwrap();
+        assert!(lib.diff(&copy).is_empty());
+        assert_eq!(copy.to_kcd("Powertrain"), kcd);
+
+        for file in &["sample", "codegen"] {
+            let lib = DbcLibrary::from_dbc_file(format!("./tests/data/{}.dbc", file)).unwrap();
+            let copy = DbcLibrary::from_kcd_str(&lib.to_kcd("Bus")).unwrap();
+            // KCD only has the cycle time of the attributes
+            let changes: Vec<_> = lib
+                .diff(&copy)
+                .into_iter()
+                .filter(|change| {
+                    change.kind != ChangeKind::FrameAttributeChanged
+                        && change.kind != ChangeKind::SignalAttributeChanged
+                })
+                .collect();
+            assert_eq!(changes, vec![], "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_like_dbc() {
+        for file in ["codegen.dbc", "layouts.dbc"] {
+            let lib = DbcLibrary::from_dbc_file(format!("./tests/data/{}", file)).unwrap();
+            let copy = DbcLibrary::from_kcd_str(&lib.to_kcd("Bus")).unwrap();
+            assert_like_dbc(&lib, &copy);
+        }
+    }
+
+    #[test]
+    fn test_buses() {
+        let kcd = KCD.replace(
+            "</NetworkDefinition>",
+            r#"<Bus name="Body"><Message id="0x123" name="Doors"/></Bus></NetworkDefinition>"#,
+        );
+        let error = DbcLibrary::from_kcd_str(&kcd).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "KCD document with 2 buses, read it as BusLibrary"
+        );
+
+        let buses = BusLibrary::from_kcd_str(&kcd).unwrap();
+        let channel = crate::trace::Channel::from("Body");
+        let doors = buses.get_frame(&channel, CanId::Standard(0x123)).unwrap();
+        assert_eq!(doors.get_name(), "Doors");
+
+        let copy = BusLibrary::from_kcd_str(&buses.to_kcd()).unwrap();
+        assert_eq!(copy.channels(), buses.channels());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error = |kcd: &str| DbcLibrary::from_kcd_str(kcd).unwrap_err().to_string();
+        assert_eq!(error("<Network/>"), "not a KCD document");
+        assert_eq!(error("<NetworkDefinition/>"), "KCD document without bus");
+        assert_eq!(
+            error(&KCD.replace("0x123", "0x800")),
+            "invalid ID \"0x800\" of message Status"
+        );
+        assert_eq!(
+            error(&KCD.replace(r#"offset="24""#, r#"offset="x""#)),
+            "invalid offset \"x\" of Signal"
+        );
+        assert_eq!(
+            error(&KCD.replace(r#"offset="0" length="16""#, r#"offset="60" length="16""#)),
+            "signal Speed beyond the end of message Status"
+        );
+        assert!(DbcLibrary::from_kcd_str("<NetworkDefinition>").is_err());
+    }
+}
@@ -1,8 +1,5 @@
-#![cfg_attr(
-    feature = "cargo-clippy",
-    allow(clippy::redundant_field_names, clippy::unreadable_literal)
-)]
-#![crate_name = "fastcan"]
+#![allow(clippy::redundant_field_names, clippy::unreadable_literal)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
 //!
@@ -11,9 +8,15 @@
 //! The fastcan-rs library is a fork of the canparse library.
 //! While the canparse library implements the J1393, the fastcan-rs
 //! library follows a more generic approach and just implements basic CAN encoding and decoding.
+//! J1939 specific lookups by PGN and SPN are available in the `j1939` module, behind the
+//! `j1939` feature (enabled by default).
 //!
 //! With the fastcan-rs library, you can load DBC files dynamically and encode as well as decode CAN messages.
 //!
+//! Everything but the `codec` and `id` modules requires the `std` feature (enabled by default).
+//! Without it the crate is `no_std` and does not allocate, `codec` then decodes and encodes
+//! signals described by static `SignalDescriptor`s, e.g. on microcontrollers.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -39,18 +42,51 @@
 //! let data = signal.decode_message(ret);
 //! ```
 
+#[cfg(feature = "std")]
 extern crate encoding;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate enum_primitive;
 #[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "std")]
 extern crate byteorder;
 
 #[cfg(feature = "use-socketcan")]
 extern crate socketcan;
 
+#[cfg(feature = "std")]
+pub mod arxml;
+#[cfg(feature = "std")]
+pub mod bus;
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "std")]
 pub mod dbc;
+#[cfg(feature = "std")]
+pub mod export;
+pub mod id;
+#[cfg(feature = "std")]
+pub mod isotp;
+#[cfg(feature = "j1939")]
+pub mod j1939;
+#[cfg(feature = "std")]
+pub mod kcd;
+#[cfg(feature = "std")]
 pub mod mapper;
+#[cfg(feature = "std")]
+pub mod mdf;
+#[cfg(feature = "use-socketcan")]
+pub mod socket;
+#[cfg(feature = "std")]
+pub mod sym;
+#[cfg(feature = "std")]
+pub mod trace;
 
+#[cfg(feature = "std")]
 mod tests;
+
+#[cfg(feature = "macros")]
+pub use fastcan_macros::dbc;
@@ -2,9 +2,8 @@
 
 use std::collections::HashMap;
 
-use byteorder::{BigEndian, ByteOrder, LittleEndian};
-
-use crate::dbc::{DbcFrame, DbcSignal};
+use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal, Multiplexing};
+use crate::id::CanId;
 
 /// The collection of functions for parsing CAN messages `N` into their defined signal values.
 pub trait DecodeMessage<N> {
@@ -13,13 +12,124 @@ pub trait DecodeMessage<N> {
     fn decode_message(&self, msg: N) -> Option<f32>;
 }
 
+/// The collection of functions for parsing CAN messages `N` into the values of all signals of a
+/// frame.
+pub trait DecodeFrame<N> {
+    /// Parses CAN message type `N` into a map of signal names to `f32` signal values on success,
+    /// or `None` on failure.
+    fn decode_frame(&self, msg: N) -> Option<HashMap<String, f32>>;
+}
+
+/// Signal values decoded from a single CAN frame
+#[derive(Clone, Debug)]
+pub struct DecodedFrame<'a> {
+    /// Time the frame was received, in seconds
+    pub timestamp: f64,
+    /// Definition of the decoded frame
+    pub frame: &'a DbcFrame,
+    /// Map of signal names to signal values
+    pub signals: HashMap<String, f32>,
+}
+
+impl DbcLibrary {
+    ///
+    /// Looks up the frame definition for a CAN ID and decodes all of its signals, of multiplexed
+    /// frames only the signals selected by the multiplexor
+    ///
+    /// Returns `None` if the library does not contain the ID or the message is empty
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::{dbc::DbcLibrary, id::CanId};
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    ///
+    /// let decoded = dbc.decode(0.0, CanId::Extended(0x0CF00400), &payload).unwrap();
+    ///
+    /// assert_eq!(decoded.frame.get_name(), "EEC1");
+    /// assert_eq!(decoded.signals["Engine_Speed"], 2728.5);
+    /// ```
+    ///
+    pub fn decode(&self, timestamp: f64, id: CanId, msg: &[u8]) -> Option<DecodedFrame<'_>> {
+        let frame = self.get_frame_by_id(id)?;
+
+        frame.decode_frame(msg).map(|signals| DecodedFrame {
+            timestamp,
+            frame,
+            signals,
+        })
+    }
+}
+
+impl DbcSignal {
+    ///
+    /// Extracts the raw value of the signal, before scale and offset are applied
+    ///
+    /// Returns `None` if the message is empty
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let signal = dbc.get_frame(2364539904).unwrap().get_signal("Engine_Speed").unwrap();
+    ///
+    /// let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    ///
+    /// assert_eq!(signal.decode_raw(&payload), Some(0x5544));
+    /// ```
+    ///
+    pub fn decode_raw(&self, msg: &[u8]) -> Option<u64> {
+        if msg.is_empty() {
+            return None;
+        }
+
+        Some(self.get_definition().descriptor().extract(msg))
+    }
+
+    ///
+    /// Looks up the label of the raw signal value in the value table of the signal
+    ///
+    /// Returns `None` if the message is empty, the signal has no value table or the value is not
+    /// part of it
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let signal = dbc.get_frame(2364539904).unwrap().get_signal("Engine_Speed").unwrap();
+    ///
+    /// let payload = [0x11, 0x22, 0x33, 0xFF, 0xFF, 0x66, 0x77, 0x88];
+    ///
+    /// assert_eq!(signal.decode_label(&payload), Some("NotAvailable"));
+    /// ```
+    ///
+    pub fn decode_label(&self, msg: &[u8]) -> Option<&str> {
+        if msg.is_empty() {
+            return None;
+        }
+
+        let value = self.get_definition().descriptor().decode_raw(msg);
+
+        self.get_value_definition()?.get_label(value)
+    }
+}
+
 /// Interface for encoding a hashmap into a can frame
 pub trait EncodeMessage<N> {
     /// Encode a can frame from signals in a hashmap
     fn encode_message(&self, signal_map: &HashMap<String, f64>) -> Result<N, String>;
 }
 
-impl<'a> DecodeMessage<&'a [u8; 8]> for DbcSignal {
+impl DecodeMessage<&[u8; 8]> for DbcSignal {
     ///
     /// Decodes a signal from a CAN frame
     ///
@@ -49,14 +159,7 @@ impl<'a> DecodeMessage<&'a [u8; 8]> for DbcSignal {
     /// ```
     ///
     fn decode_message(&self, msg: &[u8; 8]) -> Option<f32> {
-        parse_array(
-            self.get_definition().bit_len,
-            self.get_definition().start_bit,
-            self.get_definition().little_endian,
-            self.get_definition().scale,
-            self.get_definition().offset,
-            msg,
-        )
+        decode_unsigned(self, &msg[..])
     }
 }
 
@@ -92,14 +195,125 @@ impl DecodeMessage<Vec<u8>> for DbcSignal {
     /// ```
     ///
     fn decode_message(&self, msg: Vec<u8>) -> Option<f32> {
-        decode_message(
-            self.get_definition().bit_len,
-            self.get_definition().start_bit,
-            self.get_definition().little_endian,
-            self.get_definition().scale,
-            self.get_definition().offset,
-            &msg,
-        )
+        decode_unsigned(self, &msg)
+    }
+}
+
+impl DecodeMessage<&[u8]> for DbcSignal {
+    ///
+    /// Decodes a signal from a CAN frame of arbitrary length, e.g. a reassembled transport
+    /// protocol payload
+    ///
+    /// # Arguments
+    ///
+    /// `msg`: CAN frame as byte slice
+    ///
+    /// Returns the signal as float
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::{dbc::DbcLibrary, mapper::DecodeMessage};
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let signal_def = dbc.get_signal("Engine_Speed").unwrap();
+    ///
+    /// let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA];
+    ///
+    /// let data = signal_def.decode_message(&payload[..]).unwrap();
+    /// ```
+    ///
+    fn decode_message(&self, msg: &[u8]) -> Option<f32> {
+        decode_unsigned(self, msg)
+    }
+}
+
+impl DbcFrame {
+    ///
+    /// Returns the signals a message contains: signals multiplexed with another value than the
+    /// one of the multiplexor in the message are left out
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/ff.dbc").unwrap();
+    ///
+    /// let frame = dbc.get_frame(786).unwrap();
+    ///
+    /// let mut signals: Vec<&str> = frame
+    ///     .get_signals_in_message(&[101, 7])
+    ///     .iter()
+    ///     .map(|signal| signal.get_definition().name.as_str())
+    ///     .collect();
+    /// signals.sort();
+    ///
+    /// assert_eq!(signals, ["FF_BMS_Control_TS", "FF_BMS_Control_mux"]);
+    /// ```
+    ///
+    pub fn get_signals_in_message(&self, msg: &[u8]) -> Vec<&DbcSignal> {
+        let signals = self.get_signals();
+        let multiplexor = signals
+            .iter()
+            .find(|signal| signal.get_definition().multiplexing == Multiplexing::Multiplexor)
+            .and_then(|signal| signal.decode_raw(msg));
+
+        signals
+            .into_iter()
+            .filter(
+                |signal| match (signal.get_definition().multiplexing, multiplexor) {
+                    (Multiplexing::Multiplexed(value), Some(multiplexor)) => {
+                        u64::from(value) == multiplexor
+                    }
+                    _ => true,
+                },
+            )
+            .collect()
+    }
+}
+
+impl DecodeFrame<&[u8]> for DbcFrame {
+    ///
+    /// Decodes all signals of a DBC frame, of multiplexed frames only the signals selected by
+    /// the multiplexor
+    ///
+    /// # Arguments
+    ///
+    /// `msg`: CAN frame as byte slice
+    ///
+    /// Returns a HashMap of signal names to signal values, `None` for an empty message
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::{dbc::DbcLibrary, mapper::DecodeFrame};
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let frame = dbc.get_frame(2364539904).unwrap();
+    ///
+    /// let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    ///
+    /// let signals = frame.decode_frame(&payload[..]).unwrap();
+    ///
+    /// assert_eq!(signals["Engine_Speed"], 2728.5);
+    /// ```
+    ///
+    fn decode_frame(&self, msg: &[u8]) -> Option<HashMap<String, f32>> {
+        if msg.is_empty() {
+            return None;
+        }
+
+        self.get_signals_in_message(msg)
+            .into_iter()
+            .map(|signal| {
+                signal
+                    .decode_message(msg)
+                    .map(|value| (signal.get_definition().name.clone(), value))
+            })
+            .collect()
     }
 }
 
@@ -111,7 +325,8 @@ impl EncodeMessage<Vec<u8>> for DbcFrame {
     ///
     /// `signal_map`: HashMap for signal data; signal name maps to signal data (normalized to float)
     ///
-    /// Returns a byte vector of max 8 bytes (success) or an error string (failure)
+    /// Returns a byte vector of the frame length, but at least 8 bytes (success) or an error
+    /// string (failure)
     ///
     /// # Examples
     ///
@@ -134,38 +349,11 @@ impl EncodeMessage<Vec<u8>> for DbcFrame {
     /// ```
     ///
     fn encode_message(&self, signal_map: &HashMap<String, f64>) -> Result<Vec<u8>, String> {
-        let signals = self.get_signals();
+        let mut result = vec![0u8; (self.get_message_len() as usize).max(8)];
 
-        let mut result: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-
-        for signal in signals {
-            if !signal_map.contains_key(&signal.get_definition().name) {
-                return Err(format!(
-                    "Missing signal data: {}",
-                    signal.get_definition().name
-                ));
-            }
-
-            let byte_data = encode_signal(
-                signal.get_definition().bit_len,
-                signal.get_definition().start_bit,
-                signal.get_definition().little_endian,
-                signal.get_definition().scale,
-                signal.get_definition().offset,
-                *signal_map.get(&signal.get_definition().name).unwrap(),
-            );
-
-            let byte_data = match byte_data {
-                Ok(b) => b,
-                Err(err) => return Err(format!("Error encoding signal: {}", err)),
-            };
-
-            for i in 0..7 {
-                result[i] |= byte_data[i];
-            }
-        }
+        encode_signals(self, signal_map, &mut result)?;
 
-        Ok(result.to_vec())
+        Ok(result)
     }
 }
 
@@ -200,113 +388,75 @@ impl EncodeMessage<[u8; 8]> for DbcFrame {
     /// ```
     ///
     fn encode_message(&self, signal_map: &HashMap<String, f64>) -> Result<[u8; 8], String> {
-        let signals = self.get_signals();
+        let mut result = [0u8; 8];
 
-        let mut result: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-
-        for signal in signals {
-            if !signal_map.contains_key(&signal.get_definition().name) {
-                return Err(format!(
-                    "Missing signal data: {}",
-                    signal.get_definition().name
-                ));
-            }
-
-            let byte_data = encode_signal(
-                signal.get_definition().bit_len,
-                signal.get_definition().start_bit,
-                signal.get_definition().little_endian,
-                signal.get_definition().scale,
-                signal.get_definition().offset,
-                *signal_map.get(&signal.get_definition().name).unwrap(),
-            );
-
-            let byte_data = match byte_data {
-                Ok(b) => b,
-                Err(err) => return Err(format!("Error encoding signal: {}", err)),
-            };
-
-            for i in 0..7 {
-                result[i] |= byte_data[i];
-            }
-        }
+        encode_signals(self, signal_map, &mut result)?;
 
         Ok(result)
     }
 }
 
-/// Internal function for parsing CAN message arrays given the definition parameters.  This is where
-/// the real calculations happen.
-fn parse_array(
-    bit_len: usize,
-    start_bit: usize,
-    little_endian: bool,
-    scale: f32,
-    offset: f32,
-    msg: &[u8; 8],
-) -> Option<f32> {
-    let msg64: u64 = if little_endian {
-        LittleEndian::read_u64(msg)
-    } else {
-        BigEndian::read_u64(msg)
-    };
-
-    let bit_mask: u64 = 2u64.pow(bit_len as u32) - 1;
-
-    Some((((msg64 >> start_bit) & bit_mask) as f32) * scale + offset)
-}
-
-/// Internal function for parsing CAN message slices given the definition parameters.  This is where
-/// the real calculations happen.
-fn decode_message(
-    bit_len: usize,
-    start_bit: usize,
-    little_endian: bool,
-    scale: f32,
-    offset: f32,
-    msg: &[u8],
-) -> Option<f32> {
-    let mut msg = msg.to_owned();
-
+/// Internal function decoding a signal without sign extension, the behaviour of
+/// `DecodeMessage` since before the `codec` module existed
+fn decode_unsigned(signal: &DbcSignal, msg: &[u8]) -> Option<f32> {
     if msg.is_empty() {
         return None;
     }
 
-    if msg.len() < 8 {
-        msg.resize(8, 0x00);
-    }
+    let descriptor = signal.get_definition().descriptor();
 
-    let msg64: u64 = if little_endian {
-        LittleEndian::read_u64(&msg)
-    } else {
-        BigEndian::read_u64(&msg)
+    Some((descriptor.extract(msg) as f32) * descriptor.scale + descriptor.offset)
+}
+
+/// Internal function encoding the signals of a frame from a map of signal names to values, of
+/// multiplexed frames only the signals selected by the multiplexor
+fn encode_signals(
+    frame: &DbcFrame,
+    signal_map: &HashMap<String, f64>,
+    msg: &mut [u8],
+) -> Result<(), String> {
+    let is_multiplexed = |signal: &&DbcSignal| {
+        matches!(
+            signal.get_definition().multiplexing,
+            Multiplexing::Multiplexed(_)
+        )
     };
 
-    let bit_mask: u64 = 2u64.pow(bit_len as u32) - 1;
+    for signal in frame
+        .get_signals()
+        .into_iter()
+        .filter(|signal| !is_multiplexed(signal))
+    {
+        encode_signal(signal, signal_map, msg)?;
+    }
+    for signal in frame
+        .get_signals_in_message(msg)
+        .into_iter()
+        .filter(is_multiplexed)
+    {
+        encode_signal(signal, signal_map, msg)?;
+    }
 
-    Some((((msg64 >> start_bit) & bit_mask) as f32) * scale + offset)
+    Ok(())
 }
 
+/// Internal function encoding a signal from a map of signal names to values
 fn encode_signal(
-    bit_len: usize,
-    start_bit: usize,
-    little_endian: bool,
-    scale: f32,
-    offset: f32,
-    signal: f64,
-) -> Result<[u8; 8], String> {
-    let data = (signal - (offset as f64)) / (scale as f64);
-
-    if data.log2() > bit_len as f64 {
-        return Err(format!("Signal does not fit into {}", data));
-    }
-
-    let byte_data = (data as u64) << start_bit;
-
-    let result: [u8; 8] = match little_endian {
-        true => byte_data.to_le_bytes(),
-        false => byte_data.to_be_bytes(),
+    signal: &DbcSignal,
+    signal_map: &HashMap<String, f64>,
+    msg: &mut [u8],
+) -> Result<(), String> {
+    let definition = signal.get_definition();
+
+    let value = match signal_map.get(&definition.name) {
+        Some(value) => *value,
+        None => return Err(format!("Missing signal data: {}", definition.name)),
     };
 
-    Ok(result)
+    definition.descriptor().encode(value, msg).map_err(|err| {
+        format!(
+            "Error encoding signal {}: {} ({})",
+            definition.name, err, value
+        )
+    })
 }
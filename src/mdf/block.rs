@@ -0,0 +1,269 @@
+//! Block level reading and writing of MDF4 files
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::io;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+
+use byteorder::{ByteOrder, LittleEndian};
+use flate2::read::ZlibDecoder;
+
+/// Size of the identification block at the start of every file
+pub const ID_BLOCK_SIZE: u64 = 64;
+/// Size of the header every other block starts with
+pub const BLOCK_HEADER_SIZE: usize = 24;
+
+/// A block with its links and data section
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub id: [u8; 4],
+    pub links: Vec<u64>,
+    pub data: Vec<u8>,
+}
+
+impl Block {
+    /// Returns the link at `index`, 0 (no link) if the block has fewer links
+    pub fn link(&self, index: usize) -> u64 {
+        self.links.get(index).copied().unwrap_or(0)
+    }
+
+    /// Returns `len` bytes of the data section at `offset`, zero filled if the data section is
+    /// shorter, as blocks of older versions may lack trailing fields
+    pub fn bytes(&self, offset: usize, len: usize) -> Vec<u8> {
+        let mut bytes = self.data.get(offset..).unwrap_or(&[]).to_vec();
+        bytes.resize(len, 0);
+        bytes
+    }
+
+    pub fn u8(&self, offset: usize) -> u8 {
+        self.data.get(offset).copied().unwrap_or(0)
+    }
+
+    pub fn u16(&self, offset: usize) -> u16 {
+        LittleEndian::read_u16(&self.bytes(offset, 2))
+    }
+
+    pub fn u32(&self, offset: usize) -> u32 {
+        LittleEndian::read_u32(&self.bytes(offset, 4))
+    }
+
+    pub fn u64(&self, offset: usize) -> u64 {
+        LittleEndian::read_u64(&self.bytes(offset, 8))
+    }
+
+    pub fn f64(&self, offset: usize) -> f64 {
+        LittleEndian::read_f64(&self.bytes(offset, 8))
+    }
+}
+
+/// Returns an `io::Error` for malformed MDF content
+pub fn malformed<E>(error: E) -> io::Error
+where
+    E: Into<String>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error.into())
+}
+
+/// Addresses of the blocks of link chains and lists followed so far, as a malformed file may
+/// link back to one of them
+#[derive(Debug, Default)]
+pub struct Visited(HashSet<u64>);
+
+impl Visited {
+    /// Records `address`, fails if it was visited before
+    pub fn visit(&mut self, address: u64) -> io::Result<()> {
+        if self.0.insert(address) {
+            Ok(())
+        } else {
+            Err(malformed(format!("cyclic link to {:#x}", address)))
+        }
+    }
+}
+
+/// Reads the block at `address`
+pub fn read_block<R>(reader: &mut R, address: u64) -> io::Result<Block>
+where
+    R: Read + Seek,
+{
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(address))?;
+
+    let mut header = [0u8; BLOCK_HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+    if &header[..2] != b"##" {
+        return Err(malformed(format!("no block at {:#x}", address)));
+    }
+
+    let invalid_length = || malformed(format!("invalid block length at {:#x}", address));
+    let length = LittleEndian::read_u64(&header[8..]);
+    // checked before allocating, the length of a malformed block may be anything
+    if length > file_len.saturating_sub(address) {
+        return Err(invalid_length());
+    }
+    let length = length as usize;
+    let links_len = usize::try_from(LittleEndian::read_u64(&header[16..]))
+        .ok()
+        .and_then(|link_count| link_count.checked_mul(8))
+        .ok_or_else(invalid_length)?;
+    let data_len = BLOCK_HEADER_SIZE
+        .checked_add(links_len)
+        .and_then(|len| length.checked_sub(len))
+        .ok_or_else(invalid_length)?;
+
+    let mut links = vec![0u8; links_len];
+    reader.read_exact(&mut links)?;
+    let mut data = vec![0u8; data_len];
+    reader.read_exact(&mut data)?;
+
+    let mut id = [0u8; 4];
+    id.copy_from_slice(&header[..4]);
+
+    Ok(Block {
+        id,
+        links: links.chunks(8).map(LittleEndian::read_u64).collect(),
+        data,
+    })
+}
+
+/// Reads the text of a `TX` or `MD` block, empty for a missing link.  The XML of `MD` blocks
+/// is returned as is.
+pub fn read_text<R>(reader: &mut R, address: u64) -> io::Result<String>
+where
+    R: Read + Seek,
+{
+    if address == 0 {
+        return Ok(String::new());
+    }
+
+    let block = read_block(reader, address)?;
+    let end = block
+        .data
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(block.data.len());
+
+    Ok(String::from_utf8_lossy(&block.data[..end]).into_owned())
+}
+
+/// Reads the data referenced by a data link: a `DT`, `SD` or `DZ` block, or a `DL` or `HL` list
+/// of those, concatenated in order
+pub fn read_data<R>(reader: &mut R, address: u64, visited: &mut Visited) -> io::Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    if address == 0 {
+        return Ok(Vec::new());
+    }
+
+    visited.visit(address)?;
+    let block = read_block(reader, address)?;
+    match &block.id {
+        b"##DT" | b"##SD" | b"##RD" => Ok(block.data),
+        b"##DZ" => inflate(&block),
+        b"##HL" => read_data(reader, block.link(0), visited),
+        b"##DL" => {
+            let mut data = Vec::new();
+            let mut list = Some(block);
+
+            while let Some(block) = list {
+                for link in block.links.iter().skip(1) {
+                    data.extend(read_data(reader, *link, visited)?);
+                }
+                list = match block.link(0) {
+                    0 => None,
+                    next => {
+                        visited.visit(next)?;
+                        Some(read_block(reader, next)?)
+                    }
+                };
+            }
+
+            Ok(data)
+        }
+        id => Err(malformed(format!(
+            "unexpected data block {}",
+            String::from_utf8_lossy(id)
+        ))),
+    }
+}
+
+/// Uncompresses a `DZ` block, undoing the transposition of records if used
+fn inflate(block: &Block) -> io::Result<Vec<u8>> {
+    let zip_type = block.u8(2);
+    let parameter = block.u32(4) as usize;
+    let original_len = block.u64(8) as usize;
+    let compressed = block
+        .data
+        .get(24..)
+        .ok_or_else(|| malformed("DZ block too short"))?;
+
+    let mut data = Vec::new();
+    ZlibDecoder::new(compressed).read_to_end(&mut data)?;
+    if data.len() != original_len {
+        return Err(malformed("DZ block length mismatch"));
+    }
+
+    match zip_type {
+        0 => Ok(data),
+        1 if parameter > 0 => {
+            // records were stored column by column, the remainder is not transposed
+            let rows = data.len() / parameter;
+            let mut records = data.clone();
+            for column in 0..parameter {
+                for row in 0..rows {
+                    records[row * parameter + column] = data[column * rows + row];
+                }
+            }
+            Ok(records)
+        }
+        zip_type => Err(malformed(format!("unknown zip type {}", zip_type))),
+    }
+}
+
+/// Builds a file in memory, blocks are appended at 8 byte aligned addresses
+#[derive(Debug, Default)]
+pub struct BlockWriter {
+    pub buffer: Vec<u8>,
+}
+
+impl BlockWriter {
+    /// Appends a block and returns its address
+    pub fn push(&mut self, id: &[u8; 4], links: &[u64], data: &[u8]) -> u64 {
+        let address = self.buffer.len() as u64;
+        let length = BLOCK_HEADER_SIZE + links.len() * 8 + data.len();
+
+        self.buffer.extend_from_slice(id);
+        self.buffer.extend_from_slice(&[0; 4]);
+        self.buffer
+            .extend_from_slice(&(length as u64).to_le_bytes());
+        self.buffer
+            .extend_from_slice(&(links.len() as u64).to_le_bytes());
+        for link in links {
+            self.buffer.extend_from_slice(&link.to_le_bytes());
+        }
+        self.buffer.extend_from_slice(data);
+
+        let padding = (8 - self.buffer.len() % 8) % 8;
+        self.buffer.resize(self.buffer.len() + padding, 0);
+
+        address
+    }
+
+    /// Appends a `TX` or `MD` block holding a zero terminated string, returns 0 for empty text
+    pub fn push_text(&mut self, id: &[u8; 4], text: &str) -> u64 {
+        if text.is_empty() {
+            return 0;
+        }
+
+        let mut data = text.as_bytes().to_vec();
+        data.push(0);
+        self.push(id, &[], &data)
+    }
+
+    /// Sets link `index` of the block at `address`
+    pub fn set_link(&mut self, address: u64, index: usize, target: u64) {
+        let offset = address as usize + BLOCK_HEADER_SIZE + index * 8;
+        self.buffer[offset..offset + 8].copy_from_slice(&target.to_le_bytes());
+    }
+}
@@ -0,0 +1,27 @@
+//! ASAM MDF4 measurement files
+//!
+//! `MdfWriter` stores the signals decoded with a `DbcLibrary` as one channel group per frame,
+//! for tools working with MDF4 measurements.  `MdfReader` reads the `CAN_DataFrame` records of
+//! bus logging files as `TraceFrame`s, which can be decoded with `DbcLibrary::decode_trace`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::mdf::MdfReader;
+//!
+//! let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+//! let reader = MdfReader::open("./tests/data/sample.mf4").unwrap();
+//!
+//! for decoded in lib.decode_trace(reader) {
+//!     let decoded = decoded.unwrap();
+//!     println!("{} {:?}", decoded.timestamp, decoded.signals);
+//! }
+//! ```
+
+mod block;
+mod reader;
+mod writer;
+
+pub use self::reader::MdfReader;
+pub use self::writer::MdfWriter;
@@ -0,0 +1,655 @@
+//! Reading channel groups and CAN bus logging from MDF4 files
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+use std::vec;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use super::block::{malformed, read_block, read_data, read_text, Block, Visited, ID_BLOCK_SIZE};
+use crate::id::{CanId, EXTENDED_ID_MAX, STANDARD_ID_MAX};
+use crate::trace::{dlc_to_len, Channel, Direction, FrameKind, TraceFrame};
+
+/// Channel types
+const CN_VLSD: u8 = 1;
+const CN_MASTER: u8 = 2;
+const CN_VIRTUAL_MASTER: u8 = 3;
+/// Synchronization type of time master channels
+const SYNC_TIME: u8 = 1;
+/// Channel group flag of groups holding variable length signal data
+const CG_VLSD: u16 = 0x0001;
+/// Linear conversion
+const CC_LINEAR: u8 = 1;
+/// Channel flag of channels with an invalidation bit
+const CN_INVAL_BIT_VALID: u32 = 0x0002;
+
+const CAN_DATA_FRAME: &str = "CAN_DataFrame";
+
+/// Channel of a channel group with everything needed to extract its values from a record
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChannelInfo {
+    pub name: String,
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub unit: String,
+    pub channel_type: u8,
+    pub sync_type: u8,
+    pub data_type: u8,
+    pub byte_offset: usize,
+    pub bit_offset: u32,
+    pub bit_count: u32,
+    /// Offset and factor of a linear conversion
+    pub conversion: Option<(f64, f64)>,
+    /// Signal data of variable length channels
+    pub signal_data: Vec<u8>,
+    /// Position of the invalidation bit in the record
+    pub invalidation: Option<usize>,
+}
+
+impl ChannelInfo {
+    /// Returns false if the invalidation bit of the channel is set in the record
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_valid(&self, record: &[u8]) -> bool {
+        match self.invalidation {
+            Some(bit) => record
+                .get(bit / 8)
+                .is_none_or(|byte| byte & (1 << (bit % 8)) == 0),
+            None => true,
+        }
+    }
+
+    /// Returns the raw bits of integer channels
+    pub fn raw(&self, record: &[u8]) -> u64 {
+        let bit_count = self.bit_count.min(64);
+        let len = ((self.bit_offset + bit_count).div_ceil(8) as usize).min(8);
+        if len == 0 {
+            return 0;
+        }
+        let mut bytes = record
+            .get(self.byte_offset..)
+            .unwrap_or(&[])
+            .iter()
+            .take(len)
+            .copied()
+            .collect::<Vec<_>>();
+        bytes.resize(8, 0);
+
+        // odd data types are big endian
+        let value = if self.data_type % 2 == 1 {
+            BigEndian::read_u64(&bytes) >> (8 * (8 - len))
+        } else {
+            LittleEndian::read_u64(&bytes)
+        } >> self.bit_offset;
+
+        if bit_count == 64 {
+            value
+        } else {
+            value & ((1 << bit_count) - 1)
+        }
+    }
+
+    /// Returns the physical value of numeric channels
+    pub fn value(&self, record: &[u8]) -> f64 {
+        let raw = self.raw(record);
+        let value = match self.data_type {
+            // signed integers
+            2 | 3 if self.bit_count > 0 && self.bit_count < 64 => {
+                let shift = 64 - self.bit_count;
+                ((raw << shift) as i64 >> shift) as f64
+            }
+            2 | 3 => raw as i64 as f64,
+            // floats
+            4 | 5 if self.bit_count == 32 => f64::from(f32::from_bits(raw as u32)),
+            4 | 5 => f64::from_bits(raw),
+            _ => raw as f64,
+        };
+
+        match self.conversion {
+            Some((offset, factor)) => value * factor + offset,
+            None => value,
+        }
+    }
+
+    /// Returns the bytes of byte array channels, looked up in the signal data for variable
+    /// length channels
+    pub fn bytes(&self, record: &[u8]) -> Vec<u8> {
+        if self.channel_type == CN_VLSD {
+            let start = usize::try_from(self.raw(record))
+                .ok()
+                .and_then(|offset| offset.checked_add(4));
+            let len = match start.and_then(|start| self.signal_data.get(start - 4..start)) {
+                Some(len) => LittleEndian::read_u32(len) as usize,
+                None => return Vec::new(),
+            };
+            return start
+                .and_then(|start| self.signal_data.get(start..start.checked_add(len)?))
+                .unwrap_or(&[])
+                .to_vec();
+        }
+
+        let len = (self.bit_count / 8) as usize;
+        record
+            .get(self.byte_offset..self.byte_offset + len)
+            .unwrap_or(&[])
+            .to_vec()
+    }
+}
+
+/// Channel group with its channels and records
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChannelGroup {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub acquisition_name: String,
+    pub channels: Vec<ChannelInfo>,
+    pub records: Vec<Vec<u8>>,
+}
+
+impl ChannelGroup {
+    pub fn channel(&self, name: &str) -> Option<&ChannelInfo> {
+        self.channels.iter().find(|channel| channel.name == name)
+    }
+
+    /// Returns the time master channel
+    pub fn master(&self) -> Option<&ChannelInfo> {
+        self.channels.iter().find(|channel| {
+            (channel.channel_type == CN_MASTER || channel.channel_type == CN_VIRTUAL_MASTER)
+                && channel.sync_type == SYNC_TIME
+        })
+    }
+
+    /// Returns the time of a record relative to the start of the measurement
+    pub fn time(&self, index: usize) -> f64 {
+        match self.master() {
+            Some(master) if master.channel_type == CN_VIRTUAL_MASTER => {
+                let index = index as f64;
+                match master.conversion {
+                    Some((offset, factor)) => index * factor + offset,
+                    None => index,
+                }
+            }
+            Some(master) => master.value(&self.records[index]),
+            None => 0.0,
+        }
+    }
+}
+
+/// Contents of an MDF4 file
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MdfFile {
+    /// Start of the measurement in seconds since the UNIX epoch
+    pub start_time: f64,
+    pub groups: Vec<ChannelGroup>,
+}
+
+impl MdfFile {
+    /// Reads all channel groups with their records
+    pub fn read<R>(reader: &mut R) -> io::Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let mut id = [0u8; ID_BLOCK_SIZE as usize];
+        reader.read_exact(&mut id)?;
+        if &id[..3] != b"MDF" {
+            return Err(malformed("missing MDF file identifier"));
+        }
+        let version = LittleEndian::read_u16(&id[28..]);
+        if version < 400 {
+            return Err(malformed(format!("unsupported MDF version {}", version)));
+        }
+
+        let header = read_block(reader, ID_BLOCK_SIZE)?;
+        if &header.id != b"##HD" {
+            return Err(malformed("missing header block"));
+        }
+
+        let mut file = MdfFile {
+            start_time: header.u64(0) as f64 * 1e-9,
+            groups: Vec::new(),
+        };
+
+        let mut visited = Visited::default();
+        let mut data_group = header.link(0);
+        while data_group != 0 {
+            visited.visit(data_group)?;
+            let block = read_block(reader, data_group)?;
+            file.read_data_group(reader, &block, &mut visited)?;
+            data_group = block.link(0);
+        }
+
+        Ok(file)
+    }
+
+    fn read_data_group<R>(
+        &mut self,
+        reader: &mut R,
+        data_group: &Block,
+        visited: &mut Visited,
+    ) -> io::Result<()>
+    where
+        R: Read + Seek,
+    {
+        let record_id_size = match data_group.u8(0) {
+            size @ (0 | 1 | 2 | 4 | 8) => usize::from(size),
+            size => return Err(malformed(format!("invalid record ID size {}", size))),
+        };
+
+        // record ID, record size, VLSD flag and group per channel group
+        let mut groups = Vec::new();
+        let mut channel_group = data_group.link(1);
+        while channel_group != 0 {
+            visited.visit(channel_group)?;
+            let block = read_block(reader, channel_group)?;
+            let flags = block.u16(16);
+            let data_bytes = block.u32(24) as usize;
+            let size = data_bytes + block.u32(28) as usize;
+
+            let group = ChannelGroup {
+                acquisition_name: read_text(reader, block.link(2))?,
+                channels: read_channels(reader, block.link(1), "", data_bytes, visited)?,
+                records: Vec::new(),
+            };
+            groups.push((block.u64(0), size, flags & CG_VLSD != 0, group));
+            channel_group = block.link(0);
+        }
+
+        let data = read_data(reader, data_group.link(2), visited)?;
+        let mut pos = 0;
+
+        while pos < data.len() {
+            let (index, record_id) = if record_id_size == 0 {
+                (0, 0)
+            } else {
+                let record_id = match data.get(pos..pos + record_id_size) {
+                    Some(bytes) => LittleEndian::read_uint(bytes, record_id_size),
+                    None => break,
+                };
+                pos += record_id_size;
+                let index = groups
+                    .iter()
+                    .position(|group| group.0 == record_id)
+                    .ok_or_else(|| malformed(format!("unknown record ID {}", record_id)))?;
+                (index, record_id)
+            };
+
+            let (_, size, vlsd, ref mut group) = groups
+                .get_mut(index)
+                .ok_or_else(|| malformed(format!("no channel group for record {}", record_id)))?;
+            let size = if *vlsd {
+                let len = data
+                    .get(pos..pos + 4)
+                    .map(LittleEndian::read_u32)
+                    .ok_or_else(|| malformed("truncated record"))?;
+                pos += 4;
+                len as usize
+            } else {
+                *size
+            };
+
+            let record = data
+                .get(pos..pos + size)
+                .ok_or_else(|| malformed("truncated record"))?;
+            if !*vlsd {
+                group.records.push(record.to_vec());
+            }
+            pos += size;
+        }
+
+        self.groups.extend(
+            groups
+                .into_iter()
+                .filter(|group| !group.2)
+                .map(|group| group.3),
+        );
+        Ok(())
+    }
+}
+
+/// Reads a list of channels and the channels they are composed of.  Names of nested channels
+/// are prefixed with the name of their parent unless they already are.  Invalidation bits
+/// follow the `data_bytes` of the record.
+fn read_channels<R>(
+    reader: &mut R,
+    mut address: u64,
+    parent: &str,
+    data_bytes: usize,
+    visited: &mut Visited,
+) -> io::Result<Vec<ChannelInfo>>
+where
+    R: Read + Seek,
+{
+    let mut channels = Vec::new();
+
+    while address != 0 {
+        visited.visit(address)?;
+        let block = read_block(reader, address)?;
+        if &block.id != b"##CN" {
+            return Err(malformed("expected channel block"));
+        }
+
+        let mut name = read_text(reader, block.link(2))?;
+        if !parent.is_empty() && !name.starts_with(parent) {
+            name = format!("{}.{}", parent, name);
+        }
+
+        let conversion = match block.link(4) {
+            0 => None,
+            cc => {
+                let cc = read_block(reader, cc)?;
+                if cc.u8(0) == CC_LINEAR {
+                    Some((cc.f64(24), cc.f64(32)))
+                } else {
+                    None
+                }
+            }
+        };
+
+        let channel_type = block.u8(0);
+        let signal_data = if channel_type == CN_VLSD {
+            read_data(reader, block.link(5), visited)?
+        } else {
+            Vec::new()
+        };
+
+        channels.push(ChannelInfo {
+            name: name.clone(),
+            unit: read_text(reader, block.link(6))?,
+            channel_type,
+            sync_type: block.u8(1),
+            data_type: block.u8(2),
+            bit_offset: u32::from(block.u8(3)),
+            byte_offset: block.u32(4) as usize,
+            bit_count: block.u32(8),
+            conversion,
+            signal_data,
+            invalidation: if block.u32(12) & CN_INVAL_BIT_VALID != 0 {
+                Some(8 * data_bytes + block.u32(16) as usize)
+            } else {
+                None
+            },
+        });
+
+        // structures link to their first member, arrays are not supported
+        let composition = block.link(1);
+        if composition != 0 && &read_block(reader, composition)?.id == b"##CN" {
+            channels.extend(read_channels(
+                reader,
+                composition,
+                &name,
+                data_bytes,
+                visited,
+            )?);
+        }
+
+        address = block.link(0);
+    }
+
+    Ok(channels)
+}
+
+/// Converts the records of a `CAN_DataFrame` bus logging group into frames
+fn read_can_frames(group: &ChannelGroup, start_time: f64) -> Vec<TraceFrame> {
+    let field = |name: &str| group.channel(&format!("{}.{}", CAN_DATA_FRAME, name));
+
+    let (id, data_bytes) = match (field("ID"), field("DataBytes")) {
+        (Some(id), Some(data_bytes)) => (id, data_bytes),
+        _ => return Vec::new(),
+    };
+    let ide = field("IDE");
+    let bus = field("BusChannel");
+    let dlc = field("DLC");
+    let data_length = field("DataLength");
+    let dir = field("Dir");
+    let edl = field("EDL");
+    let brs = field("BRS");
+    let esi = field("ESI");
+
+    let flag = |channel: Option<&ChannelInfo>, record: &[u8]| {
+        channel
+            .map(|channel| channel.raw(record) != 0)
+            .unwrap_or(false)
+    };
+
+    group
+        .records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let raw_id = id.raw(record) as u32;
+            // the IDE bit is stored in bit 31 of the ID if there is no separate channel
+            let extended = match ide {
+                Some(ide) => ide.raw(record) != 0,
+                None => raw_id & 0x8000_0000 != 0 || raw_id & EXTENDED_ID_MAX > STANDARD_ID_MAX,
+            };
+            let id = if extended {
+                CanId::Extended(raw_id & EXTENDED_ID_MAX)
+            } else {
+                CanId::Standard((raw_id & STANDARD_ID_MAX) as u16)
+            };
+
+            let kind = if flag(edl, record) {
+                FrameKind::Fd {
+                    brs: flag(brs, record),
+                    esi: flag(esi, record),
+                }
+            } else {
+                FrameKind::Data
+            };
+
+            let mut data = data_bytes.bytes(record);
+            let len = match (data_length, dlc) {
+                (Some(len), _) => len.raw(record) as usize,
+                (None, Some(dlc)) => dlc_to_len(dlc.raw(record) as u8),
+                (None, None) => data.len(),
+            };
+            data.truncate(len);
+
+            TraceFrame {
+                timestamp: start_time + group.time(index),
+                channel: Channel::Index(bus.map(|bus| bus.raw(record) as u16).unwrap_or(1)),
+                id,
+                kind,
+                direction: if flag(dir, record) {
+                    Direction::Tx
+                } else {
+                    Direction::Rx
+                },
+                data,
+            }
+        })
+        .collect()
+}
+
+/// Reads `CAN_DataFrame` records of ASAM MDF4 bus logging files, one `io::Result<TraceFrame>`
+/// per frame.  Frames of all bus logging channel groups are returned in order of their
+/// timestamps, which are seconds since the UNIX epoch.
+///
+/// The whole file is read on creation, as frames of different channel groups are interleaved.
+pub struct MdfReader {
+    start_time: f64,
+    frames: vec::IntoIter<TraceFrame>,
+}
+
+impl MdfReader {
+    /// Creates a reader from any seekable source
+    pub fn new<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let file = MdfFile::read(&mut reader)?;
+
+        let mut frames: Vec<TraceFrame> = file
+            .groups
+            .iter()
+            .flat_map(|group| read_can_frames(group, file.start_time))
+            .collect();
+        frames.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        Ok(MdfReader {
+            start_time: file.start_time,
+            frames: frames.into_iter(),
+        })
+    }
+
+    /// Opens an MDF4 file
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).and_then(|f| Self::new(BufReader::new(f)))
+    }
+
+    /// Returns the start of the measurement in seconds since the UNIX epoch
+    pub fn start_time(&self) -> f64 {
+        self.start_time
+    }
+}
+
+impl Iterator for MdfReader {
+    type Item = io::Result<TraceFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdf::block::BlockWriter;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_groups() {
+        let mut file = File::open("./tests/data/sample.mf4").unwrap();
+        let mdf = MdfFile::read(&mut file).unwrap();
+
+        let names: Vec<_> = mdf
+            .groups
+            .iter()
+            .map(|group| group.acquisition_name.as_str())
+            .collect();
+        assert_eq!(names, ["CAN1", "CAN2", "Misc"]);
+
+        // nested channels of the CAN_DataFrame structure
+        assert!(mdf.groups[0].channel("CAN_DataFrame.DataBytes").is_some());
+
+        // master with linear conversion from nanoseconds
+        assert!((mdf.groups[1].time(1) - 0.03).abs() < 1e-12);
+
+        let misc = &mdf.groups[2];
+        let temperature = misc.channel("Temperature").unwrap();
+        assert_eq!(temperature.unit, "degC");
+        assert_eq!(temperature.value(&misc.records[0]), 40.0);
+    }
+
+    #[test]
+    fn test_raw_values() {
+        let channel = ChannelInfo {
+            byte_offset: 1,
+            bit_offset: 4,
+            bit_count: 12,
+            ..Default::default()
+        };
+        assert_eq!(channel.raw(&[0xFF, 0x21, 0x43, 0xFF]), 0x432);
+
+        let signed = ChannelInfo {
+            data_type: 2,
+            bit_count: 8,
+            ..Default::default()
+        };
+        assert_eq!(signed.value(&[0xFE]), -2.0);
+
+        let big_endian = ChannelInfo {
+            data_type: 1,
+            bit_count: 16,
+            ..Default::default()
+        };
+        assert_eq!(big_endian.raw(&[0x12, 0x34]), 0x1234);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(MdfReader::new(Cursor::new(vec![0u8; 64])).is_err());
+
+        let mut mdf = b"MDF     3.30    ".to_vec();
+        mdf.resize(64, 0);
+        mdf[28..30].copy_from_slice(&330u16.to_le_bytes());
+        assert!(MdfReader::new(Cursor::new(mdf)).is_err());
+    }
+
+    /// Returns a file with the header linking to the first data group returned by `blocks`
+    fn mdf_file<F>(blocks: F) -> Vec<u8>
+    where
+        F: FnOnce(&mut BlockWriter) -> u64,
+    {
+        let mut file = BlockWriter::default();
+        file.buffer.extend_from_slice(b"MDF     4.10    ");
+        file.buffer.resize(ID_BLOCK_SIZE as usize, 0);
+        file.buffer[28..30].copy_from_slice(&410u16.to_le_bytes());
+        let hd = file.push(b"##HD", &[0; 6], &[0; 32]);
+        let dg = blocks(&mut file);
+        file.set_link(hd, 0, dg);
+        file.buffer
+    }
+
+    fn read_error(mdf: Vec<u8>) -> String {
+        MdfFile::read(&mut Cursor::new(mdf))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_malformed_blocks() {
+        // block length beyond the end of the file
+        let mut mdf = mdf_file(|file| file.push(b"##DG", &[0; 4], &[0; 8]));
+        let dg = mdf.len() - 64;
+        mdf[dg + 8..dg + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(read_error(mdf), "invalid block length at 0xa8");
+
+        // link count overflowing the block length
+        let mut mdf = mdf_file(|file| file.push(b"##DG", &[0; 4], &[0; 8]));
+        let dg = mdf.len() - 64;
+        mdf[dg + 16..dg + 24].copy_from_slice(&(u64::MAX / 4).to_le_bytes());
+        assert_eq!(read_error(mdf), "invalid block length at 0xa8");
+
+        // data groups linking to themselves
+        let mdf = mdf_file(|file| {
+            let dg = file.push(b"##DG", &[0; 4], &[0; 8]);
+            file.set_link(dg, 0, dg);
+            dg
+        });
+        assert_eq!(read_error(mdf), "cyclic link to 0xa8");
+
+        // channels linking back to their parent structure
+        let mdf = mdf_file(|file| {
+            let parent = file.push(b"##CN", &[0; 8], &[0; 72]);
+            let member = file.push(b"##CN", &[0; 8], &[0; 72]);
+            file.set_link(parent, 1, member);
+            file.set_link(member, 0, parent);
+            let cg = file.push(b"##CG", &[0, parent, 0, 0, 0, 0], &[0; 32]);
+            file.push(b"##DG", &[0, cg, 0, 0], &[0; 8])
+        });
+        assert!(read_error(mdf).starts_with("cyclic link to"));
+
+        let mdf = mdf_file(|file| file.push(b"##DG", &[0; 4], &[3, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(read_error(mdf), "invalid record ID size 3");
+    }
+
+    #[test]
+    fn test_vlsd_offset_overflow() {
+        let channel = ChannelInfo {
+            channel_type: CN_VLSD,
+            bit_count: 64,
+            signal_data: vec![1, 0, 0, 0, 0xAA],
+            ..Default::default()
+        };
+        assert_eq!(channel.bytes(&[0; 8]), [0xAA]);
+        assert!(channel.bytes(&[0xFF; 8]).is_empty());
+        assert!(channel
+            .bytes(&[0xFD, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF])
+            .is_empty());
+    }
+}
@@ -0,0 +1,403 @@
+//! Writing decoded signals to MDF4 files
+
+use std::collections::HashMap;
+use std::io;
+use std::io::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::block::{BlockWriter, ID_BLOCK_SIZE};
+use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal, Multiplexing};
+use crate::id::CanId;
+use crate::trace::TraceFrame;
+
+/// Channel data types
+const DT_UINT_LE: u8 = 0;
+const DT_INT_LE: u8 = 2;
+const DT_FLOAT_LE: u8 = 4;
+/// Channel types
+const CN_FIXED: u8 = 0;
+const CN_MASTER: u8 = 2;
+/// Synchronization types
+const SYNC_NONE: u8 = 0;
+const SYNC_TIME: u8 = 1;
+/// Linear conversion
+const CC_LINEAR: u8 = 1;
+/// Channel flag of channels with an invalidation bit
+const CN_INVAL_BIT_VALID: u32 = 0x0002;
+
+/// Records of a single frame, every signal takes 8 bytes after the 8 byte timestamp.  Records
+/// of multiplexed frames end with one invalidation bit per signal.
+struct GroupRecords<'a> {
+    frame: &'a DbcFrame,
+    signals: Vec<&'a DbcSignal>,
+    /// Size of the invalidation bits, 0 for frames without multiplexed signals
+    inval_bytes: usize,
+    records: Vec<u8>,
+    count: u64,
+}
+
+/// Writes decoded signals to an ASAM MDF 4.10 file
+///
+/// Every frame of the library that is written becomes a data group with a single channel group,
+/// named after the frame.  The group holds a time master channel in seconds and one channel per
+/// signal with the raw value, unit and a linear conversion from the scale and offset of the
+/// signal definition.  Multiplexed signals not selected by the multiplexor of a frame are marked
+/// invalid in its record.  Timestamps are stored relative to the first frame written, which is the
+/// start time of the file.
+///
+/// The file is assembled in memory and written by `finish`.
+///
+/// # Example
+///
+/// ```rust
+/// use fastcan::dbc::DbcLibrary;
+/// use fastcan::mdf::MdfWriter;
+/// use fastcan::trace::candump::CandumpReader;
+///
+/// let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+/// let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+///
+/// let mut writer = MdfWriter::new(Vec::new(), &lib);
+/// writer.write_trace(reader).unwrap();
+/// let mf4 = writer.finish().unwrap();
+///
+/// assert_eq!(&mf4[..8], b"MDF     ");
+/// ```
+pub struct MdfWriter<'a, W> {
+    writer: W,
+    lib: &'a DbcLibrary,
+    start_time: Option<f64>,
+    groups: Vec<GroupRecords<'a>>,
+    index: HashMap<CanId, usize>,
+}
+
+impl<'a, W: Write> MdfWriter<'a, W> {
+    /// Creates a writer for frames of the given library
+    pub fn new(writer: W, lib: &'a DbcLibrary) -> Self {
+        MdfWriter {
+            writer,
+            lib,
+            start_time: None,
+            groups: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Adds the signals of a frame.  Returns false if the library does not contain the ID or
+    /// the message is empty, in which case nothing is written.
+    pub fn write_frame(&mut self, timestamp: f64, id: CanId, msg: &[u8]) -> bool {
+        let frame = match self.lib.get_frame_by_id(id) {
+            Some(frame) if !msg.is_empty() => frame,
+            _ => return false,
+        };
+
+        let index = match self.index.get(&id) {
+            Some(index) => *index,
+            None => {
+                let mut signals = frame.get_signals();
+                signals.sort_by(|a, b| {
+                    let (a, b) = (a.get_definition(), b.get_definition());
+                    (a.start_bit, &a.name).cmp(&(b.start_bit, &b.name))
+                });
+                let multiplexed = signals.iter().any(|signal| {
+                    matches!(
+                        signal.get_definition().multiplexing,
+                        Multiplexing::Multiplexed(_)
+                    )
+                });
+                let inval_bytes = if multiplexed {
+                    signals.len().div_ceil(8)
+                } else {
+                    0
+                };
+                self.groups.push(GroupRecords {
+                    frame,
+                    signals,
+                    inval_bytes,
+                    records: Vec::new(),
+                    count: 0,
+                });
+                self.index.insert(id, self.groups.len() - 1);
+                self.groups.len() - 1
+            }
+        };
+
+        let start_time = *self.start_time.get_or_insert(timestamp);
+        let group = &mut self.groups[index];
+        let active = frame.get_signals_in_message(msg);
+
+        group
+            .records
+            .extend_from_slice(&(timestamp - start_time).to_le_bytes());
+        let mut invalid = vec![0u8; group.inval_bytes];
+        for (i, signal) in group.signals.iter().enumerate() {
+            let raw = if active
+                .iter()
+                .any(|other| other.get_definition().name == signal.get_definition().name)
+            {
+                signal.decode_raw(msg).unwrap_or_default()
+            } else {
+                invalid[i / 8] |= 1 << (i % 8);
+                0
+            };
+            group.records.extend_from_slice(&raw.to_le_bytes());
+        }
+        group.records.extend_from_slice(&invalid);
+        group.count += 1;
+
+        true
+    }
+
+    /// Adds the signals of all data frames of a trace.  Remote and error frames as well as IDs
+    /// not contained in the library are skipped, read errors are passed on.
+    pub fn write_trace<I>(&mut self, frames: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = io::Result<TraceFrame>>,
+    {
+        for frame in frames {
+            let frame = frame?;
+            if frame.is_data() {
+                self.write_frame(frame.timestamp, frame.id, &frame.data);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the file and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let start_time = self.start_time.unwrap_or_default();
+        let mut file = BlockWriter::default();
+
+        file.buffer.extend_from_slice(b"MDF     4.10    fastcan ");
+        file.buffer.resize(ID_BLOCK_SIZE as usize, 0);
+        file.buffer[28..30].copy_from_slice(&410u16.to_le_bytes());
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&((start_time * 1e9) as u64).to_le_bytes());
+        header.extend_from_slice(&[0; 24]);
+        let hd = file.push(b"##HD", &[0; 6], &header);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+        let comment = file.push_text(
+            b"##MD",
+            &format!(
+                "<FHcomment><TX>Decoded CAN signals</TX><tool_id>fastcan</tool_id>\
+                 <tool_vendor>fastcan</tool_vendor><tool_version>{}</tool_version></FHcomment>",
+                env!("CARGO_PKG_VERSION")
+            ),
+        );
+        let mut history = now.to_le_bytes().to_vec();
+        history.extend_from_slice(&[0; 8]);
+        let fh = file.push(b"##FH", &[0, comment], &history);
+        file.set_link(hd, 1, fh);
+
+        let mut previous_dg = None;
+        for group in &self.groups {
+            let dg = write_group(&mut file, group);
+            match previous_dg {
+                Some(previous) => file.set_link(previous, 0, dg),
+                None => file.set_link(hd, 0, dg),
+            }
+            previous_dg = Some(dg);
+        }
+
+        self.writer.write_all(&file.buffer)?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes the data group of a frame, returns its address
+fn write_group(file: &mut BlockWriter, group: &GroupRecords) -> u64 {
+    let dg = file.push(b"##DG", &[0; 4], &[0; 8]);
+
+    let record_len = 8 * (group.signals.len() as u32 + 1);
+    let mut data = Vec::new();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&group.count.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&u16::from(b'.').to_le_bytes());
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&record_len.to_le_bytes());
+    data.extend_from_slice(&(group.inval_bytes as u32).to_le_bytes());
+    let name = file.push_text(b"##TX", group.frame.get_name());
+    let cg = file.push(b"##CG", &[0, 0, name, 0, 0, 0], &data);
+    file.set_link(dg, 1, cg);
+
+    let time = write_channel(
+        file,
+        "time",
+        "s",
+        None,
+        (CN_MASTER, SYNC_TIME, DT_FLOAT_LE),
+        (0, 64),
+        None,
+    );
+    file.set_link(cg, 1, time);
+
+    let mut previous = time;
+    for (i, signal) in group.signals.iter().enumerate() {
+        let definition = signal.get_definition();
+        let data_type = if definition.signed {
+            DT_INT_LE
+        } else {
+            DT_UINT_LE
+        };
+
+        let cn = write_channel(
+            file,
+            &definition.name,
+            &definition.units,
+            Some((f64::from(definition.offset), f64::from(definition.scale))),
+            (CN_FIXED, SYNC_NONE, data_type),
+            (8 * (i as u32 + 1), definition.bit_len.min(64) as u32),
+            match definition.multiplexing {
+                Multiplexing::Multiplexed(_) => Some(i as u32),
+                _ => None,
+            },
+        );
+        file.set_link(previous, 0, cn);
+        previous = cn;
+    }
+
+    if !group.records.is_empty() {
+        let dt = file.push(b"##DT", &[], &group.records);
+        file.set_link(dg, 2, dt);
+    }
+
+    dg
+}
+
+/// Writes a channel with its name, unit, conversion and invalidation bit, returns its address
+fn write_channel(
+    file: &mut BlockWriter,
+    name: &str,
+    unit: &str,
+    conversion: Option<(f64, f64)>,
+    (channel_type, sync_type, data_type): (u8, u8, u8),
+    (byte_offset, bit_count): (u32, u32),
+    invalidation: Option<u32>,
+) -> u64 {
+    let name = file.push_text(b"##TX", name);
+    let unit = file.push_text(b"##TX", unit);
+    let conversion = match conversion {
+        Some((offset, factor)) => {
+            let mut data = vec![CC_LINEAR, 0];
+            data.extend_from_slice(&0u16.to_le_bytes());
+            data.extend_from_slice(&0u16.to_le_bytes());
+            data.extend_from_slice(&2u16.to_le_bytes());
+            data.extend_from_slice(&[0; 16]);
+            data.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&factor.to_le_bytes());
+            file.push(b"##CC", &[0; 4], &data)
+        }
+        None => 0,
+    };
+
+    let mut data = vec![channel_type, sync_type, data_type, 0];
+    data.extend_from_slice(&byte_offset.to_le_bytes());
+    data.extend_from_slice(&bit_count.to_le_bytes());
+    match invalidation {
+        Some(bit) => {
+            data.extend_from_slice(&CN_INVAL_BIT_VALID.to_le_bytes());
+            data.extend_from_slice(&bit.to_le_bytes());
+        }
+        None => data.extend_from_slice(&[0; 8]),
+    }
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&[0; 48]);
+
+    file.push(b"##CN", &[0, 0, name, 0, conversion, 0, unit, 0], &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdf::reader::MdfFile;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_write_decoded_signals() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let mut writer = MdfWriter::new(Vec::new(), &lib);
+
+        let id = CanId::Extended(0x0CF00400);
+        assert!(writer.write_frame(100.0, id, &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]));
+        assert!(writer.write_frame(100.5, id, &[0x11, 0x22, 0x33, 0xA8, 0x55, 0x66, 0x77, 0x88]));
+        assert!(!writer.write_frame(101.0, CanId::Standard(0x123), &[0x00]));
+        assert!(!writer.write_frame(101.0, id, &[]));
+
+        let mf4 = writer.finish().unwrap();
+        assert_eq!(mf4.len() % 8, 0);
+
+        let file = MdfFile::read(&mut Cursor::new(mf4)).unwrap();
+        assert_eq!(file.start_time, 100.0);
+        assert_eq!(file.groups.len(), 1);
+
+        let group = &file.groups[0];
+        assert_eq!(group.acquisition_name, "EEC1");
+        assert_eq!(group.records.len(), 2);
+        assert_eq!(group.time(1), 0.5);
+
+        let time = group.master().unwrap();
+        assert_eq!(time.unit, "s");
+
+        let speed = group.channel("Engine_Speed").unwrap();
+        assert_eq!(speed.invalidation, None);
+        assert_eq!(speed.unit, "rpm");
+        assert_eq!(speed.bit_count, 16);
+        assert_eq!(speed.conversion, Some((0.0, 0.125)));
+        assert_eq!(speed.raw(&group.records[0]), 0x5544);
+        assert_eq!(speed.value(&group.records[0]), 2728.5);
+        assert_eq!(speed.value(&group.records[1]), 2741.0);
+    }
+
+    #[test]
+    fn test_write_multiplexed_signals() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+        let mut writer = MdfWriter::new(Vec::new(), &lib);
+
+        let id = CanId::Standard(0x700);
+        assert!(writer.write_frame(1.0, id, &[0x01, 0x05, 0, 0, 0, 0, 0x12, 0x34]));
+        assert!(writer.write_frame(2.0, id, &[0x00, 0x12, 0x34, 0, 0, 0, 0, 0]));
+
+        let mf4 = writer.finish().unwrap();
+        let file = MdfFile::read(&mut Cursor::new(mf4)).unwrap();
+        let group = &file.groups[0];
+        assert_eq!(group.records[0].len(), 8 * 5 + 1);
+
+        let mode = group.channel("Mode").unwrap();
+        let speed = group.channel("Speed").unwrap();
+        let pressure = group.channel("Pressure").unwrap();
+        let valve = group.channel("Valve").unwrap();
+        assert_eq!(mode.invalidation, None);
+
+        // Mode 1 selects Pressure and Valve
+        let record = &group.records[0];
+        assert!(mode.is_valid(record));
+        assert!(!speed.is_valid(record));
+        assert_eq!(speed.raw(record), 0);
+        assert!(pressure.is_valid(record));
+        assert_eq!(pressure.value(record), 10.0);
+        assert!(valve.is_valid(record));
+
+        // Mode 0 selects Speed
+        let record = &group.records[1];
+        assert!(speed.is_valid(record));
+        assert_eq!(speed.raw(record), 0x1234);
+        assert!(!pressure.is_valid(record));
+        assert_eq!(pressure.raw(record), 0);
+        assert!(!valve.is_valid(record));
+    }
+
+    #[test]
+    fn test_write_empty_file() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let mf4 = MdfWriter::new(Vec::new(), &lib).finish().unwrap();
+
+        let file = MdfFile::read(&mut Cursor::new(mf4)).unwrap();
+        assert!(file.groups.is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! SocketCAN integration, available with the `use-socketcan` feature
+//!
+//! Implements `DecodeMessage`, `DecodeFrame` and `EncodeMessage` for the frame types of the
+//! `socketcan` crate, and provides `decode_frames` for decoding everything received on a socket.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::socket::decode_frames;
+//! use socketcan::{CanSocket, Socket};
+//!
+//! let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+//! let socket = CanSocket::open("vcan0").unwrap();
+//!
+//! for decoded in decode_frames(&socket, &lib) {
+//!     let decoded = decoded.unwrap();
+//!     println!("{} {:?}", decoded.frame.get_name(), decoded.signals);
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use socketcan::{
+    CanFdFrame, CanFrame, EmbeddedFrame, ExtendedId, Frame as SocketFrame, Id, Socket, StandardId,
+};
+
+use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal};
+use crate::id::CanId;
+use crate::mapper::{DecodeFrame, DecodeMessage, DecodedFrame, EncodeMessage};
+
+impl From<Id> for CanId {
+    fn from(id: Id) -> Self {
+        match id {
+            Id::Standard(id) => CanId::Standard(id.as_raw()),
+            Id::Extended(id) => CanId::Extended(id.as_raw()),
+        }
+    }
+}
+
+/// Converts a `CanId` into the identifier type used by `socketcan`, `None` if the ID is out of
+/// range for its type
+pub fn to_socketcan_id(id: CanId) -> Option<Id> {
+    match id {
+        CanId::Standard(id) => StandardId::new(id).map(Id::Standard),
+        CanId::Extended(id) => ExtendedId::new(id).map(Id::Extended),
+    }
+}
+
+impl DecodeMessage<&CanFrame> for DbcSignal {
+    /// Decodes a signal from a classic socketcan frame
+    fn decode_message(&self, msg: &CanFrame) -> Option<f32> {
+        self.decode_message(msg.data())
+    }
+}
+
+impl DecodeMessage<&CanFdFrame> for DbcSignal {
+    /// Decodes a signal from a socketcan FD frame
+    fn decode_message(&self, msg: &CanFdFrame) -> Option<f32> {
+        self.decode_message(msg.data())
+    }
+}
+
+impl DecodeFrame<&CanFrame> for DbcFrame {
+    /// Decodes all signals from a classic socketcan frame
+    fn decode_frame(&self, msg: &CanFrame) -> Option<HashMap<String, f32>> {
+        self.decode_frame(msg.data())
+    }
+}
+
+impl DecodeFrame<&CanFdFrame> for DbcFrame {
+    /// Decodes all signals from a socketcan FD frame
+    fn decode_frame(&self, msg: &CanFdFrame) -> Option<HashMap<String, f32>> {
+        self.decode_frame(msg.data())
+    }
+}
+
+impl EncodeMessage<CanFrame> for DbcFrame {
+    ///
+    /// Encodes Hashmap of signal data into a classic socketcan frame with the ID and length of
+    /// the DBC frame
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use fastcan::{dbc::DbcLibrary, mapper::EncodeMessage};
+    /// use socketcan::{CanFrame, EmbeddedFrame};
+    ///
+    /// use std::collections::HashMap;
+    ///
+    /// let dbc = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    ///
+    /// let mut signal_map: HashMap<String, f64> = HashMap::new();
+    /// signal_map.insert("Engine_Speed".to_string(), 2728.5);
+    ///
+    /// let frame: CanFrame = dbc.get_frame(2364539904).unwrap().encode_message(&signal_map).unwrap();
+    ///
+    /// assert!(frame.is_extended());
+    /// ```
+    ///
+    fn encode_message(&self, signal_map: &HashMap<String, f64>) -> Result<CanFrame, String> {
+        let data = encode_payload(self, signal_map)?;
+        let id = to_socketcan_id(self.get_can_id())
+            .ok_or_else(|| format!("Invalid CAN ID: {}", self.get_can_id()))?;
+
+        CanFrame::new(id, &data).ok_or_else(|| format!("Invalid frame length: {}", data.len()))
+    }
+}
+
+impl EncodeMessage<CanFdFrame> for DbcFrame {
+    /// Encodes Hashmap of signal data into a socketcan FD frame with the ID and length of the DBC
+    /// frame
+    fn encode_message(&self, signal_map: &HashMap<String, f64>) -> Result<CanFdFrame, String> {
+        let data = encode_payload(self, signal_map)?;
+        let id = to_socketcan_id(self.get_can_id())
+            .ok_or_else(|| format!("Invalid CAN ID: {}", self.get_can_id()))?;
+
+        CanFdFrame::new(id, &data).ok_or_else(|| format!("Invalid frame length: {}", data.len()))
+    }
+}
+
+/// Encodes the signals and truncates the payload to the frame length
+fn encode_payload(frame: &DbcFrame, signal_map: &HashMap<String, f64>) -> Result<Vec<u8>, String> {
+    let mut data: Vec<u8> = frame.encode_message(signal_map)?;
+    data.truncate(frame.get_message_len() as usize);
+    Ok(data)
+}
+
+/// Iterator returned by `decode_frames`
+pub struct DecodeFrames<'a, S> {
+    socket: &'a S,
+    lib: &'a DbcLibrary,
+}
+
+impl<'a, S> Iterator for DecodeFrames<'a, S>
+where
+    S: Socket,
+    S::FrameType: SocketFrame,
+{
+    type Item = io::Result<DecodedFrame<'a>>;
+
+    /// Blocks until a frame contained in the library is received.  Remote and error frames as
+    /// well as unknown IDs are skipped, read errors are passed on.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = match self.socket.read_frame() {
+                Ok(frame) => frame,
+                Err(e) => return Some(Err(e)),
+            };
+            if frame.is_remote_frame() || frame.is_error_frame() {
+                continue;
+            }
+
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs_f64())
+                .unwrap_or_default();
+
+            if let Some(decoded) = self.lib.decode(timestamp, frame.id().into(), frame.data()) {
+                return Some(Ok(decoded));
+            }
+        }
+    }
+}
+
+/// Reads frames from a socketcan socket and decodes them with a `DbcLibrary`.  Works with both
+/// `CanSocket` and `CanFdSocket`, frames are timestamped on reception.
+pub fn decode_frames<'a, S>(socket: &'a S, lib: &'a DbcLibrary) -> DecodeFrames<'a, S>
+where
+    S: Socket,
+    S::FrameType: SocketFrame,
+{
+    DecodeFrames { socket, lib }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use socketcan::{CanFdSocket, CanSocket};
+    use std::os::fd::OwnedFd;
+    use std::os::unix::net::UnixDatagram;
+
+    /// Raw `struct can_frame` as sent by the kernel
+    fn raw_frame(id: u32, data: &[u8]) -> [u8; 16] {
+        let mut raw = [0u8; 16];
+        raw[..4].copy_from_slice(&id.to_ne_bytes());
+        raw[4] = data.len() as u8;
+        raw[8..8 + data.len()].copy_from_slice(data);
+        raw
+    }
+
+    #[test]
+    fn test_decode_frames_socketpair() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let (tx, rx) = UnixDatagram::pair().unwrap();
+        let socket = CanSocket::from(OwnedFd::from(rx));
+
+        // unknown standard frame, then EEC1 with the extended frame flag set
+        tx.send(&raw_frame(0x123, &[0; 8])).unwrap();
+        tx.send(&raw_frame(
+            0x8CF00400,
+            &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88],
+        ))
+        .unwrap();
+
+        let decoded = decode_frames(&socket, &lib).next().unwrap().unwrap();
+
+        assert_eq!(decoded.frame.get_name(), "EEC1");
+        assert_eq!(decoded.signals["Engine_Speed"], 2728.5);
+        assert!(decoded.timestamp > 0.0);
+    }
+
+    #[test]
+    fn test_decode_frames_fd_socket() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let (tx, rx) = UnixDatagram::pair().unwrap();
+        let socket = CanFdSocket::from(OwnedFd::from(rx));
+
+        tx.send(&raw_frame(
+            0x8CF00400,
+            &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88],
+        ))
+        .unwrap();
+
+        let decoded = decode_frames(&socket, &lib).next().unwrap().unwrap();
+
+        assert_eq!(decoded.signals["Engine_Speed"], 2728.5);
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+        let frame = lib.get_frame(2364539904).unwrap();
+        let mut signal_map: HashMap<String, f64> = HashMap::new();
+        signal_map.insert("Engine_Speed".to_string(), 2728.5);
+
+        let can_frame: CanFrame = frame.encode_message(&signal_map).unwrap();
+        let fd_frame: CanFdFrame = frame.encode_message(&signal_map).unwrap();
+
+        assert_eq!(CanId::from(can_frame.id()), CanId::Extended(0x0CF00400));
+        assert_eq!(can_frame.data().len(), 8);
+        assert_eq!(
+            frame.decode_frame(&can_frame).unwrap()["Engine_Speed"],
+            2728.5
+        );
+        assert_eq!(
+            frame
+                .get_signal("Engine_Speed")
+                .unwrap()
+                .decode_message(&fd_frame),
+            Some(2728.5)
+        );
+    }
+
+    #[test]
+    fn test_encode_decode_fd_roundtrip() {
+        let lib = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+        let frame = lib.get_frame(0x600).unwrap();
+        let mut signal_map: HashMap<String, f64> = HashMap::new();
+        signal_map.insert("Checksum".to_string(), 165.0);
+        signal_map.insert("Head".to_string(), 1.0);
+        signal_map.insert("Temperature".to_string(), -30.0);
+        signal_map.insert("Tail".to_string(), 4660.0);
+
+        let fd_frame: CanFdFrame = frame.encode_message(&signal_map).unwrap();
+
+        assert_eq!(fd_frame.data().len(), 64);
+        assert_eq!(&fd_frame.data()[60..], &[0x34, 0x12, 0x00, 0xA5]);
+        let signals = frame.decode_frame(&fd_frame).unwrap();
+        assert_eq!(signals["Checksum"], 165.0);
+        assert_eq!(signals["Head"], 1.0);
+        assert_eq!(signals["Temperature"], -30.0);
+        assert_eq!(signals["Tail"], 4660.0);
+    }
+}
@@ -0,0 +1,822 @@
+//! Reading PCAN symbol files of PEAK-System
+//!
+//! Symbol files (`.sym`, `FormatVersion=6.0`) list the messages of a bus in the `{SEND}`,
+//! `{RECEIVE}` and `{SENDRECEIVE}` sections.  Each `[Name]` block becomes a `DbcFrame`, its `Var=`
+//! lines and the `Sig=` references to the `{SIGNALS}` section its `DbcSignal`s, and the enums of
+//! the `{ENUMS}` section referenced with `/e:` their value tables.  Comments behind variables are
+//! kept as descriptions, `CycleTime=` as `GenMsgCycleTime` attribute.
+//!
+//! Multiplexed messages are written as several blocks with the same ID, each with a `Mux=` line
+//! giving the multiplexor and its value.  The blocks are merged into one frame, variables of a
+//! block are multiplexed with the value of its `Mux=` line.  Variables repeated in blocks of
+//! several values are kept as plain signals.
+//!
+//! Big endian variables (`-m`) are numbered like in KCD files, from the most significant bit of
+//! the first byte, and converted to the numbering of `DbcSignalDefinition`.  Symbol files know
+//! no nodes, so senders and receivers are `Vector__XXX`.  Floating point variables (`float` and
+//! `double`), strings of more than 64 bits and the direction of the sections are not supported
+//! and skipped.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//!
+//! let lib = DbcLibrary::from_sym_file("./tests/data/sample.sym").unwrap();
+//!
+//! let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+//! let decoded = lib.decode(0.0, lib.get_frame(0x0CF00400).unwrap().get_can_id(), &payload);
+//! assert_eq!(decoded.unwrap().signals["Engine_Speed"], 2728.5);
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use encoding::all::WINDOWS_1252;
+use encoding::{DecoderTrap, Encoding};
+
+use crate::dbc::{
+    invalid, DbcFrame, DbcLibrary, DbcSignal, DbcSignalDefinition, Multiplexing, ValueDefinition,
+    CYCLE_TIME, NO_NODE,
+};
+use crate::id::CanId;
+
+/// Sections holding messages
+const MESSAGE_SECTIONS: [&str; 3] = ["SEND", "RECEIVE", "SENDRECEIVE"];
+
+impl DbcLibrary {
+    /// Reads the messages of a PCAN symbol file, which PCAN tools write in Windows-1252
+    pub fn from_sym_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let data = WINDOWS_1252
+            .decode(&fs::read(path)?, DecoderTrap::Replace)
+            .map_err(io::Error::other)?;
+        Self::from_sym_str(&data)
+    }
+
+    /// Reads the messages of a PCAN symbol file
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the file does not start with a supported
+    /// `FormatVersion`, or has messages, variables or enums that can not be parsed.  The error
+    /// gives the line number.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastcan::dbc::DbcLibrary;
+    ///
+    /// let lib = DbcLibrary::from_sym_str(
+    ///     "FormatVersion=6.0\n{SEND}\n[Status]\nID=64h\nDLC=1\nVar=Running bit 0,1",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(lib.get_frame(100).unwrap().get_name(), "Status");
+    /// ```
+    pub fn from_sym_str(data: &str) -> io::Result<Self> {
+        let lines = read_lines(data)?;
+        let enums = read_enums(&lines)?;
+        let signals = read_signals(&lines, &enums)?;
+
+        let mut lib = DbcLibrary::default();
+        for group in group_messages(read_messages(&lines, &enums, &signals)?)? {
+            let frame = build_frame(group)?;
+            lib.frames_mut().insert(frame.get_can_id(), frame);
+        }
+        Ok(lib)
+    }
+}
+
+fn invalid_line(number: usize, error: String) -> io::Error {
+    invalid(format!("line {}: {}", number, error))
+}
+
+/// Line of a symbol file without comment
+struct Line<'a> {
+    number: usize,
+    section: &'a str,
+    text: &'a str,
+    comment: Option<&'a str>,
+}
+
+/// Splits a line at a `//` outside of quotes
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quoted = false;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '/' if previous == '/' && !quoted => {
+                let comment = line[index + 1..].trim();
+                return (&line[..index - 1], Some(comment).filter(|c| !c.is_empty()));
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    (line, None)
+}
+
+/// Splits the text at separators outside of quotes
+fn split_unquoted(text: &str, separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        if c == '"' {
+            quoted = !quoted;
+            part.push(c);
+        } else if !quoted && separator(c) {
+            parts.push(std::mem::take(&mut part));
+        } else {
+            part.push(c);
+        }
+    }
+    parts.push(part);
+    parts
+}
+
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// Parses a decimal number or a hexadecimal number with `h` suffix
+fn number(text: &str) -> Option<i64> {
+    let text = text.trim();
+    match text.strip_suffix(['h', 'H']) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn read_lines(data: &str) -> io::Result<Vec<Line<'_>>> {
+    let mut lines = Vec::new();
+    let mut section = "";
+    let mut version = None;
+
+    for (index, line) in data.lines().enumerate() {
+        let (text, comment) = split_comment(line);
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        if version.is_none() {
+            version = text.strip_prefix("FormatVersion=").map(str::trim);
+            match version {
+                Some(version) if version.split('.').next() == Some("6") => continue,
+                Some(version) => {
+                    return Err(invalid(format!("unsupported FormatVersion {}", version)))
+                }
+                None => return Err(invalid("not a PCAN symbol file")),
+            }
+        }
+
+        if let Some(name) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+            section = name.trim();
+            continue;
+        }
+
+        lines.push(Line {
+            number: index + 1,
+            section,
+            text,
+            comment,
+        });
+    }
+
+    if version.is_none() {
+        return Err(invalid("not a PCAN symbol file"));
+    }
+    Ok(lines)
+}
+
+/// Reads the `enum Name(value="label", ...)` lines of the `{ENUMS}` section, which may continue
+/// over several lines
+fn read_enums(lines: &[Line]) -> io::Result<HashMap<String, ValueDefinition>> {
+    let mut enums = HashMap::new();
+    let mut pending = String::new();
+    let mut first = 0;
+
+    for line in lines.iter().filter(|line| line.section == "ENUMS") {
+        if pending.is_empty() {
+            first = line.number;
+        } else {
+            pending.push(' ');
+        }
+        pending.push_str(line.text);
+
+        // the definition ends with a closing parenthesis outside of quotes
+        let closed = split_unquoted(&pending, |c| c == ')').len() > 1;
+        if !closed {
+            continue;
+        }
+
+        let definition = std::mem::take(&mut pending);
+        let error = || invalid_line(first, format!("invalid enum \"{}\"", definition));
+        let body = definition.strip_prefix("enum").ok_or_else(error)?;
+        let open = body.find('(').ok_or_else(error)?;
+        let close = body.rfind(')').ok_or_else(error)?;
+        let name = body[..open].trim().to_string();
+
+        let mut values = Vec::new();
+        for entry in split_unquoted(&body[open + 1..close], |c| c == ',') {
+            if entry.trim().is_empty() {
+                continue;
+            }
+            let (value, label) = entry.split_once('=').ok_or_else(error)?;
+            let value = number(value).ok_or_else(error)?;
+            values.push((value, unquote(label).to_string()));
+        }
+        enums.insert(name, ValueDefinition::new(values));
+    }
+
+    if !pending.is_empty() {
+        return Err(invalid_line(first, "unterminated enum".to_string()));
+    }
+    Ok(enums)
+}
+
+/// Variable of a message, the start bit holds the offset of the symbol file until the message
+/// length is known
+#[derive(Clone)]
+struct SymVariable {
+    line: usize,
+    definition: DbcSignalDefinition,
+    description: Option<String>,
+    values: Option<ValueDefinition>,
+}
+
+/// Parses a variable of the given type and flags, `None` for unsupported types
+fn read_variable(
+    line: &Line,
+    name: &str,
+    kind: &str,
+    start_bit: usize,
+    bit_len: usize,
+    flags: &[String],
+    enums: &HashMap<String, ValueDefinition>,
+) -> io::Result<Option<SymVariable>> {
+    let signed = match kind {
+        "unsigned" | "bit" | "char" | "raw" | "string" => false,
+        "signed" => true,
+        "float" | "double" => return Ok(None),
+        _ => {
+            return Err(invalid_line(
+                line.number,
+                format!("unknown type \"{}\" of variable {}", kind, name),
+            ))
+        }
+    };
+    if bit_len == 0 || bit_len > 64 {
+        return Ok(None);
+    }
+
+    let mut definition = DbcSignalDefinition {
+        name: name.to_string(),
+        start_bit,
+        bit_len,
+        little_endian: true,
+        signed,
+        scale: 1.0,
+        offset: 0.0,
+        min_value: 0.0,
+        max_value: 0.0,
+        units: String::new(),
+        receiving_node: NO_NODE.to_string(),
+        multiplexing: Multiplexing::None,
+    };
+    let mut values = None;
+
+    for flag in flags {
+        if flag == "-m" {
+            definition.little_endian = false;
+            continue;
+        }
+        let (key, value) = match flag.split_once(':') {
+            Some((key, value)) => (key, unquote(value)),
+            // display flags like `-h` and `-b`
+            None => continue,
+        };
+        let float = || {
+            value.parse::<f32>().map_err(|_| {
+                invalid_line(
+                    line.number,
+                    format!("invalid {} \"{}\" of variable {}", key, value, name),
+                )
+            })
+        };
+        match key {
+            "/u" => definition.units = value.to_string(),
+            "/f" => definition.scale = float()?,
+            "/o" => definition.offset = float()?,
+            "/min" => definition.min_value = float()?,
+            "/max" => definition.max_value = float()?,
+            "/e" => {
+                values = Some(enums.get(value).cloned().ok_or_else(|| {
+                    invalid_line(line.number, format!("unknown enum \"{}\"", value))
+                })?)
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Some(SymVariable {
+        line: line.number,
+        definition,
+        description: line.comment.map(str::to_string),
+        values,
+    }))
+}
+
+/// Parses a `start,length` position
+fn position(line: &Line, name: &str, text: Option<&String>) -> io::Result<(usize, usize)> {
+    text.and_then(|text| {
+        let (start, len) = text.split_once(',')?;
+        Some((start.trim().parse().ok()?, len.trim().parse().ok()?))
+    })
+    .ok_or_else(|| {
+        invalid_line(
+            line.number,
+            format!("invalid position of variable {}", name),
+        )
+    })
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    split_unquoted(text, char::is_whitespace)
+        .into_iter()
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// Reads the `Sig=Name type length flags` definitions of the `{SIGNALS}` section
+fn read_signals(
+    lines: &[Line],
+    enums: &HashMap<String, ValueDefinition>,
+) -> io::Result<HashMap<String, SymVariable>> {
+    let mut signals = HashMap::new();
+
+    for line in lines.iter().filter(|line| line.section == "SIGNALS") {
+        let text = match line.text.strip_prefix("Sig=") {
+            Some(text) => text,
+            None => continue,
+        };
+        let tokens = tokens(text);
+        let (name, kind) = match (tokens.first(), tokens.get(1)) {
+            (Some(name), Some(kind)) => (name, kind),
+            _ => {
+                return Err(invalid_line(
+                    line.number,
+                    format!("invalid signal \"{}\"", text),
+                ))
+            }
+        };
+        let bit_len = tokens
+            .get(2)
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(|| {
+                invalid_line(line.number, format!("invalid length of signal {}", name))
+            })?;
+
+        let flags = tokens.get(3..).unwrap_or_default();
+        if let Some(signal) = read_variable(line, name, kind, 0, bit_len, flags, enums)? {
+            signals.insert(name.clone(), signal);
+        }
+    }
+
+    Ok(signals)
+}
+
+/// Block of a message section
+struct SymMessage {
+    name: String,
+    line: usize,
+    id: Option<u32>,
+    extended: bool,
+    len: Option<u32>,
+    cycle_time: Option<String>,
+    multiplexor: Option<(SymVariable, u32)>,
+    variables: Vec<SymVariable>,
+}
+
+fn read_messages(
+    lines: &[Line],
+    enums: &HashMap<String, ValueDefinition>,
+    signals: &HashMap<String, SymVariable>,
+) -> io::Result<Vec<SymMessage>> {
+    let mut messages: Vec<SymMessage> = Vec::new();
+
+    for line in lines {
+        if !MESSAGE_SECTIONS.contains(&line.section) {
+            continue;
+        }
+        if let Some(name) = line
+            .text
+            .strip_prefix('[')
+            .and_then(|t| t.strip_suffix(']'))
+        {
+            messages.push(SymMessage {
+                name: name.trim().to_string(),
+                line: line.number,
+                id: None,
+                extended: false,
+                len: None,
+                cycle_time: None,
+                multiplexor: None,
+                variables: Vec::new(),
+            });
+            continue;
+        }
+
+        let (message, (key, value)) = match (messages.last_mut(), line.text.split_once('=')) {
+            (Some(message), Some(entry)) => (message, entry),
+            _ => continue,
+        };
+        let value = value.trim();
+        let tokens = tokens(value);
+        let name = tokens.first().map(String::as_str).unwrap_or_default();
+
+        match key.trim() {
+            "ID" => {
+                message.id = Some(
+                    number(value)
+                        .and_then(|id| u32::try_from(id).ok())
+                        .ok_or_else(|| {
+                            invalid_line(
+                                line.number,
+                                format!("invalid ID \"{}\" of message {}", value, message.name),
+                            )
+                        })?,
+                )
+            }
+            "Type" => message.extended = value.eq_ignore_ascii_case("extended"),
+            "DLC" | "Length" => {
+                message.len = Some(value.parse().map_err(|_| {
+                    invalid_line(
+                        line.number,
+                        format!("invalid length \"{}\" of message {}", value, message.name),
+                    )
+                })?)
+            }
+            "CycleTime" => message.cycle_time = Some(value.to_string()),
+            "Var" => {
+                let kind = tokens.get(1).map(String::as_str).unwrap_or_default();
+                let (start, len) = position(line, name, tokens.get(2))?;
+                let flags = tokens.get(3..).unwrap_or_default();
+                message
+                    .variables
+                    .extend(read_variable(line, name, kind, start, len, flags, enums)?);
+            }
+            "Sig" => {
+                let mut signal = signals.get(name).cloned().ok_or_else(|| {
+                    invalid_line(line.number, format!("unknown signal \"{}\"", name))
+                })?;
+                signal.definition.start_bit = tokens
+                    .get(1)
+                    .and_then(|start| start.parse().ok())
+                    .ok_or_else(|| {
+                        invalid_line(line.number, format!("invalid position of signal {}", name))
+                    })?;
+                signal.line = line.number;
+                if line.comment.is_some() {
+                    signal.description = line.comment.map(str::to_string);
+                }
+                message.variables.push(signal);
+            }
+            "Mux" => {
+                let (start, len) = position(line, name, tokens.get(1))?;
+                let mux_value = tokens
+                    .get(2)
+                    .and_then(|value| number(value))
+                    .and_then(|value| u32::try_from(value).ok())
+                    .ok_or_else(|| {
+                        invalid_line(
+                            line.number,
+                            format!("invalid value of multiplexor {}", name),
+                        )
+                    })?;
+                let flags = tokens.get(3..).unwrap_or_default();
+                let multiplexor = read_variable(line, name, "unsigned", start, len, flags, enums)?;
+                message.multiplexor = multiplexor.map(|variable| (variable, mux_value));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Groups the blocks of multiplexed messages by ID, in the order of the file
+fn group_messages(messages: Vec<SymMessage>) -> io::Result<Vec<(CanId, Vec<SymMessage>)>> {
+    let mut groups: Vec<(CanId, Vec<SymMessage>)> = Vec::new();
+
+    for message in messages {
+        let id = message.id.ok_or_else(|| {
+            invalid_line(message.line, format!("message {} without ID", message.name))
+        })?;
+        let id = CanId::new(id, message.extended).ok_or_else(|| {
+            invalid_line(
+                message.line,
+                format!("invalid ID {:X}h of message {}", id, message.name),
+            )
+        })?;
+
+        match groups.iter_mut().find(|(group_id, _)| *group_id == id) {
+            Some((_, group)) if group[0].name == message.name => group.push(message),
+            Some(_) => {
+                return Err(invalid_line(
+                    message.line,
+                    format!("duplicate message ID 0x{}", id),
+                ))
+            }
+            None => groups.push((id, vec![message])),
+        }
+    }
+
+    Ok(groups)
+}
+
+fn build_frame((id, group): (CanId, Vec<SymMessage>)) -> io::Result<DbcFrame> {
+    let mut variables: Vec<SymVariable> = Vec::new();
+    for message in &group {
+        let multiplexing = match &message.multiplexor {
+            Some((multiplexor, value)) => {
+                if !variables
+                    .iter()
+                    .any(|variable| variable.definition.name == multiplexor.definition.name)
+                {
+                    let mut multiplexor = multiplexor.clone();
+                    multiplexor.definition.multiplexing = Multiplexing::Multiplexor;
+                    variables.push(multiplexor);
+                }
+                Multiplexing::Multiplexed(*value)
+            }
+            None => Multiplexing::None,
+        };
+
+        for variable in &message.variables {
+            match variables
+                .iter_mut()
+                .find(|known| known.definition.name == variable.definition.name)
+            {
+                // repeated in the blocks of several values
+                Some(known) if known.definition.multiplexing != multiplexing => {
+                    known.definition.multiplexing = Multiplexing::None
+                }
+                Some(_) => {}
+                None => {
+                    let mut variable = variable.clone();
+                    variable.definition.multiplexing = multiplexing;
+                    variables.push(variable);
+                }
+            }
+        }
+    }
+
+    let first = &group[0];
+    let message_len = group
+        .iter()
+        .find_map(|message| message.len)
+        .unwrap_or_else(|| {
+            variables
+                .iter()
+                .map(|variable| {
+                    (variable.definition.start_bit + variable.definition.bit_len).div_ceil(8)
+                })
+                .max()
+                .unwrap_or(0) as u32
+        });
+    let bits = 8 * (message_len as usize).max(8);
+
+    let mut signal_map = HashMap::new();
+    for variable in variables {
+        let line = variable.line;
+        let mut definition = variable.definition;
+        if !definition.little_endian {
+            definition.start_bit = bits
+                .checked_sub(definition.start_bit + definition.bit_len)
+                .ok_or_else(|| {
+                    invalid_line(
+                        line,
+                        format!(
+                            "variable {} beyond the end of message {}",
+                            definition.name, first.name
+                        ),
+                    )
+                })?;
+        }
+        let signal = DbcSignal::new(
+            Some(definition),
+            variable.description,
+            HashMap::new(),
+            variable.values,
+        );
+        signal_map.insert(signal.get_definition().name.clone(), signal);
+    }
+
+    let mut attributes = HashMap::new();
+    if let Some(cycle_time) = group.iter().find_map(|message| message.cycle_time.as_ref()) {
+        attributes.insert(CYCLE_TIME.to_string(), cycle_time.clone());
+    }
+
+    Ok(DbcFrame::new(
+        first.name.clone(),
+        id.to_dbc(),
+        message_len,
+        NO_NODE.to_string(),
+        attributes,
+        None,
+        signal_map,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbc::Impact;
+    use crate::mapper::DecodeMessage;
+    use crate::tests::helpers::assert_like_dbc;
+
+    const SYM: &str = r#"FormatVersion=6.0 // Do not edit this line!
+Title="test"
+
+{ENUMS}
+enum Mode(0="Off", 1="On // not a comment",
+  2="Error")
+
+{SENDRECEIVE}
+
+[Status]
+ID=123h
+DLC=8
+CycleTime=50
+Var=Speed unsigned 0,16 -m /u:"km/h" /f:0.25 /max:16383.75 // Vehicle speed
+Var=Temp signed 16,8 /o:-40 /min:-168 /max:87
+Var=Mode unsigned 24,2 -h /e:Mode
+Var=Voltage float 32,32
+
+[Diagnostics]
+ID=18FEF100h
+Type=Extended
+Mux=Page 0,8 0
+Var=Counter unsigned 24,8
+Var=Level unsigned 8,4
+
+[Diagnostics]
+ID=18FEF100h
+Type=Extended
+Mux=Page 0,8 1
+Var=Counter unsigned 24,8
+Var=Code unsigned 12,12 -m
+"#;
+
+    #[test]
+    fn test_read() {
+        let lib = DbcLibrary::from_sym_str(SYM).unwrap();
+        assert_eq!(lib.len(), 2);
+
+        let status = lib.get_frame_by_id(CanId::Standard(0x123)).unwrap();
+        assert_eq!(status.get_message_len(), 8);
+        assert_eq!(status.get_sending_node(), NO_NODE);
+        assert_eq!(status.get_attributes()[CYCLE_TIME], "50");
+        assert!(status.get_signal("Voltage").is_none());
+
+        let payload = [0x12, 0x34, 0xFE, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let speed = status.get_signal("Speed").unwrap();
+        assert_eq!(speed.get_description(), Some("Vehicle speed"));
+        assert_eq!(speed.get_definition().units, "km/h");
+        assert_eq!(speed.decode_raw(&payload), Some(0x1234));
+        assert_eq!(speed.decode_message(&payload), Some(1165.0));
+        let temp = status.get_signal("Temp").unwrap();
+        assert_eq!(temp.get_description(), None);
+        assert_eq!(
+            temp.get_definition().descriptor().decode(&payload),
+            Some(-42.0)
+        );
+        let mode = status.get_signal("Mode").unwrap();
+        assert_eq!(mode.decode_label(&payload), Some("On // not a comment"));
+        assert_eq!(mode.get_value_definition().unwrap().get_values().len(), 3);
+
+        let diagnostics = lib.get_frame_by_id(CanId::Extended(0x18FEF100)).unwrap();
+        assert_eq!(diagnostics.get_message_len(), 4);
+        let multiplexing = |name: &str| {
+            diagnostics
+                .get_signal(name)
+                .unwrap()
+                .get_definition()
+                .multiplexing
+        };
+        assert_eq!(multiplexing("Page"), Multiplexing::Multiplexor);
+        assert_eq!(multiplexing("Level"), Multiplexing::Multiplexed(0));
+        assert_eq!(multiplexing("Code"), Multiplexing::Multiplexed(1));
+        assert_eq!(multiplexing("Counter"), Multiplexing::None);
+
+        // bits 12 to 23 in network order, the low nibble of byte 1 and byte 2
+        let code = diagnostics.get_signal("Code").unwrap();
+        assert_eq!(code.decode_raw(&[0x01, 0x0A, 0xBC, 0x00]), Some(0xABC));
+    }
+
+    #[test]
+    fn test_like_dbc() {
+        let dbc = DbcLibrary::from_dbc_file("./tests/data/codegen.dbc").unwrap();
+        let sym = DbcLibrary::from_sym_file("./tests/data/sample.sym").unwrap();
+
+        // only nodes, comments of frames and attributes differ
+        let changes = dbc.diff(&sym);
+        assert!(changes
+            .iter()
+            .all(|change| change.impact() == Impact::NonBreaking));
+        let changes: Vec<_> = changes.iter().map(ToString::to_string).collect();
+        let receivers = |signal: &str| {
+            format!(
+                "non-breaking: {}: receivers changed from Dashboard to Vector__XXX",
+                signal
+            )
+        };
+        assert_eq!(
+            changes,
+            vec![
+                "non-breaking: Transmission: sender changed from Transmission to Vector__XXX"
+                    .to_string(),
+                receivers("Transmission.Clutch_Engaged"),
+                receivers("Transmission.Gear"),
+                receivers("Transmission.Output_Torque"),
+                receivers("Transmission.ShiftCount"),
+                "non-breaking: Diagnostics: sender changed from Engine to Vector__XXX".to_string(),
+                receivers("Diagnostics.Battery_Voltage"),
+                receivers("Diagnostics.Error_Code"),
+                receivers("Diagnostics.Oil_Pressure"),
+                receivers("Diagnostics.Page"),
+                "non-breaking: EEC1: sender changed from Engine to Vector__XXX".to_string(),
+                "non-breaking: EEC1: comment changed from \"Electronic Engine Controller 1\" to none"
+                    .to_string(),
+                "non-breaking: EEC1: attribute changed from none to GenMsgCycleTime = 100"
+                    .to_string(),
+                receivers("EEC1.Engine_Speed"),
+                receivers("EEC1.Engine_Temp"),
+                receivers("EEC1.Engine_Torque_Mode"),
+            ]
+        );
+        assert_like_dbc(&dbc, &sym);
+
+        let dbc = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+        let sym = DbcLibrary::from_sym_file("./tests/data/layouts.sym").unwrap();
+        assert_like_dbc(&dbc, &sym);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error = |sym: &str| DbcLibrary::from_sym_str(sym).unwrap_err().to_string();
+        assert_eq!(error("{SEND}"), "not a PCAN symbol file");
+        assert_eq!(error(""), "not a PCAN symbol file");
+        assert_eq!(error("FormatVersion=5.0"), "unsupported FormatVersion 5.0");
+        assert_eq!(
+            error(&SYM.replace("ID=123h", "ID=800h")),
+            "line 10: invalid ID 800h of message Status"
+        );
+        assert_eq!(
+            error(&SYM.replace("ID=123h", "ID=xyz")),
+            "line 11: invalid ID \"xyz\" of message Status"
+        );
+        assert_eq!(
+            error(&SYM.replace("ID=123h\n", "")),
+            "line 10: message Status without ID"
+        );
+        assert_eq!(
+            error(&SYM.replace("16,8", "16")),
+            "line 15: invalid position of variable Temp"
+        );
+        assert_eq!(
+            error(&SYM.replace("/f:0.25", "/f:x")),
+            "line 14: invalid /f \"x\" of variable Speed"
+        );
+        assert_eq!(
+            error(&SYM.replace("/e:Mode", "/e:State")),
+            "line 16: unknown enum \"State\""
+        );
+        assert_eq!(
+            error(&SYM.replace("signed 16,8", "integer 16,8")),
+            "line 15: unknown type \"integer\" of variable Temp"
+        );
+        assert_eq!(
+            error(&SYM.replace("Var=Code unsigned 12,12", "DLC=8\nVar=Code unsigned 60,12")),
+            "line 32: variable Code beyond the end of message Diagnostics"
+        );
+        assert_eq!(
+            error(&SYM.replace("ID=18FEF100h\nType=Extended\nMux=Page 0,8 0", "ID=123h")),
+            "line 19: duplicate message ID 0x123"
+        );
+        assert_eq!(
+            error(&SYM.replace("  2=\"Error\")", "")),
+            "line 5: unterminated enum"
+        );
+    }
+}
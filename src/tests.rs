@@ -3,9 +3,10 @@ mod test_mapper {
     use std::collections::HashMap;
 
     use crate::{
-        dbc::DbcSignalDefinition,
         dbc::{DbcFrame, DbcLibrary, DbcSignal, DbcVersion, Entry},
-        mapper::{DecodeMessage, EncodeMessage},
+        dbc::{DbcSignalDefinition, Multiplexing},
+        id::CanId,
+        mapper::{DecodeFrame, DecodeMessage, EncodeMessage},
     };
     use approx::assert_relative_eq;
 
@@ -26,7 +27,8 @@ mod test_mapper {
             min_value: 0.0,
             max_value: 8031.88,
             units: "rpm".to_string(),
-            receiving_node: "Vector__XXX".to_string()
+            receiving_node: "Vector__XXX".to_string(),
+            multiplexing: Multiplexing::None,
         };
         static ref SIGNAL_DEF_BE: DbcSignalDefinition = {
             let mut _spndef = SIGNAL_DEF.clone();
@@ -85,6 +87,62 @@ mod test_mapper {
         );
     }
 
+    #[test]
+    fn get_frame_extended_id() {
+        let frame = DBC_ONE
+            .get_frame(2364539904)
+            .expect("failed to get frame with DBC encoded ID");
+
+        assert_eq!(frame.get_id(), 2364539904);
+        assert_eq!(frame.get_can_id(), CanId::Extended(0x0CF00400));
+
+        assert!(DBC_ONE.get_frame(0x0CF00400).is_some());
+        assert!(DBC_ONE.get_frame_raw(0x0CF00400, true).is_some());
+        assert!(DBC_ONE.get_frame_raw(0x0CF00400, false).is_none());
+        assert!(DBC_ONE
+            .get_frame_by_id(CanId::Extended(0x0CF00400))
+            .is_some());
+    }
+
+    #[test]
+    fn get_frame_standard_id() {
+        let frame = DBC_FF
+            .get_frame_raw(1297, false)
+            .expect("Did not find FSG_EXTRA_FRAME");
+
+        assert_eq!(frame.get_id(), 1297);
+        assert_eq!(frame.get_can_id(), CanId::Standard(1297));
+        assert!(DBC_FF.get_frame_raw(1297, true).is_none());
+    }
+
+    #[test]
+    fn new_dbclibrary_normalizes_ids() {
+        let mut frames = HashMap::new();
+        frames.insert(2364539904, FRAME_DEF.clone());
+
+        let lib = DbcLibrary::new(frames);
+
+        assert!(lib.get_frame_raw(0x0CF00400, true).is_some());
+    }
+
+    #[test]
+    fn can_id_conversion() {
+        assert_eq!(CanId::from_dbc(0x123), CanId::Standard(0x123));
+        assert_eq!(CanId::from_dbc(0x8000_0123), CanId::Extended(0x123));
+        assert_eq!(CanId::Extended(0x0CF00400).to_dbc(), 2364539904);
+        assert_eq!(CanId::Extended(0x0CF00400).raw(), 0x0CF00400);
+        assert_eq!(CanId::new(0x2000_0000, true), None);
+        assert_eq!(
+            CanId::try_from_dbc(0x8000_0123),
+            Some(CanId::Extended(0x123))
+        );
+        assert_eq!(CanId::try_from_dbc(0xC000_0000), None);
+        assert_eq!(CanId::try_from_dbc(0x4000_0000), None);
+        assert_eq!(CanId::try_from_dbc(0x2000_0000), None);
+        assert_eq!(CanId::Standard(0x12).to_string(), "012");
+        assert_eq!(CanId::Extended(0x0CF00400).to_string(), "0CF00400");
+    }
+
     #[test]
     fn unsupported_entry() {
         let mut pgnlib: DbcLibrary = DbcLibrary::default();
@@ -94,6 +152,110 @@ mod test_mapper {
         assert!(res.is_err(), "Unsupported entry: Version");
     }
 
+    #[test]
+    fn decode_multiplexed_frame() {
+        let frame = DBC_FF.get_frame(786).unwrap();
+
+        let balance = frame.decode_frame(&[100, 7][..]).unwrap();
+        assert_eq!(balance.len(), 2);
+        assert_eq!(balance["FF_BMS_Control_mux"], 100.0);
+        assert_eq!(balance["FF_BMS_Control_Balance"], 7.0);
+
+        let ts = DBC_FF
+            .decode(0.0, CanId::Standard(786), &[101, 9])
+            .unwrap()
+            .signals;
+        assert_eq!(ts.len(), 2);
+        assert_eq!(ts["FF_BMS_Control_TS"], 9.0);
+    }
+
+    #[test]
+    fn encode_multiplexed_frame() {
+        let frame = DBC_FF.get_frame(786).unwrap();
+        let mut signal_map = HashMap::new();
+        signal_map.insert("FF_BMS_Control_mux".to_string(), 101.0);
+        signal_map.insert("FF_BMS_Control_TS".to_string(), 9.0);
+
+        // the balance alternative is neither needed nor encoded
+        let encoded: Vec<u8> = frame.encode_message(&signal_map).unwrap();
+        assert_eq!(&encoded[..2], &[101, 9]);
+
+        signal_map.insert("FF_BMS_Control_Balance".to_string(), 7.0);
+        let encoded: Vec<u8> = frame.encode_message(&signal_map).unwrap();
+        assert_eq!(&encoded[..2], &[101, 9]);
+
+        signal_map.remove("FF_BMS_Control_TS");
+        let encoded: Result<Vec<u8>, String> = frame.encode_message(&signal_map);
+        assert_eq!(
+            encoded.unwrap_err(),
+            "Missing signal data: FF_BMS_Control_TS"
+        );
+    }
+
+    #[test]
+    fn signal_entries_of_undefined_frame() {
+        let lib = DbcLibrary::from_dbc_str(concat!(
+            "BO_ 100 X: 8 A\n",
+            " SG_ S : 0|8@1+ (1,0) [0|255] \"\" X\n",
+            "VAL_ 200 S 1 \"On\" ;\n",
+            "CM_ SG_ 200 S \"Missing frame\";\n",
+        ));
+        assert_eq!(lib.len(), 1);
+        assert!(lib.get_frame(200).is_none());
+
+        let mut lib = DbcLibrary::default();
+        let entry = Entry::ValueDescription(crate::dbc::DbcValueDescription {
+            id: 200,
+            signal_name: "S".to_string(),
+            values: vec![(1, "On".to_string())],
+        });
+        assert!(lib.add_entry(entry).is_err());
+        assert!(lib.is_empty());
+    }
+
+    #[test]
+    fn pseudo_frames() {
+        let lib = DbcLibrary::from_dbc_str(concat!(
+            "BO_ 2147483648 Zero: 8 Vector__XXX\n",
+            " SG_ Z : 0|8@1+ (1,0) [0|255] \"\" Vector__XXX\n",
+            "BO_ 3221225472 VECTOR__INDEPENDENT_SIG_MSG: 0 Vector__XXX\n",
+            " SG_ Unused : 0|8@1+ (1,0) [0|255] \"\" X\n",
+            "CM_ BO_ 3221225472 \"Signals not sent in any frame\";\n",
+            "CM_ SG_ 3221225472 Unused \"Not sent\";\n",
+            "VAL_ 3221225472 Unused 1 \"On\" ;\n",
+        ));
+
+        assert_eq!(lib.len(), 1);
+        assert!(lib.get_frame(3221225472).is_none());
+        let frame = lib.get_frame_by_id(CanId::Extended(0)).unwrap();
+        assert_eq!(frame.get_name(), "Zero");
+        assert_eq!(frame.get_signals().len(), 1);
+        assert!(frame.get_description().is_none());
+        assert!(lib.lint().is_empty());
+
+        let mut frames = HashMap::new();
+        frames.insert(3221225472, FRAME_DEF.clone());
+        assert!(DbcLibrary::new(frames).is_empty());
+    }
+
+    #[test]
+    fn signals_without_definition() {
+        let lib = DbcLibrary::from_dbc_str(concat!(
+            "BO_ 100 X: 8 A\n",
+            " SG_ S : 0|8@1+ (1,0) [0|255] \"\" X\n",
+            "VAL_ 100 T 1 \"On\" ;\n",
+        ));
+        let frame = lib.get_frame(100).unwrap();
+
+        assert_eq!(frame.get_signals().len(), 1);
+        assert!(frame.get_signal("T").is_none());
+        assert!(lib.get_signal("T").is_none());
+
+        let decoded = lib.decode(0.0, CanId::Standard(100), &[7]).unwrap();
+        assert_eq!(decoded.signals.len(), 1);
+        assert_eq!(decoded.signals["S"], 7.0);
+    }
+
     #[test]
     fn test_parse_array() {
         let dbc_signal = DbcSignal::new(Some(SIGNAL_DEF.clone()), None, HashMap::new(), None);
@@ -106,6 +268,31 @@ mod test_mapper {
         );
     }
 
+    #[test]
+    fn test_parse_long_message() {
+        let msg: Vec<u8> = (1..=12).collect();
+
+        let mut last_byte = SIGNAL_DEF_BE.clone();
+        last_byte.start_bit = 0;
+        last_byte.bit_len = 8;
+        last_byte.scale = 1.0;
+        let last_byte = DbcSignal::new(Some(last_byte), None, HashMap::new(), None);
+        assert_eq!(last_byte.decode_message(&msg[..]).unwrap(), 12.0);
+
+        // bytes 8 and 9 as big endian value, numbered from the end of the message
+        let mut be = SIGNAL_DEF_BE.clone();
+        be.start_bit = 16;
+        be.scale = 1.0;
+        let be = DbcSignal::new(Some(be), None, HashMap::new(), None);
+        assert_eq!(be.decode_message(&msg[..]).unwrap(), 0x090A as f32);
+
+        let mut le = SIGNAL_DEF.clone();
+        le.start_bit = 64;
+        le.scale = 1.0;
+        let le = DbcSignal::new(Some(le), None, HashMap::new(), None);
+        assert_eq!(le.decode_message(&msg[..]).unwrap(), 0x0A09 as f32);
+    }
+
     #[test]
     fn test_long_names() {
         let name = DBC_FF
@@ -174,4 +361,115 @@ mod test_mapper {
 
         assert_eq!(sig.unwrap(), 2728.5);
     }
+
+    #[test]
+    fn test_encode_message_errors() {
+        let mut signal_map: HashMap<String, f64> = HashMap::new();
+
+        let ret: Result<Vec<u8>, String> = FRAME_DEF.encode_message(&signal_map);
+        assert_eq!(ret.unwrap_err(), "Missing signal data: Engine_Speed");
+
+        signal_map.insert("Engine_Speed".to_string(), 9000.0);
+        let ret: Result<Vec<u8>, String> = FRAME_DEF.encode_message(&signal_map);
+        assert_eq!(
+            ret.unwrap_err(),
+            "Error encoding signal Engine_Speed: value does not fit into the signal (9000)"
+        );
+
+        signal_map.insert("Engine_Speed".to_string(), -1.0);
+        let ret: Result<Vec<u8>, String> = FRAME_DEF.encode_message(&signal_map);
+        assert!(ret.is_err());
+    }
+}
+
+/// Assertions shared by the tests of the readers of other definition formats
+#[cfg(test)]
+pub(crate) mod helpers {
+    use std::collections::BTreeMap;
+
+    use crate::dbc::{DbcFrame, DbcLibrary, DbcSignal, Multiplexing};
+    use crate::mapper::DecodeMessage;
+
+    /// Signals of a frame by name, the multiplexor by its role as formats name it differently
+    fn signals(frame: &DbcFrame) -> BTreeMap<String, &DbcSignal> {
+        frame
+            .get_signals()
+            .into_iter()
+            .map(|signal| {
+                let definition = signal.get_definition();
+                let key = match definition.multiplexing {
+                    Multiplexing::Multiplexor => "<multiplexor>".to_string(),
+                    _ => definition.name.clone(),
+                };
+                (key, signal)
+            })
+            .collect()
+    }
+
+    /// Value table of a signal in the order of the values
+    fn values(signal: &DbcSignal) -> Vec<(i64, String)> {
+        let mut values = signal
+            .get_value_definition()
+            .map(|values| values.get_values().to_vec())
+            .unwrap_or_default();
+        values.sort();
+        values
+    }
+
+    /// Asserts that `copy` has the frames of `dbc` with the same length and signals that occupy
+    /// the same bits, decode alike and have the same multiplexing and value tables.
+    /// `tests/data/layouts.dbc` has short, CAN FD and multiplexed frames with signals of both
+    /// byte orders.
+    pub(crate) fn assert_like_dbc(dbc: &DbcLibrary, copy: &DbcLibrary) {
+        for frame in dbc.get_frames() {
+            let name = frame.get_name();
+            let copied = copy
+                .get_frame_by_id(frame.get_can_id())
+                .unwrap_or_else(|| panic!("frame {} missing", name));
+            assert_eq!(
+                copied.get_message_len(),
+                frame.get_message_len(),
+                "{}",
+                name
+            );
+
+            let len = frame.get_message_len() as usize;
+            let payload: Vec<u8> = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+            let (signals, copied_signals) = (signals(frame), signals(copied));
+            assert_eq!(
+                signals.keys().collect::<Vec<_>>(),
+                copied_signals.keys().collect::<Vec<_>>(),
+                "{}",
+                name
+            );
+
+            for (key, signal) in &signals {
+                let copied = copied_signals[key];
+                let (definition, copied_definition) =
+                    (signal.get_definition(), copied.get_definition());
+                assert_eq!(
+                    definition.multiplexing, copied_definition.multiplexing,
+                    "{}.{}",
+                    name, key
+                );
+
+                let mut bits = vec![0u8; len];
+                definition.descriptor().insert(u64::MAX, &mut bits);
+                let mut copied_bits = vec![0u8; len];
+                copied_definition
+                    .descriptor()
+                    .insert(u64::MAX, &mut copied_bits);
+                assert_eq!(bits, copied_bits, "{}.{}", name, key);
+
+                assert_eq!(
+                    signal.decode_message(&payload[..]),
+                    copied.decode_message(&payload[..]),
+                    "{}.{}",
+                    name,
+                    key
+                );
+                assert_eq!(values(signal), values(copied), "{}.{}", name, key);
+            }
+        }
+    }
 }
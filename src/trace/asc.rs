@@ -0,0 +1,694 @@
+//! Vector ASC trace files, as written by CANoe and CANalyzer
+//!
+//! ```text
+//! date Wed Jun 12 10:21:41.123 am 2019
+//! base hex  timestamps absolute
+//! internal events logged
+//! // version 9.0.0
+//! Begin Triggerblock Wed Jun 12 10:21:41.123 am 2019
+//!    0.000000 Start of measurement
+//!    0.010000 1  123             Rx   d 4 DE AD BE EF
+//!    0.020000 1  CF00400x        Rx   d 8 11 22 33 44 55 66 77 88
+//!    0.030000 1  123             Rx   r 4
+//!    0.040000 1  ErrorFrame
+//!    0.050000 CANFD   1 Rx      123  1 0 9 12 00 01 02 03 04 05 06 07 08 09 0A 0B
+//! End TriggerBlock
+//! ```
+//!
+//! Timestamps are seconds since the start of the measurement, the start date is available from
+//! `AscReader::start_date`.  Relative timestamps (`timestamps relative`) are accumulated, so
+//! frames read from either variant hold the time since the start of the measurement.  Events
+//! other than frames, such as statistics and status messages, are skipped.
+
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{invalid_data, len_to_dlc, Channel, Direction, FrameKind, TraceFrame};
+use crate::id::{CanId, EXTENDED_ID_MAX, STANDARD_ID_MAX};
+
+/// Flag marking frames of `CANFD` lines sent in FD format (EDL bit)
+const FD_FLAG_EDL: u32 = 0x1000;
+const FD_FLAG_BRS: u32 = 0x2000;
+const FD_FLAG_ESI: u32 = 0x4000;
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parses a single event line with the given number base (16 for `base hex`, 10 for `base dec`).
+/// Returns `None` for events other than CAN frames.
+///
+/// # Example
+/// ```rust
+/// use fastcan::id::CanId;
+/// use fastcan::trace::{asc, Channel};
+///
+/// let frame = asc::parse_line("   0.010000 1  CF00400x  Rx   d 2 11 22", 16)
+///     .unwrap()
+///     .unwrap();
+///
+/// assert_eq!(frame.channel, Channel::Index(1));
+/// assert_eq!(frame.id, CanId::Extended(0x0CF00400));
+/// assert_eq!(frame.data, vec![0x11, 0x22]);
+/// ```
+pub fn parse_line(line: &str, base: u32) -> Result<Option<TraceFrame>, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+
+    let timestamp = match fields.first().map(|field| field.parse::<f64>()) {
+        Some(Ok(timestamp)) => timestamp,
+        _ => return Ok(None),
+    };
+
+    match fields.get(1) {
+        Some(&"CANFD") => parse_fd(timestamp, &fields[2..], base),
+        Some(channel) => match channel.parse::<u16>() {
+            Ok(channel) => parse_classic(timestamp, channel, &fields[2..], base),
+            Err(_) => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+fn parse_classic(
+    timestamp: f64,
+    channel: u16,
+    fields: &[&str],
+    base: u32,
+) -> Result<Option<TraceFrame>, String> {
+    if fields.first() == Some(&"ErrorFrame") {
+        return Ok(Some(error_frame(timestamp, channel)));
+    }
+
+    // anything without a direction, e.g. `1 Statistic: ...`, is no frame
+    let direction = match fields.get(1).map(|dir| parse_direction(dir)) {
+        Some(Some(direction)) => direction,
+        _ => return Ok(None),
+    };
+    let id = parse_id(fields[0], base)?;
+
+    let (kind, data) = match fields.get(2) {
+        Some(&"d") | Some(&"D") => {
+            let dlc = fields
+                .get(3)
+                .ok_or_else(|| "missing DLC".to_string())
+                .and_then(|dlc| parse_byte(dlc, base))?;
+            let len = usize::from(dlc.min(8));
+            (FrameKind::Data, parse_data(&fields[4..], len, base)?)
+        }
+        Some(&"r") | Some(&"R") => {
+            let len = fields
+                .get(3)
+                .and_then(|dlc| parse_byte(dlc, base).ok())
+                .unwrap_or(0);
+            (FrameKind::Remote(len), Vec::new())
+        }
+        _ => return Err(format!("unknown frame type: {}", fields.join(" "))),
+    };
+
+    Ok(Some(TraceFrame {
+        timestamp,
+        channel: Channel::Index(channel),
+        id,
+        kind,
+        direction,
+        data,
+    }))
+}
+
+/// Parses `<channel> <dir> <id> [<name>] <brs> <esi> <dlc> <length> <data> ... <flags> ...`
+fn parse_fd(timestamp: f64, fields: &[&str], base: u32) -> Result<Option<TraceFrame>, String> {
+    let channel = fields
+        .first()
+        .ok_or_else(|| "missing channel".to_string())?
+        .parse::<u16>()
+        .map_err(|e| format!("invalid channel: {}", e))?;
+    if fields.get(2) == Some(&"ErrorFrame") {
+        return Ok(Some(error_frame(timestamp, channel)));
+    }
+
+    let direction = fields
+        .get(1)
+        .and_then(|dir| parse_direction(dir))
+        .ok_or_else(|| format!("invalid direction: {}", fields.join(" ")))?;
+    let id = parse_id(fields.get(2).ok_or_else(|| "missing ID".to_string())?, base)?;
+
+    // the symbolic name is optional, the bit rate switch is always 0 or 1
+    let fields = match fields.get(3) {
+        Some(&"0") | Some(&"1") => &fields[3..],
+        Some(_) => &fields[4..],
+        None => return Err("missing BRS".to_string()),
+    };
+    if fields.len() < 4 {
+        return Err(format!("truncated CANFD frame: {}", fields.join(" ")));
+    }
+
+    let brs = fields[0] == "1";
+    let esi = fields[1] == "1";
+    let dlc = u8::from_str_radix(fields[2], 16).map_err(|e| format!("invalid DLC: {}", e))?;
+    let len = fields[3]
+        .parse::<usize>()
+        .map_err(|e| format!("invalid data length: {}", e))?;
+    let data = parse_data(&fields[4..], len, base)?;
+
+    // message duration and length precede the flags
+    let flags = fields
+        .get(4 + len + 2)
+        .and_then(|flags| u32::from_str_radix(flags, 16).ok());
+
+    let kind = match flags {
+        Some(flags) if flags & FD_FLAG_EDL == 0 && len == 0 && dlc > 0 => FrameKind::Remote(dlc),
+        Some(flags) if flags & FD_FLAG_EDL == 0 => FrameKind::Data,
+        _ => FrameKind::Fd { brs, esi },
+    };
+
+    Ok(Some(TraceFrame {
+        timestamp,
+        channel: Channel::Index(channel),
+        id,
+        kind,
+        direction,
+        data,
+    }))
+}
+
+fn error_frame(timestamp: f64, channel: u16) -> TraceFrame {
+    TraceFrame {
+        timestamp,
+        channel: Channel::Index(channel),
+        kind: FrameKind::Error,
+        ..Default::default()
+    }
+}
+
+fn parse_direction(direction: &str) -> Option<Direction> {
+    match direction {
+        "Rx" => Some(Direction::Rx),
+        "Tx" => Some(Direction::Tx),
+        _ => None,
+    }
+}
+
+/// Parses an ID, extended IDs carry an `x` suffix
+fn parse_id(id: &str, base: u32) -> Result<CanId, String> {
+    let (digits, extended) = match id.strip_suffix('x').or_else(|| id.strip_suffix('X')) {
+        Some(digits) => (digits, true),
+        None => (id, false),
+    };
+    let raw = u32::from_str_radix(digits, base).map_err(|e| format!("invalid ID {}: {}", id, e))?;
+
+    if extended {
+        Ok(CanId::Extended(raw & EXTENDED_ID_MAX))
+    } else if raw <= STANDARD_ID_MAX {
+        Ok(CanId::Standard(raw as u16))
+    } else {
+        Err(format!("standard ID out of range: {}", id))
+    }
+}
+
+fn parse_byte(byte: &str, base: u32) -> Result<u8, String> {
+    u8::from_str_radix(byte, base).map_err(|e| format!("invalid byte {}: {}", byte, e))
+}
+
+fn parse_data(fields: &[&str], len: usize, base: u32) -> Result<Vec<u8>, String> {
+    if fields.len() < len {
+        return Err(format!(
+            "expected {} data bytes, found {}",
+            len,
+            fields.len()
+        ));
+    }
+    fields[..len]
+        .iter()
+        .map(|byte| parse_byte(byte, base))
+        .collect()
+}
+
+/// Formats a frame as event line with hex numbers, without line break.  Named channels are
+/// written as channel 1 for `can0`, 2 for `can1` and so on.
+///
+/// # Example
+/// ```rust
+/// use fastcan::id::CanId;
+/// use fastcan::trace::{asc, Channel, TraceFrame};
+///
+/// let frame = TraceFrame::new(0.01, Channel::Index(1), CanId::Standard(0x123), vec![0xDE, 0xAD]);
+///
+/// assert_eq!(asc::format_line(&frame), "   0.010000 1  123             Rx   d 2 DE AD");
+/// ```
+pub fn format_line(frame: &TraceFrame) -> String {
+    let channel = match frame.channel {
+        Channel::Index(index) => index,
+        Channel::Name(ref name) => name
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .parse::<u16>()
+            .map(|index| index + 1)
+            .unwrap_or(1),
+    };
+    let id = match frame.id {
+        CanId::Standard(id) => format!("{:X}", id),
+        CanId::Extended(id) => format!("{:X}x", id),
+    };
+    let direction = match frame.direction {
+        Direction::Rx => "Rx",
+        Direction::Tx => "Tx",
+    };
+    let data = frame
+        .data
+        .iter()
+        .map(|byte| format!(" {:02X}", byte))
+        .collect::<String>();
+
+    let event = match frame.kind {
+        FrameKind::Data => format!(
+            "{:<2} {:<15} {:<4} d {:X}{}",
+            channel,
+            id,
+            direction,
+            frame.data.len(),
+            data
+        ),
+        FrameKind::Remote(len) => {
+            format!("{:<2} {:<15} {:<4} r {:X}", channel, id, direction, len)
+        }
+        FrameKind::Error => format!("{:<2} ErrorFrame", channel),
+        FrameKind::Fd { brs, esi } => {
+            let flags =
+                FD_FLAG_EDL | if brs { FD_FLAG_BRS } else { 0 } | if esi { FD_FLAG_ESI } else { 0 };
+            format!(
+                "CANFD {:>3} {:<4} {:>8}  {} {} {:X} {:>2}{} {:>8} {:>4} {:>8X} {:>8} {:>8} {:>8} {:>8} {:>8}",
+                channel,
+                direction,
+                id,
+                brs as u8,
+                esi as u8,
+                len_to_dlc(frame.data.len()),
+                frame.data.len(),
+                data,
+                0,
+                0,
+                flags,
+                0,
+                0,
+                0,
+                0,
+                0
+            )
+        }
+    };
+
+    format!("{:>11.6} {}", frame.timestamp, event)
+}
+
+/// Formats a UNIX timestamp the way ASC headers do, e.g. `Wed Jun 12 10:21:41.123 am 2019`
+fn format_date(timestamp: f64) -> String {
+    let millis = (timestamp.max(0.0) * 1000.0) as u64;
+    let days = millis / 86_400_000;
+    let millis_of_day = millis % 86_400_000;
+
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let hour = millis_of_day / 3_600_000;
+    let (hour12, meridiem) = match hour {
+        0 => (12, "am"),
+        1..=11 => (hour, "am"),
+        12 => (12, "pm"),
+        _ => (hour - 12, "pm"),
+    };
+
+    format!(
+        "{} {} {:02} {:02}:{:02}:{:02}.{:03} {} {}",
+        WEEKDAYS[(days % 7) as usize],
+        MONTHS[(month - 1) as usize],
+        day,
+        hour12,
+        millis_of_day / 60_000 % 60,
+        millis_of_day / 1000 % 60,
+        millis_of_day % 1000,
+        meridiem,
+        year
+    )
+}
+
+/// Reads frames from an ASC trace, one `io::Result<TraceFrame>` per frame
+pub struct AscReader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    base: u32,
+    relative: bool,
+    last_timestamp: f64,
+    start_date: Option<String>,
+}
+
+impl<R: BufRead> AscReader<R> {
+    /// Creates a reader from any buffered source
+    pub fn new(reader: R) -> Self {
+        AscReader {
+            lines: reader.lines(),
+            line: 0,
+            base: 16,
+            relative: false,
+            last_timestamp: 0.0,
+            start_date: None,
+        }
+    }
+
+    /// Returns the start date from the header as written, once it has been read
+    pub fn start_date(&self) -> Option<&str> {
+        self.start_date.as_deref()
+    }
+
+    /// Handles header lines, returns false for event lines
+    fn parse_header(&mut self, line: &str) -> Result<bool, String> {
+        let mut fields = line.split_whitespace();
+
+        match fields.next() {
+            Some("date") => {
+                self.start_date = Some(line.trim()["date".len()..].trim().to_string());
+            }
+            Some("base") => {
+                self.base = match fields.next() {
+                    Some("hex") => 16,
+                    Some("dec") => 10,
+                    base => return Err(format!("unknown base: {:?}", base)),
+                };
+                if fields.next() == Some("timestamps") {
+                    self.relative = fields.next() == Some("relative");
+                }
+            }
+            Some("Begin") | Some("End") | Some("internal") | Some("no") => {}
+            Some(comment) if comment.starts_with("//") => {}
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+impl AscReader<BufReader<File>> {
+    /// Opens an ASC trace file
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).map(|f| Self::new(BufReader::new(f)))
+    }
+}
+
+impl<R: BufRead> Iterator for AscReader<R> {
+    type Item = io::Result<TraceFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line += 1;
+
+            match self.parse_header(&line) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => return Some(Err(invalid_data(self.line, e))),
+            }
+
+            // relative timestamps count from the previous event, frame or not
+            if self.relative {
+                if let Some(Ok(delta)) = line.split_whitespace().next().map(str::parse::<f64>) {
+                    self.last_timestamp += delta;
+                }
+            }
+
+            match parse_line(&line, self.base) {
+                Ok(Some(mut frame)) => {
+                    if self.relative {
+                        frame.timestamp = self.last_timestamp;
+                    }
+                    return Some(Ok(frame));
+                }
+                Ok(None) => continue,
+                Err(e) => return Some(Err(invalid_data(self.line, e))),
+            }
+        }
+    }
+}
+
+/// Writes frames as ASC trace with hex numbers and absolute timestamps.  The header is written
+/// on creation, `finish` closes the trigger block.
+pub struct AscWriter<W: Write> {
+    writer: W,
+    start: f64,
+}
+
+impl<W: Write> AscWriter<W> {
+    /// Creates a writer for frames with timestamps relative to the start of the measurement,
+    /// dated with the current time
+    pub fn new(writer: W) -> io::Result<Self> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs_f64())
+            .unwrap_or_default();
+
+        Self::write_header(writer, &format_date(now), 0.0)
+    }
+
+    /// Creates a writer for frames with UNIX timestamps, such as those read from candump logs.
+    /// The measurement starts at `start`, which is subtracted from every frame.
+    pub fn with_start_time(writer: W, start: f64) -> io::Result<Self> {
+        Self::write_header(writer, &format_date(start), start)
+    }
+
+    fn write_header(mut writer: W, date: &str, start: f64) -> io::Result<Self> {
+        writeln!(writer, "date {}", date)?;
+        writeln!(writer, "base hex  timestamps absolute")?;
+        writeln!(writer, "internal events logged")?;
+        writeln!(writer, "// version 9.0.0")?;
+        writeln!(writer, "Begin Triggerblock {}", date)?;
+        writeln!(writer, "{:>11.6} Start of measurement", 0.0)?;
+
+        Ok(AscWriter { writer, start })
+    }
+
+    /// Writes a single frame
+    pub fn write_frame(&mut self, frame: &TraceFrame) -> io::Result<()> {
+        if self.start == 0.0 {
+            return writeln!(self.writer, "{}", format_line(frame));
+        }
+
+        let frame = TraceFrame {
+            timestamp: frame.timestamp - self.start,
+            ..frame.clone()
+        };
+        writeln!(self.writer, "{}", format_line(&frame))
+    }
+
+    /// Closes the trigger block and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        writeln!(self.writer, "End TriggerBlock")?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classic_frames() {
+        let frame = parse_line("   0.010000 1  123             Tx   d 4 DE AD BE EF", 16)
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.id, CanId::Standard(0x123));
+        assert_eq!(frame.direction, Direction::Tx);
+        assert_eq!(frame.data, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+
+        // trailing frame details written by CANoe are ignored
+        let frame = parse_line(
+            "   0.020000 2  CF00400x        Rx   d 2 11 22  Length = 0 BitCount = 0 ID = 217056256x",
+            16,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(frame.channel, Channel::Index(2));
+        assert_eq!(frame.id, CanId::Extended(0x0CF00400));
+        assert_eq!(frame.data, vec![0x11, 0x22]);
+    }
+
+    #[test]
+    fn test_remote_and_error_frames() {
+        let remote = parse_line("   0.030000 1  123             Rx   r 4", 16)
+            .unwrap()
+            .unwrap();
+        assert_eq!(remote.kind, FrameKind::Remote(4));
+
+        let error = parse_line("   0.040000 1  ErrorFrame", 16)
+            .unwrap()
+            .unwrap();
+        assert_eq!(error.kind, FrameKind::Error);
+
+        let error = parse_line("   0.040000 CANFD   1 Rx ErrorFrame", 16)
+            .unwrap()
+            .unwrap();
+        assert_eq!(error.kind, FrameKind::Error);
+    }
+
+    #[test]
+    fn test_fd_frames() {
+        let frame = parse_line(
+            "   0.050000 CANFD   1 Rx      123  EngineData  1 0 9 12 00 01 02 03 04 05 06 07 08 09 0A 0B   0    0     3000 0 0 0 0 0",
+            16,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            frame.kind,
+            FrameKind::Fd {
+                brs: true,
+                esi: false
+            }
+        );
+        assert_eq!(frame.data.len(), 12);
+        assert_eq!(frame.data[11], 0x0B);
+
+        // classic frame on an FD channel, without EDL flag
+        let frame = parse_line(
+            "   0.060000 CANFD   2 Tx      123  0 0 2  2 11 22   0    0        0 0 0 0 0 0",
+            16,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(frame.kind, FrameKind::Data);
+        assert_eq!(frame.direction, Direction::Tx);
+
+        assert!(parse_line("   0.070000 CANFD   1 Rx 123 1 0 9 12 00 01", 16).is_err());
+    }
+
+    #[test]
+    fn test_other_events() {
+        for line in [
+            "   0.000000 Start of measurement",
+            "   1.000000 1  Statistic: D 0 R 0 XD 0 XR 0 E 0 O 0 B 0.00%",
+            "   1.000000 CAN 1 Status:chip status error active",
+        ] {
+            assert_eq!(parse_line(line, 16), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_decimal_relative_trace() {
+        let trace = "date Wed Jun 12 10:21:41.123 am 2019\n\
+                     base dec  timestamps relative\n\
+                     // comment\n\
+                     Begin Triggerblock Wed Jun 12 10:21:41.123 am 2019\n\
+                     0.500000 Start of measurement\n\
+                     0.250000 1  291             Rx   d 2 17 255\n\
+                     0.250000 1  217056256x      Rx   d 0\n\
+                     End TriggerBlock\n";
+        let mut reader = AscReader::new(trace.as_bytes());
+        let frames: Vec<_> = reader.by_ref().collect::<io::Result<_>>().unwrap();
+
+        assert_eq!(reader.start_date(), Some("Wed Jun 12 10:21:41.123 am 2019"));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].id, CanId::Standard(0x123));
+        assert_eq!(frames[0].data, vec![0x11, 0xFF]);
+        assert!((frames[0].timestamp - 0.75).abs() < 1e-9);
+        assert_eq!(frames[1].id, CanId::Extended(0x0CF00400));
+        assert!((frames[1].timestamp - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reader_reports_line() {
+        let trace = "base hex  timestamps absolute\n   0.01 1 123 Rx d 4 11 22\n";
+        let frames: Vec<_> = AscReader::new(trace.as_bytes()).collect();
+
+        assert!(frames[0]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .starts_with("line 2"));
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0.0), "Thu Jan 01 12:00:00.000 am 1970");
+        assert_eq!(
+            format_date(1560334901.123),
+            "Wed Jun 12 10:21:41.123 am 2019"
+        );
+        assert_eq!(
+            format_date(1436509052.249),
+            "Fri Jul 10 06:17:32.249 am 2015"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let frames = vec![
+            TraceFrame::new(
+                1436509052.25,
+                Channel::Name("can0".to_string()),
+                CanId::Extended(0x0CF00400),
+                vec![0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88],
+            ),
+            TraceFrame {
+                kind: FrameKind::Remote(4),
+                direction: Direction::Tx,
+                ..TraceFrame::new(
+                    1436509052.5,
+                    Channel::Index(2),
+                    CanId::Standard(0x7FF),
+                    vec![],
+                )
+            },
+            TraceFrame {
+                kind: FrameKind::Fd {
+                    brs: true,
+                    esi: true,
+                },
+                ..TraceFrame::new(
+                    1436509052.75,
+                    Channel::Index(1),
+                    CanId::Standard(0x123),
+                    (0..16).collect(),
+                )
+            },
+            TraceFrame {
+                kind: FrameKind::Error,
+                ..TraceFrame::new(1436509053.0, Channel::Index(1), CanId::default(), vec![])
+            },
+        ];
+
+        let mut writer = AscWriter::with_start_time(Vec::new(), 1436509052.0).unwrap();
+        for frame in &frames {
+            writer.write_frame(frame).unwrap();
+        }
+        let written = writer.finish().unwrap();
+
+        let read: Vec<_> = AscReader::new(&written[..])
+            .collect::<io::Result<_>>()
+            .unwrap();
+
+        assert_eq!(read.len(), frames.len());
+        for (read, frame) in read.iter().zip(&frames) {
+            assert!((read.timestamp + 1436509052.0 - frame.timestamp).abs() < 1e-6);
+            assert_eq!(read.id, frame.id);
+            assert_eq!(read.kind, frame.kind);
+            assert_eq!(read.direction, frame.direction);
+            assert_eq!(read.data, frame.data);
+        }
+        assert_eq!(read[0].channel, Channel::Index(1));
+    }
+}
@@ -0,0 +1,720 @@
+//! Vector BLF binary logging files
+//!
+//! BLF files start with a `LOGG` file header followed by `LOBJ` objects, usually wrapped in
+//! zlib compressed `LOG_CONTAINER` objects.  `BlfReader` streams CAN and CAN FD messages as well
+//! as error frames, other objects are skipped.
+//!
+//! Timestamps are seconds since the UNIX epoch, computed from the start time in the file header,
+//! which is taken as UTC.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::trace::blf::BlfReader;
+//!
+//! let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+//! let reader = BlfReader::open("./tests/data/sample.blf").unwrap();
+//!
+//! for decoded in lib.decode_trace(reader) {
+//!     let decoded = decoded.unwrap();
+//!     println!("{} {:?}", decoded.timestamp, decoded.signals);
+//! }
+//! ```
+
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+use flate2::read::ZlibDecoder;
+
+use super::{dlc_to_len, Channel, Direction, FrameKind, TraceFrame};
+use crate::id::{CanId, EXTENDED_ID_MAX};
+
+const FILE_SIGNATURE: &[u8] = b"LOGG";
+const OBJECT_SIGNATURE: &[u8] = b"LOBJ";
+
+/// Size of the object header fields common to all versions
+const OBJECT_HEADER_BASE_SIZE: usize = 16;
+/// Size of the file header up to and including the start time
+const FILE_HEADER_MIN_SIZE: usize = 56;
+/// Upper limit of the uncompressed container data held at once, which also limits the size of
+/// objects in containers
+const MAX_BUFFER_SIZE: usize = 64 << 20;
+
+const CAN_MESSAGE: u32 = 1;
+const CAN_ERROR: u32 = 2;
+const LOG_CONTAINER: u32 = 10;
+const CAN_ERROR_EXT: u32 = 73;
+const CAN_MESSAGE2: u32 = 86;
+const CAN_FD_MESSAGE: u32 = 100;
+const CAN_FD_MESSAGE_64: u32 = 101;
+
+const NO_COMPRESSION: u16 = 0;
+const ZLIB_DEFLATE: u16 = 2;
+
+/// Object timestamps in units of 10 µs, nanoseconds otherwise
+const TIME_TEN_MICS: u32 = 1;
+
+const CAN_MSG_EXT: u32 = 0x8000_0000;
+const CAN_MSG_TX: u8 = 0x01;
+const CAN_MSG_RTR: u8 = 0x80;
+
+const FD_MSG_EDL: u8 = 0x01;
+const FD_MSG_BRS: u8 = 0x02;
+const FD_MSG_ESI: u8 = 0x04;
+
+const FD_64_RTR: u32 = 0x0010;
+const FD_64_EDL: u32 = 0x1000;
+const FD_64_BRS: u32 = 0x2000;
+const FD_64_ESI: u32 = 0x4000;
+
+/// Reads frames from a BLF file, one `io::Result<TraceFrame>` per CAN object
+pub struct BlfReader<R> {
+    reader: R,
+    start: f64,
+    /// Uncompressed container data, objects may span several containers
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> BlfReader<R> {
+    /// Creates a reader from any source, reading the file header
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != FILE_SIGNATURE {
+            return Err(malformed("missing LOGG signature"));
+        }
+
+        let header_size = LittleEndian::read_u32(&header[4..]) as usize;
+        if header_size < FILE_HEADER_MIN_SIZE {
+            return Err(malformed(format!("file header too short: {}", header_size)));
+        }
+        let mut rest = vec![0u8; header_size - header.len()];
+        reader.read_exact(&mut rest)?;
+
+        // SYSTEMTIME of the measurement start, after the version, size and count fields
+        let mut time = [0u16; 8];
+        LittleEndian::read_u16_into(&rest[32..48], &mut time);
+        let start = system_time_to_unix(&time);
+
+        Ok(BlfReader {
+            reader,
+            start,
+            buffer: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Returns the start of the measurement in seconds since the UNIX epoch
+    pub fn start_time(&self) -> f64 {
+        self.start
+    }
+
+    /// Reads the next top level object, `None` at the end of the file
+    fn read_object(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut object = vec![0u8; OBJECT_HEADER_BASE_SIZE];
+        match self.reader.read_exact(&mut object) {
+            Ok(()) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        if &object[..4] != OBJECT_SIGNATURE {
+            return Err(malformed("missing LOBJ signature"));
+        }
+
+        let size = LittleEndian::read_u32(&object[8..]) as usize;
+        if size < OBJECT_HEADER_BASE_SIZE {
+            return Err(malformed(format!("object too short: {}", size)));
+        }
+        // read the body incrementally instead of allocating the claimed size up front, which
+        // may be up to 4 GiB in a corrupt file
+        let body = (size - OBJECT_HEADER_BASE_SIZE) as u64;
+        let read = self.reader.by_ref().take(body).read_to_end(&mut object)?;
+        if (read as u64) < body {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("object truncated: {} of {} bytes", object.len(), size),
+            ));
+        }
+
+        // top level objects are padded to 4 bytes, the last one may be unpadded
+        let mut padding = [0u8; 3];
+        match self.reader.read_exact(&mut padding[..size % 4]) {
+            Ok(()) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(Some(object))
+    }
+
+    /// Takes the next complete object from the container buffer, `None` if the object continues
+    /// in the next container
+    fn buffered_object(&mut self) -> io::Result<Option<(usize, usize)>> {
+        // objects in containers are padded to 4 bytes, the padding is skipped by searching the
+        // signature
+        let search = &self.buffer[self.pos..];
+        let offset = match search
+            .windows(OBJECT_SIGNATURE.len())
+            .position(|window| window == OBJECT_SIGNATURE)
+        {
+            Some(offset) => offset,
+            // padding followed by the start of a signature continued in the next container
+            None if search.len() < 2 * OBJECT_SIGNATURE.len() - 1 => return Ok(None),
+            None => {
+                self.pos = self.buffer.len();
+                return Err(malformed("missing LOBJ signature in log container"));
+            }
+        };
+        let start = self.pos + offset;
+        if self.buffer.len() < start + OBJECT_HEADER_BASE_SIZE {
+            return Ok(None);
+        }
+
+        let size = LittleEndian::read_u32(&self.buffer[start + 8..]) as usize;
+        if !(OBJECT_HEADER_BASE_SIZE..=MAX_BUFFER_SIZE).contains(&size) {
+            self.pos = self.buffer.len();
+            return Err(malformed(format!("invalid object size: {}", size)));
+        }
+        let end = start + size;
+        if end > self.buffer.len() {
+            return Ok(None);
+        }
+
+        self.pos = end;
+        Ok(Some((start, end)))
+    }
+
+    /// Reports the rest of the container buffer at the end of the file, which may only be padding
+    fn truncated_object(&mut self) -> Option<io::Error> {
+        let rest = self.buffer.len() - self.pos;
+        self.pos = self.buffer.len();
+        if rest < OBJECT_SIGNATURE.len() {
+            return None;
+        }
+        Some(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("object truncated: {} bytes left in log container", rest),
+        ))
+    }
+
+    /// Appends the uncompressed content of a log container to the buffer
+    fn unpack_container(&mut self, object: &[u8]) -> io::Result<()> {
+        let header_size = LittleEndian::read_u16(&object[4..]) as usize;
+        let data = object
+            .get(header_size + 16..)
+            .ok_or_else(|| malformed("log container too short"))?;
+        let method = LittleEndian::read_u16(&object[header_size..]);
+
+        self.buffer.drain(..self.pos);
+        self.pos = 0;
+
+        // the rest of the buffer is part of an object, which is at most `MAX_BUFFER_SIZE` bytes
+        let limit = MAX_BUFFER_SIZE.saturating_sub(self.buffer.len()) as u64;
+        let read = match method {
+            NO_COMPRESSION => data.take(limit + 1).read_to_end(&mut self.buffer)?,
+            ZLIB_DEFLATE => ZlibDecoder::new(data)
+                .take(limit + 1)
+                .read_to_end(&mut self.buffer)?,
+            method => {
+                return Err(malformed(format!("unknown compression method: {}", method)));
+            }
+        };
+        if read as u64 > limit {
+            self.buffer.clear();
+            return Err(malformed(format!(
+                "log container data exceeds {} bytes",
+                MAX_BUFFER_SIZE
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Converts a CAN object into a frame, `None` for other objects
+    fn parse_object(&self, object: &[u8]) -> io::Result<Option<TraceFrame>> {
+        let header_size = LittleEndian::read_u16(&object[4..]) as usize;
+        let object_type = LittleEndian::read_u32(&object[12..]);
+        if !matches!(
+            object_type,
+            CAN_MESSAGE
+                | CAN_MESSAGE2
+                | CAN_ERROR
+                | CAN_ERROR_EXT
+                | CAN_FD_MESSAGE
+                | CAN_FD_MESSAGE_64
+        ) {
+            return Ok(None);
+        }
+
+        // header version 1 and 2 both start with flags, two 16 bit fields and the timestamp
+        if header_size < OBJECT_HEADER_BASE_SIZE + 16 || object.len() < header_size {
+            return Err(malformed(format!(
+                "object header too short: {}",
+                header_size
+            )));
+        }
+        let flags = LittleEndian::read_u32(&object[16..]);
+        let ticks = LittleEndian::read_u64(&object[24..]);
+        let factor = if flags == TIME_TEN_MICS { 1e-5 } else { 1e-9 };
+        let timestamp = self.start + ticks as f64 * factor;
+
+        let body = &object[header_size..];
+        let frame = match object_type {
+            CAN_MESSAGE | CAN_MESSAGE2 => parse_can_message(body)?,
+            CAN_ERROR | CAN_ERROR_EXT => parse_can_error(body)?,
+            CAN_FD_MESSAGE => parse_can_fd_message(body)?,
+            _ => parse_can_fd_message_64(body)?,
+        };
+
+        Ok(Some(TraceFrame { timestamp, ..frame }))
+    }
+}
+
+impl BlfReader<BufReader<File>> {
+    /// Opens a BLF file
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).and_then(|f| Self::new(BufReader::new(f)))
+    }
+}
+
+impl<R: Read> Iterator for BlfReader<R> {
+    type Item = io::Result<TraceFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = match self.buffered_object() {
+                Ok(Some((start, end))) => self.parse_object(&self.buffer[start..end]),
+                Ok(None) => {
+                    let object = match self.read_object() {
+                        Ok(Some(object)) => object,
+                        Ok(None) => return self.truncated_object().map(Err),
+                        Err(e) => return Some(Err(e)),
+                    };
+                    if LittleEndian::read_u32(&object[12..]) == LOG_CONTAINER {
+                        match self.unpack_container(&object) {
+                            Ok(()) => continue,
+                            Err(e) => return Some(Err(e)),
+                        }
+                    }
+                    self.parse_object(&object)
+                }
+                Err(e) => return Some(Err(e)),
+            };
+
+            match frame {
+                Ok(Some(frame)) => return Some(Ok(frame)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// `CAN_MESSAGE` and `CAN_MESSAGE2`: channel, flags, DLC, ID and 8 data bytes
+fn parse_can_message(body: &[u8]) -> io::Result<TraceFrame> {
+    check_len(body, 16, "CAN message")?;
+
+    let flags = body[2];
+    let dlc = body[3];
+    let kind = if flags & CAN_MSG_RTR != 0 {
+        FrameKind::Remote(dlc)
+    } else {
+        FrameKind::Data
+    };
+    let data = match kind {
+        FrameKind::Remote(_) => Vec::new(),
+        _ => body[8..8 + usize::from(dlc.min(8))].to_vec(),
+    };
+
+    Ok(TraceFrame {
+        channel: Channel::Index(LittleEndian::read_u16(body)),
+        id: parse_id(LittleEndian::read_u32(&body[4..])),
+        kind,
+        direction: parse_direction(flags & CAN_MSG_TX != 0),
+        data,
+        ..Default::default()
+    })
+}
+
+/// `CAN_ERROR` and `CAN_ERROR_EXT`, only the channel is used
+fn parse_can_error(body: &[u8]) -> io::Result<TraceFrame> {
+    check_len(body, 2, "CAN error")?;
+
+    Ok(TraceFrame {
+        channel: Channel::Index(LittleEndian::read_u16(body)),
+        kind: FrameKind::Error,
+        ..Default::default()
+    })
+}
+
+/// `CAN_FD_MESSAGE`: channel, flags, DLC, ID, frame length, bit count, FD flags, valid bytes and
+/// 64 data bytes
+fn parse_can_fd_message(body: &[u8]) -> io::Result<TraceFrame> {
+    check_len(body, 84, "CAN FD message")?;
+
+    let flags = body[2];
+    let dlc = body[3];
+    let fd_flags = body[13];
+    let valid_bytes = usize::from(body[14]).min(64);
+
+    let kind = if flags & CAN_MSG_RTR != 0 {
+        FrameKind::Remote(dlc)
+    } else if fd_flags & FD_MSG_EDL != 0 {
+        FrameKind::Fd {
+            brs: fd_flags & FD_MSG_BRS != 0,
+            esi: fd_flags & FD_MSG_ESI != 0,
+        }
+    } else {
+        FrameKind::Data
+    };
+    let data = match kind {
+        FrameKind::Remote(_) => Vec::new(),
+        _ => body[20..20 + valid_bytes.min(dlc_to_len(dlc))].to_vec(),
+    };
+
+    Ok(TraceFrame {
+        channel: Channel::Index(LittleEndian::read_u16(body)),
+        id: parse_id(LittleEndian::read_u32(&body[4..])),
+        kind,
+        direction: parse_direction(flags & CAN_MSG_TX != 0),
+        data,
+        ..Default::default()
+    })
+}
+
+/// `CAN_FD_MESSAGE_64`: 40 byte header with channel, DLC, valid bytes, ID, flags and direction,
+/// followed by the data
+fn parse_can_fd_message_64(body: &[u8]) -> io::Result<TraceFrame> {
+    check_len(body, 40, "CAN FD message")?;
+
+    let dlc = body[1];
+    let flags = LittleEndian::read_u32(&body[12..]);
+    // the valid byte count may exceed the data actually stored
+    let valid_bytes = usize::from(body[2]).min(body.len() - 40);
+
+    let kind = if flags & FD_64_RTR != 0 {
+        FrameKind::Remote(dlc)
+    } else if flags & FD_64_EDL != 0 {
+        FrameKind::Fd {
+            brs: flags & FD_64_BRS != 0,
+            esi: flags & FD_64_ESI != 0,
+        }
+    } else {
+        FrameKind::Data
+    };
+    let data = match kind {
+        FrameKind::Remote(_) => Vec::new(),
+        _ => body[40..40 + valid_bytes].to_vec(),
+    };
+
+    Ok(TraceFrame {
+        channel: Channel::Index(u16::from(body[0])),
+        id: parse_id(LittleEndian::read_u32(&body[4..])),
+        kind,
+        direction: parse_direction(body[34] != 0),
+        data,
+        ..Default::default()
+    })
+}
+
+fn parse_id(raw: u32) -> CanId {
+    if raw & CAN_MSG_EXT != 0 {
+        CanId::Extended(raw & EXTENDED_ID_MAX)
+    } else {
+        CanId::Standard((raw & 0x7FF) as u16)
+    }
+}
+
+fn parse_direction(tx: bool) -> Direction {
+    if tx {
+        Direction::Tx
+    } else {
+        Direction::Rx
+    }
+}
+
+fn check_len(body: &[u8], len: usize, object: &str) -> io::Result<()> {
+    if body.len() < len {
+        return Err(malformed(format!(
+            "{} too short: {} bytes",
+            object,
+            body.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Converts a Windows `SYSTEMTIME` (year, month, weekday, day, hour, minute, second,
+/// milliseconds) into seconds since the UNIX epoch
+fn system_time_to_unix(time: &[u16; 8]) -> f64 {
+    if time[0] == 0 {
+        return 0.0;
+    }
+
+    // days from civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let (year, month, day) = (i64::from(time[0]), i64::from(time[1]), i64::from(time[3]));
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let seconds =
+        days * 86_400 + i64::from(time[4]) * 3600 + i64::from(time[5]) * 60 + i64::from(time[6]);
+    seconds as f64 + f64::from(time[7]) / 1000.0
+}
+
+fn malformed<E>(error: E) -> io::Error
+where
+    E: Into<String>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_header(time: [u16; 8]) -> Vec<u8> {
+        let mut header = vec![0u8; 144];
+        header[..4].copy_from_slice(FILE_SIGNATURE);
+        LittleEndian::write_u32(&mut header[4..], 144);
+        LittleEndian::write_u16_into(&time, &mut header[40..56]);
+        header
+    }
+
+    fn object(object_type: u32, ticks: u64, body: &[u8]) -> Vec<u8> {
+        let mut object = vec![0u8; 32];
+        object[..4].copy_from_slice(OBJECT_SIGNATURE);
+        LittleEndian::write_u16(&mut object[4..], 32);
+        LittleEndian::write_u16(&mut object[6..], 1);
+        LittleEndian::write_u32(&mut object[8..], (32 + body.len()) as u32);
+        LittleEndian::write_u32(&mut object[12..], object_type);
+        LittleEndian::write_u32(&mut object[16..], 2);
+        LittleEndian::write_u64(&mut object[24..], ticks);
+        object.extend_from_slice(body);
+        object
+    }
+
+    fn can_message(channel: u16, flags: u8, id: u32, data: &[u8]) -> Vec<u8> {
+        let mut body = vec![0u8; 16];
+        LittleEndian::write_u16(&mut body, channel);
+        body[2] = flags;
+        body[3] = data.len() as u8;
+        LittleEndian::write_u32(&mut body[4..], id);
+        body[8..8 + data.len()].copy_from_slice(data);
+        body
+    }
+
+    fn container(data: &[u8]) -> Vec<u8> {
+        let mut object = vec![0u8; 32];
+        object[..4].copy_from_slice(OBJECT_SIGNATURE);
+        LittleEndian::write_u16(&mut object[4..], 16);
+        LittleEndian::write_u16(&mut object[6..], 1);
+        LittleEndian::write_u32(&mut object[8..], (32 + data.len()) as u32);
+        LittleEndian::write_u32(&mut object[12..], LOG_CONTAINER);
+        LittleEndian::write_u16(&mut object[16..], NO_COMPRESSION);
+        LittleEndian::write_u32(&mut object[24..], data.len() as u32);
+        object.extend_from_slice(data);
+        object
+    }
+
+    #[test]
+    fn test_system_time() {
+        assert_eq!(system_time_to_unix(&[1970, 1, 4, 1, 0, 0, 0, 0]), 0.0);
+        assert_eq!(
+            system_time_to_unix(&[2015, 7, 5, 10, 6, 17, 32, 250]),
+            1436509052.25
+        );
+        assert_eq!(
+            system_time_to_unix(&[2000, 2, 2, 29, 0, 0, 0, 0]),
+            951782400.0
+        );
+    }
+
+    #[test]
+    fn test_top_level_objects() {
+        let mut file = file_header([2015, 7, 5, 10, 6, 17, 32, 250]);
+        file.extend(object(
+            CAN_MESSAGE,
+            1_000_000,
+            &can_message(2, CAN_MSG_TX, 0x123, &[0xDE, 0xAD, 0xBE]),
+        ));
+        // odd sized unknown object with padding
+        file.extend(object(65, 0, &[0x41]));
+        file.extend([0u8; 1]);
+        file.extend(object(CAN_ERROR, 2_000_000, &[1, 0, 0, 0]));
+
+        let frames: Vec<_> = BlfReader::new(&file[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert!((frames[0].timestamp - 1436509052.251).abs() < 1e-6);
+        assert_eq!(frames[0].channel, Channel::Index(2));
+        assert_eq!(frames[0].id, CanId::Standard(0x123));
+        assert_eq!(frames[0].direction, Direction::Tx);
+        assert_eq!(frames[0].data, vec![0xDE, 0xAD, 0xBE]);
+        assert_eq!(frames[1].kind, FrameKind::Error);
+    }
+
+    #[test]
+    fn test_object_split_across_containers() {
+        let mut objects = object(
+            CAN_MESSAGE2,
+            0,
+            &can_message(1, CAN_MSG_RTR, 0x8CF0_0400, &[]),
+        );
+        objects.extend(object(
+            CAN_MESSAGE,
+            10,
+            &can_message(1, 0, 0x8CF0_0400, &[1, 2, 3, 4, 5, 6, 7, 8]),
+        ));
+
+        let mut file = file_header([0; 8]);
+        file.extend(container(&objects[..40]));
+        file.extend(container(&objects[40..]));
+
+        let frames: Vec<_> = BlfReader::new(&file[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].kind, FrameKind::Remote(0));
+        assert_eq!(frames[0].id, CanId::Extended(0x0CF00400));
+        assert_eq!(frames[1].data, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!((frames[1].timestamp - 1e-8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(BlfReader::new(&b"LOGX"[..]).is_err());
+
+        let mut file = file_header([0; 8]);
+        file.extend(b"LOBX");
+        file.extend([0u8; 12]);
+        let mut reader = BlfReader::new(&file[..]).unwrap();
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_truncated_object() {
+        // an object claiming almost 4 GiB is reported as truncated without allocating its size
+        let mut file = file_header([0; 8]);
+        let mut object = object(CAN_MESSAGE, 0, &can_message(1, 0, 0x123, &[1]));
+        LittleEndian::write_u32(&mut object[8..], 0xFFFF_FFF0);
+        file.extend(object);
+
+        let mut reader = BlfReader::new(&file[..]).unwrap();
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            error.to_string(),
+            "object truncated: 48 of 4294967280 bytes"
+        );
+    }
+
+    #[test]
+    fn test_container_resync() {
+        let message = object(CAN_MESSAGE, 0, &can_message(1, 0, 0x123, &[1]));
+
+        // garbage between objects is skipped up to the next signature
+        let mut data = message.clone();
+        data.extend([0xAAu8; 20]);
+        data.extend(&message);
+        let mut file = file_header([0; 8]);
+        file.extend(container(&data));
+        let frames: Vec<_> = BlfReader::new(&file[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+
+        // container data without any signature is rejected
+        let mut file = file_header([0; 8]);
+        file.extend(container(&[0xAA; 20]));
+        file.extend(container(&message));
+        let mut reader = BlfReader::new(&file[..]).unwrap();
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "missing LOBJ signature in log container");
+        assert_eq!(reader.next().unwrap().unwrap().id, CanId::Standard(0x123));
+        assert!(reader.next().is_none());
+
+        // objects larger than the buffer are rejected instead of buffering the rest of the file
+        let mut huge = message.clone();
+        LittleEndian::write_u32(&mut huge[8..], 0xFFFF_FFF0);
+        let mut file = file_header([0; 8]);
+        file.extend(container(&huge));
+        file.extend(container(&message));
+        let mut reader = BlfReader::new(&file[..]).unwrap();
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "invalid object size: 4294967280");
+        assert_eq!(reader.next().unwrap().unwrap().id, CanId::Standard(0x123));
+    }
+
+    #[test]
+    fn test_truncated_container_object() {
+        let message = object(CAN_MESSAGE, 0, &can_message(1, 0, 0x123, &[1]));
+        let mut data = message.clone();
+        data.extend([0u8; 3]);
+        data.extend(&message[..30]);
+        let mut file = file_header([0; 8]);
+        file.extend(container(&data));
+
+        let mut reader = BlfReader::new(&file[..]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            error.to_string(),
+            "object truncated: 33 bytes left in log container"
+        );
+        assert!(reader.next().is_none());
+
+        // padding after the last object is not an object
+        let mut data = message.clone();
+        data.extend([0u8; 3]);
+        let mut file = file_header([0; 8]);
+        file.extend(container(&data));
+        let frames: Vec<_> = BlfReader::new(&file[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(frames.len(), 1);
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read failed"))
+        }
+    }
+
+    #[test]
+    fn test_padding_errors() {
+        let mut file = file_header([0; 8]);
+        file.extend(object(65, 0, &[0x41]));
+
+        // a missing padding at the end of the file is accepted, other errors are returned
+        let mut reader = BlfReader::new(&file[..]).unwrap();
+        assert!(reader.next().is_none());
+
+        let mut reader = BlfReader::new((&file[..]).chain(FailingReader)).unwrap();
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+    }
+}
@@ -0,0 +1,318 @@
+//! candump log files, as written by `candump -l`
+//!
+//! Each line holds the timestamp in seconds since the UNIX epoch, the interface and the frame:
+//!
+//! ```text
+//! (1436509052.249713) can0 123#DEADBEEF
+//! (1436509052.250021) can0 0CF00400#1122334455667788
+//! (1436509052.251000) can1 123##1DEADBEEF
+//! (1436509052.252000) can0 123#R4
+//! ```
+//!
+//! FD frames use `##` followed by a hex digit holding the flags (1: bit rate switch, 2: error
+//! state indicator), remote frames `#R` with an optional length.
+
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::{invalid_data, Channel, FrameKind, TraceFrame};
+use crate::id::{CanId, EXTENDED_ID_MAX, STANDARD_ID_MAX};
+
+/// Flag marking error frames in the ID
+const CAN_ERR_FLAG: u32 = 0x2000_0000;
+
+const FD_FLAG_BRS: u8 = 0x01;
+const FD_FLAG_ESI: u8 = 0x02;
+
+/// Parses a single candump log line
+///
+/// # Example
+/// ```rust
+/// use fastcan::id::CanId;
+/// use fastcan::trace::{candump, Channel};
+///
+/// let frame = candump::parse_line("(1436509052.249713) can0 123#DEADBEEF").unwrap();
+///
+/// assert_eq!(frame.channel, Channel::Name("can0".to_string()));
+/// assert_eq!(frame.id, CanId::Standard(0x123));
+/// assert_eq!(frame.data, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+/// ```
+pub fn parse_line(line: &str) -> Result<TraceFrame, String> {
+    let mut fields = line.split_whitespace();
+
+    let timestamp = fields
+        .next()
+        .and_then(|field| field.strip_prefix('('))
+        .and_then(|field| field.strip_suffix(')'))
+        .ok_or_else(|| "missing timestamp".to_string())?
+        .parse::<f64>()
+        .map_err(|e| format!("invalid timestamp: {}", e))?;
+    let channel = fields
+        .next()
+        .ok_or_else(|| "missing interface".to_string())?;
+    let frame = fields.next().ok_or_else(|| "missing frame".to_string())?;
+
+    let (id, payload) = frame
+        .split_once('#')
+        .ok_or_else(|| format!("missing '#' in frame: {}", frame))?;
+    let raw_id = u32::from_str_radix(id, 16).map_err(|e| format!("invalid ID {}: {}", id, e))?;
+
+    let (id, mut kind) = if id.len() > 3 || raw_id > STANDARD_ID_MAX {
+        if raw_id & CAN_ERR_FLAG != 0 {
+            (CanId::Extended(raw_id & EXTENDED_ID_MAX), FrameKind::Error)
+        } else {
+            (CanId::Extended(raw_id & EXTENDED_ID_MAX), FrameKind::Data)
+        }
+    } else {
+        (CanId::Standard(raw_id as u16), FrameKind::Data)
+    };
+
+    let data = if let Some(payload) = payload.strip_prefix('#') {
+        let flags = payload
+            .get(..1)
+            .and_then(|flags| u8::from_str_radix(flags, 16).ok())
+            .ok_or_else(|| format!("missing FD flags: {}", frame))?;
+        kind = FrameKind::Fd {
+            brs: flags & FD_FLAG_BRS != 0,
+            esi: flags & FD_FLAG_ESI != 0,
+        };
+        parse_hex(&payload[1..])?
+    } else if let Some(len) = payload
+        .strip_prefix('R')
+        .or_else(|| payload.strip_prefix('r'))
+    {
+        let len = match len {
+            "" => 0,
+            len => len
+                .parse::<u8>()
+                .map_err(|e| format!("invalid remote length {}: {}", len, e))?,
+        };
+        kind = FrameKind::Remote(len);
+        Vec::new()
+    } else {
+        // drop the raw DLC of classic frames with more than 8 bytes (`_X` suffix)
+        parse_hex(payload.split('_').next().unwrap_or(""))?
+    };
+
+    Ok(TraceFrame {
+        timestamp,
+        channel: Channel::Name(channel.to_string()),
+        id,
+        kind,
+        data,
+        ..Default::default()
+    })
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex: String = hex.chars().filter(|c| *c != '.').collect();
+
+    // digits are sliced by byte offset below
+    if !hex.is_ascii() {
+        return Err(format!("invalid data {}", hex));
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits: {}", hex));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|e| format!("invalid data {}: {}", hex, e))
+        })
+        .collect()
+}
+
+/// Formats a frame as candump log line, without line break.  Numbered channels are written as
+/// `can0` for channel 1, `can1` for channel 2 and so on.
+///
+/// # Example
+/// ```rust
+/// use fastcan::id::CanId;
+/// use fastcan::trace::{candump, Channel, TraceFrame};
+///
+/// let frame = TraceFrame::new(1436509052.249713, Channel::Index(1), CanId::Standard(0x123), vec![0xDE, 0xAD]);
+///
+/// assert_eq!(candump::format_line(&frame), "(1436509052.249713) can0 123#DEAD");
+/// ```
+pub fn format_line(frame: &TraceFrame) -> String {
+    let channel = match frame.channel {
+        Channel::Name(ref name) => name.clone(),
+        Channel::Index(index) => format!("can{}", index.saturating_sub(1)),
+    };
+    let id = match frame.kind {
+        FrameKind::Error => format!("{:08X}", frame.id.raw() | CAN_ERR_FLAG),
+        _ => frame.id.to_string(),
+    };
+    let payload = match frame.kind {
+        FrameKind::Data | FrameKind::Error => format_hex(&frame.data),
+        FrameKind::Fd { brs, esi } => {
+            let flags = if brs { FD_FLAG_BRS } else { 0 } | if esi { FD_FLAG_ESI } else { 0 };
+            format!("#{:X}{}", flags, format_hex(&frame.data))
+        }
+        FrameKind::Remote(0) => "R".to_string(),
+        FrameKind::Remote(len) => format!("R{}", len),
+    };
+
+    format!("({:017.6}) {} {}#{}", frame.timestamp, channel, id, payload)
+}
+
+fn format_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Reads frames from a candump log, one `io::Result<TraceFrame>` per non-empty line
+pub struct CandumpReader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> CandumpReader<R> {
+    /// Creates a reader from any buffered source
+    pub fn new(reader: R) -> Self {
+        CandumpReader {
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+}
+
+impl CandumpReader<BufReader<File>> {
+    /// Opens a candump log file
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).map(|f| Self::new(BufReader::new(f)))
+    }
+}
+
+impl<R: BufRead> Iterator for CandumpReader<R> {
+    type Item = io::Result<TraceFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(parse_line(&line).map_err(|e| invalid_data(self.line, e)));
+        }
+    }
+}
+
+/// Writes frames as candump log
+pub struct CandumpWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> CandumpWriter<W> {
+    /// Creates a writer to any destination
+    pub fn new(writer: W) -> Self {
+        CandumpWriter { writer }
+    }
+
+    /// Writes a single frame
+    pub fn write_frame(&mut self, frame: &TraceFrame) -> io::Result<()> {
+        writeln!(self.writer, "{}", format_line(frame))
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_frame() {
+        let frame = parse_line("(1436509052.249713) can0 0CF00400#1122334455667788").unwrap();
+
+        assert_eq!(frame.id, CanId::Extended(0x0CF00400));
+        assert_eq!(frame.kind, FrameKind::Data);
+        assert_eq!(frame.data.len(), 8);
+        assert!((frame.timestamp - 1436509052.249713).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fd_frame() {
+        let frame = parse_line("(1436509052.249713) can1 123##3DEADBEEF").unwrap();
+
+        assert_eq!(
+            frame.kind,
+            FrameKind::Fd {
+                brs: true,
+                esi: true
+            }
+        );
+        assert_eq!(frame.data, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+    }
+
+    #[test]
+    fn test_remote_and_error_frames() {
+        let remote = parse_line("(1436509052.249713) can0 123#R4").unwrap();
+        assert_eq!(remote.kind, FrameKind::Remote(4));
+        assert!(remote.data.is_empty());
+
+        let error = parse_line("(1436509052.249713) can0 20000080#0000000000000000").unwrap();
+        assert_eq!(error.kind, FrameKind::Error);
+        assert_eq!(error.id, CanId::Extended(0x80));
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert!(parse_line("1436509052.249713 can0 123#DEADBEEF").is_err());
+        assert!(parse_line("(1436509052.249713) can0 123DEADBEEF").is_err());
+        assert!(parse_line("(1436509052.249713) can0 123#DEADBEE").is_err());
+        assert!(parse_line("(1436509052.249713) can0 XYZ#00").is_err());
+        assert!(parse_line("(1.0) can0 123#0é0").is_err());
+        assert!(parse_line("(1.0) can0 123##10é0").is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let lines = [
+            "(1436509052.249713) can0 123#DEADBEEF",
+            "(1436509052.249714) can0 0CF00400#1122334455667788",
+            "(1436509052.249715) vcan1 123##1DEADBEEF",
+            "(1436509052.249716) can0 123#R",
+            "(1436509052.249717) can0 7FF#R8",
+            "(1436509052.249718) can0 20000080#0000000000000000",
+            "(0000000001.500000) can0 001#",
+        ];
+
+        let mut writer = CandumpWriter::new(Vec::new());
+        for frame in CandumpReader::new(lines.join("\n").as_bytes()) {
+            writer.write_frame(&frame.unwrap()).unwrap();
+        }
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(written.lines().collect::<Vec<_>>(), lines);
+    }
+
+    #[test]
+    fn test_reader_reports_line() {
+        let log = "(1.0) can0 123#00\n\n(2.0) can0 123#0\n";
+        let frames: Vec<_> = CandumpReader::new(log.as_bytes()).collect();
+
+        assert!(frames[0].is_ok());
+        assert!(frames[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .starts_with("line 3"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Reading and writing CAN trace files
+//!
+//! All trace formats produce `TraceFrame`s, which can be decoded with
+//! `DbcLibrary::decode_trace` regardless of the format they were read from.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fastcan::dbc::DbcLibrary;
+//! use fastcan::trace::candump::CandumpReader;
+//!
+//! let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+//! let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+//!
+//! for decoded in lib.decode_trace(reader) {
+//!     let decoded = decoded.unwrap();
+//!     println!("{} {:?}", decoded.timestamp, decoded.signals);
+//! }
+//! ```
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::dbc::DbcLibrary;
+use crate::id::CanId;
+use crate::mapper::DecodedFrame;
+
+pub mod asc;
+pub mod blf;
+pub mod candump;
+pub mod trc;
+
+/// Bus a frame was recorded on
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Channel {
+    /// Numbered channel as used by Vector and PEAK tools, starting at 1
+    Index(u16),
+    /// Named interface as used by SocketCAN, e.g. `can0`
+    Name(String),
+}
+
+impl Channel {
+    /// Returns the channel number, `None` for named interfaces
+    pub fn index(&self) -> Option<u16> {
+        match *self {
+            Channel::Index(index) => Some(index),
+            Channel::Name(_) => None,
+        }
+    }
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Channel::Index(1)
+    }
+}
+
+impl From<u16> for Channel {
+    fn from(index: u16) -> Self {
+        Channel::Index(index)
+    }
+}
+
+impl From<&str> for Channel {
+    /// Parses numbers as channel numbers and anything else as interface names
+    fn from(channel: &str) -> Self {
+        match channel.parse() {
+            Ok(index) => Channel::Index(index),
+            Err(_) => Channel::Name(channel.to_string()),
+        }
+    }
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Channel::Index(index) => write!(f, "{}", index),
+            Channel::Name(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Type of a recorded frame
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum FrameKind {
+    /// Classic CAN data frame
+    #[default]
+    Data,
+    /// CAN FD data frame
+    Fd {
+        /// Bit rate switch
+        brs: bool,
+        /// Error state indicator
+        esi: bool,
+    },
+    /// Remote transmission request with the requested length
+    Remote(u8),
+    /// Error frame, the ID holds the error class
+    Error,
+}
+
+/// Direction of a recorded frame
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum Direction {
+    /// Frame was received
+    #[default]
+    Rx,
+    /// Frame was transmitted by the recording node
+    Tx,
+}
+
+/// Timestamped CAN frame from a trace file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TraceFrame {
+    /// Time the frame was recorded, in seconds.  Absolute (since the UNIX epoch) or relative to
+    /// the start of the trace, depending on the format.
+    pub timestamp: f64,
+    /// Bus the frame was recorded on
+    pub channel: Channel,
+    /// Arbitration ID
+    pub id: CanId,
+    /// Frame type
+    pub kind: FrameKind,
+    /// Whether the frame was received or transmitted
+    pub direction: Direction,
+    /// Payload
+    pub data: Vec<u8>,
+}
+
+impl TraceFrame {
+    /// Returns a received classic data frame
+    pub fn new(timestamp: f64, channel: Channel, id: CanId, data: Vec<u8>) -> Self {
+        TraceFrame {
+            timestamp,
+            channel,
+            id,
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true for classic and FD data frames
+    pub fn is_data(&self) -> bool {
+        match self.kind {
+            FrameKind::Data | FrameKind::Fd { .. } => true,
+            FrameKind::Remote(_) | FrameKind::Error => false,
+        }
+    }
+
+    /// Returns true for CAN FD frames
+    pub fn is_fd(&self) -> bool {
+        matches!(self.kind, FrameKind::Fd { .. })
+    }
+}
+
+/// Iterator returned by `DbcLibrary::decode_trace`
+pub struct DecodeTrace<'a, I> {
+    lib: &'a DbcLibrary,
+    frames: I,
+}
+
+impl<'a, I> Iterator for DecodeTrace<'a, I>
+where
+    I: Iterator<Item = io::Result<TraceFrame>>,
+{
+    type Item = io::Result<DecodedFrame<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = match self.frames.next()? {
+                Ok(frame) => frame,
+                Err(e) => return Some(Err(e)),
+            };
+            if !frame.is_data() {
+                continue;
+            }
+            if let Some(decoded) = self.lib.decode(frame.timestamp, frame.id, &frame.data) {
+                return Some(Ok(decoded));
+            }
+        }
+    }
+}
+
+impl DbcLibrary {
+    /// Decodes a stream of trace frames into timestamped signal values.  Remote and error frames
+    /// as well as IDs not contained in the library are skipped, read errors are passed on.
+    pub fn decode_trace<I>(&self, frames: I) -> DecodeTrace<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = io::Result<TraceFrame>>,
+    {
+        DecodeTrace {
+            lib: self,
+            frames: frames.into_iter(),
+        }
+    }
+}
+
+/// Converts a data length code into the payload length of CAN FD frames
+pub fn dlc_to_len(dlc: u8) -> usize {
+    match dlc {
+        0..=8 => usize::from(dlc),
+        9 => 12,
+        10 => 16,
+        11 => 20,
+        12 => 24,
+        13 => 32,
+        14 => 48,
+        _ => 64,
+    }
+}
+
+/// Converts a payload length into the smallest data length code of CAN FD frames that holds it
+pub fn len_to_dlc(len: usize) -> u8 {
+    match len {
+        0..=8 => len as u8,
+        9..=12 => 9,
+        13..=16 => 10,
+        17..=20 => 11,
+        21..=24 => 12,
+        25..=32 => 13,
+        33..=48 => 14,
+        _ => 15,
+    }
+}
+
+/// Returns an `io::Error` for malformed trace content
+pub(crate) fn invalid_data<E>(line: usize, error: E) -> io::Error
+where
+    E: Display,
+{
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, error),
+    )
+}
@@ -0,0 +1,683 @@
+//! PEAK PCAN trace files (`.trc`) as written by PCAN-View, versions 1.0 to 2.1
+//!
+//! ```text
+//! ;$FILEVERSION=2.1
+//! ;$STARTTIME=42209.4075997106
+//! ;$COLUMNS=N,O,T,B,I,d,R,L,D
+//! ;
+//!       1      1059.900 DT 1     0300 Rx -  8    00 00 00 00 04 00 00 00
+//!       2      1283.231 FB 1 18EFC001 Tx -  9    00 11 22 33 44 55 66 77 88 99 AA BB
+//!       3      1298.987 RR 2     0400 Rx -  2
+//! ```
+//!
+//! The versions differ in their columns: 1.x files have a fixed layout which gained a bus
+//! column in 1.2 and a reserved column in 1.3, 2.x files list their columns in the header.
+//! Timestamps are the start time from the header (taken as UTC) plus the offset of the frame, or
+//! just the offset in seconds for files without start time.
+
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::SplitWhitespace;
+
+use super::{dlc_to_len, invalid_data, len_to_dlc, Channel, Direction, FrameKind, TraceFrame};
+use crate::id::{CanId, EXTENDED_ID_MAX, STANDARD_ID_MAX};
+
+/// Days between the OLE automation date epoch (1899-12-30) used for `$STARTTIME` and the UNIX
+/// epoch
+const OLE_UNIX_EPOCH_DAYS: f64 = 25569.0;
+
+/// File format version
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default)]
+pub enum TrcVersion {
+    /// Message number, offset, ID, length and data
+    V1_0,
+    /// Adds the message type
+    V1_1,
+    /// Adds the bus
+    V1_2,
+    /// Adds a reserved column
+    V1_3,
+    /// Column layout in the header, CAN FD support
+    V2_0,
+    /// Adds bus and reserved columns to the default layout
+    #[default]
+    V2_1,
+}
+
+impl TrcVersion {
+    fn parse(version: &str) -> Result<Self, String> {
+        match version.trim() {
+            "1.0" => Ok(TrcVersion::V1_0),
+            "1.1" => Ok(TrcVersion::V1_1),
+            "1.2" => Ok(TrcVersion::V1_2),
+            "1.3" => Ok(TrcVersion::V1_3),
+            "2.0" => Ok(TrcVersion::V2_0),
+            "2.1" => Ok(TrcVersion::V2_1),
+            version => Err(format!("unsupported file version: {}", version)),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            TrcVersion::V1_0 => "1.0",
+            TrcVersion::V1_1 => "1.1",
+            TrcVersion::V1_2 => "1.2",
+            TrcVersion::V1_3 => "1.3",
+            TrcVersion::V2_0 => "2.0",
+            TrcVersion::V2_1 => "2.1",
+        }
+    }
+
+    /// Columns of the version, as listed in `$COLUMNS` of 2.x files
+    fn columns(self) -> &'static str {
+        match self {
+            TrcVersion::V1_0 => "NOIlD",
+            TrcVersion::V1_1 => "NOTIlD",
+            TrcVersion::V1_2 => "NOBTIlD",
+            TrcVersion::V1_3 => "NOBTIRlD",
+            TrcVersion::V2_0 => "NOTIdlD",
+            TrcVersion::V2_1 => "NOTBIdRLD",
+        }
+    }
+}
+
+/// Parses a message line with the given columns, returns the time offset in milliseconds and
+/// the frame, or `None` for status and other events
+fn parse_line(line: &str, columns: &[char]) -> Result<Option<(f64, TraceFrame)>, String> {
+    let mut fields = line.split_whitespace().peekable();
+    let mut frame = TraceFrame::default();
+    let mut offset = 0.0;
+    let mut len = 0;
+
+    for column in columns {
+        if *column == 'D' {
+            break;
+        }
+        // error frames have no ID in 2.x files
+        if *column == 'I' && frame.kind == FrameKind::Error {
+            if let Some(id) = fields.peek() {
+                if u32::from_str_radix(id, 16).is_ok() {
+                    fields.next();
+                }
+            }
+            continue;
+        }
+
+        let field = fields
+            .next()
+            .ok_or_else(|| format!("missing column {}", column))?;
+        match column {
+            'O' => {
+                offset = field
+                    .parse::<f64>()
+                    .map_err(|e| format!("invalid time offset {}: {}", field, e))?
+            }
+            'T' => match field {
+                "DT" => frame.kind = FrameKind::Data,
+                "FD" | "FB" | "FE" | "BI" => {
+                    frame.kind = FrameKind::Fd {
+                        brs: field == "FB" || field == "BI",
+                        esi: field == "FE" || field == "BI",
+                    }
+                }
+                "RR" => frame.kind = FrameKind::Remote(0),
+                "ER" | "Error" => frame.kind = FrameKind::Error,
+                "Rx" => frame.direction = Direction::Rx,
+                "Tx" => frame.direction = Direction::Tx,
+                // status changes, error counters and events
+                "ST" | "EC" | "EV" | "Warng" => return Ok(None),
+                field => return Err(format!("unknown message type: {}", field)),
+            },
+            'B' => {
+                frame.channel = Channel::Index(
+                    field
+                        .parse::<u16>()
+                        .map_err(|e| format!("invalid bus {}: {}", field, e))?,
+                )
+            }
+            'I' => frame.id = parse_id(field)?,
+            'd' => {
+                frame.direction = match field {
+                    "Rx" => Direction::Rx,
+                    "Tx" => Direction::Tx,
+                    field => return Err(format!("invalid direction: {}", field)),
+                }
+            }
+            'l' => {
+                len = field
+                    .parse::<usize>()
+                    .map_err(|e| format!("invalid data length {}: {}", field, e))?
+            }
+            'L' => {
+                len = field
+                    .parse::<u8>()
+                    .map(dlc_to_len)
+                    .map_err(|e| format!("invalid DLC {}: {}", field, e))?
+            }
+            // message number and reserved columns
+            _ => {}
+        }
+    }
+
+    match frame.kind {
+        FrameKind::Error => {}
+        FrameKind::Remote(_) => frame.kind = FrameKind::Remote(len_to_dlc(len)),
+        // 1.x files mark remote frames in the data column
+        _ if fields.peek() == Some(&"RTR") => frame.kind = FrameKind::Remote(len_to_dlc(len)),
+        _ => frame.data = parse_data(&mut fields, len)?,
+    }
+
+    Ok(Some((offset, frame)))
+}
+
+/// Parses an ID, 4 hex digits for standard and 8 for extended IDs
+fn parse_id(id: &str) -> Result<CanId, String> {
+    let raw = u32::from_str_radix(id, 16).map_err(|e| format!("invalid ID {}: {}", id, e))?;
+
+    if id.len() > 4 || raw > STANDARD_ID_MAX {
+        Ok(CanId::Extended(raw & EXTENDED_ID_MAX))
+    } else {
+        Ok(CanId::Standard(raw as u16))
+    }
+}
+
+fn parse_data(fields: &mut Peekable<SplitWhitespace>, len: usize) -> Result<Vec<u8>, String> {
+    (0..len)
+        .map(|_| {
+            let byte = fields
+                .next()
+                .ok_or_else(|| format!("expected {} data bytes", len))?;
+            u8::from_str_radix(byte, 16).map_err(|e| format!("invalid byte {}: {}", byte, e))
+        })
+        .collect()
+}
+
+/// Reads frames from a PCAN trace, one `io::Result<TraceFrame>` per message
+pub struct TrcReader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    version: TrcVersion,
+    start: Option<f64>,
+    columns: Vec<char>,
+    /// First message line, read along with the header
+    pending: Option<String>,
+}
+
+impl<R: BufRead> TrcReader<R> {
+    /// Creates a reader from any buffered source, reading the header
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut trc = TrcReader {
+            lines: reader.lines(),
+            line: 0,
+            version: TrcVersion::V1_0,
+            start: None,
+            columns: Vec::new(),
+            pending: None,
+        };
+        let mut columns = None;
+
+        for line in trc.lines.by_ref() {
+            let line = line?;
+            trc.line += 1;
+            let line_number = trc.line;
+
+            let header = match line.trim().strip_prefix(';') {
+                Some(header) => header,
+                None if line.trim().is_empty() => continue,
+                None => {
+                    trc.pending = Some(line);
+                    break;
+                }
+            };
+
+            if let Some((key, value)) = header.strip_prefix('$').and_then(|h| h.split_once('=')) {
+                match key {
+                    "FILEVERSION" => {
+                        trc.version =
+                            TrcVersion::parse(value).map_err(|e| invalid_data(line_number, e))?
+                    }
+                    "STARTTIME" => {
+                        let days = value
+                            .trim()
+                            .parse::<f64>()
+                            .map_err(|e| invalid_data(line_number, e))?;
+                        trc.start = Some((days - OLE_UNIX_EPOCH_DAYS) * 86400.0);
+                    }
+                    "COLUMNS" => {
+                        columns = Some(
+                            value
+                                .split(',')
+                                .filter_map(|column| column.trim().chars().next())
+                                .collect(),
+                        )
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        trc.columns = columns.unwrap_or_else(|| trc.version.columns().chars().collect());
+        Ok(trc)
+    }
+
+    /// Returns the file format version
+    pub fn version(&self) -> TrcVersion {
+        self.version
+    }
+
+    /// Returns the start time from the header in seconds since the UNIX epoch
+    pub fn start_time(&self) -> Option<f64> {
+        self.start
+    }
+}
+
+impl TrcReader<BufReader<File>> {
+    /// Opens a PCAN trace file
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).and_then(|f| Self::new(BufReader::new(f)))
+    }
+}
+
+impl<R: BufRead> Iterator for TrcReader<R> {
+    type Item = io::Result<TraceFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => {
+                    let line = match self.lines.next()? {
+                        Ok(line) => line,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    self.line += 1;
+                    line
+                }
+            };
+
+            if line.trim().is_empty() || line.trim_start().starts_with(';') {
+                continue;
+            }
+
+            match parse_line(&line, &self.columns) {
+                Ok(Some((offset, frame))) => {
+                    return Some(Ok(TraceFrame {
+                        timestamp: self.start.unwrap_or_default() + offset / 1000.0,
+                        ..frame
+                    }))
+                }
+                Ok(None) => continue,
+                Err(e) => return Some(Err(invalid_data(self.line, e))),
+            }
+        }
+    }
+}
+
+/// Writes frames as PCAN trace.  FD and error frames are only supported by 2.x files, writing
+/// them to 1.x files fails with `io::ErrorKind::InvalidInput`.
+pub struct TrcWriter<W> {
+    writer: W,
+    version: TrcVersion,
+    start: f64,
+    count: u64,
+}
+
+impl<W: Write> TrcWriter<W> {
+    /// Creates a writer of the given version for frames with UNIX timestamps, writing the
+    /// header.  Frames are written with their offset to `start`.
+    pub fn new(mut writer: W, version: TrcVersion, start: f64) -> io::Result<Self> {
+        writeln!(writer, ";$FILEVERSION={}", version.as_str())?;
+        writeln!(
+            writer,
+            ";$STARTTIME={:.10}",
+            start / 86400.0 + OLE_UNIX_EPOCH_DAYS
+        )?;
+        if version >= TrcVersion::V2_0 {
+            let columns: Vec<String> = version.columns().chars().map(String::from).collect();
+            writeln!(writer, ";$COLUMNS={}", columns.join(","))?;
+        }
+        writeln!(writer, ";")?;
+        writeln!(writer, ";   Generated by fastcan")?;
+        writeln!(writer, ";")?;
+
+        Ok(TrcWriter {
+            writer,
+            version,
+            start,
+            count: 0,
+        })
+    }
+
+    /// Writes a single frame
+    pub fn write_frame(&mut self, frame: &TraceFrame) -> io::Result<()> {
+        let version = self.version;
+        let unsupported = |kind| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} frames are not supported by version {}",
+                    kind,
+                    version.as_str()
+                ),
+            )
+        };
+
+        let offset = (frame.timestamp - self.start) * 1000.0;
+        let channel = frame.channel.index().unwrap_or(1);
+        let id = match frame.kind {
+            FrameKind::Error => String::new(),
+            _ => match frame.id {
+                CanId::Standard(id) => format!("{:04X}", id),
+                CanId::Extended(id) => format!("{:08X}", id),
+            },
+        };
+        let direction = match frame.direction {
+            Direction::Rx => "Rx",
+            Direction::Tx => "Tx",
+        };
+        let data = match frame.kind {
+            FrameKind::Remote(_) if self.version < TrcVersion::V2_0 => "RTR".to_string(),
+            _ => frame
+                .data
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        let len = match frame.kind {
+            FrameKind::Remote(len) => usize::from(len),
+            _ => frame.data.len(),
+        };
+
+        if self.version < TrcVersion::V2_0 {
+            match frame.kind {
+                FrameKind::Fd { .. } => return Err(unsupported("FD")),
+                FrameKind::Error => return Err(unsupported("error")),
+                _ => {}
+            }
+        }
+
+        self.count += 1;
+        let number = self.count;
+
+        if self.version < TrcVersion::V2_0 {
+            return match self.version {
+                TrcVersion::V1_0 => writeln!(
+                    self.writer,
+                    "{:>6}) {:>11.1} {:>8}  {}  {}",
+                    number, offset, id, len, data
+                ),
+                TrcVersion::V1_1 => writeln!(
+                    self.writer,
+                    "{:>6}) {:>11.1}  {:<3} {:>8}  {}  {}",
+                    number, offset, direction, id, len, data
+                ),
+                TrcVersion::V1_2 => writeln!(
+                    self.writer,
+                    "{:>6}) {:>11.1} {} {:<3} {:>8}  {}  {}",
+                    number, offset, channel, direction, id, len, data
+                ),
+                _ => writeln!(
+                    self.writer,
+                    "{:>6}) {:>11.1} {} {:<3} {:>8} - {}  {}",
+                    number, offset, channel, direction, id, len, data
+                ),
+            };
+        }
+
+        let kind = match frame.kind {
+            FrameKind::Data => "DT",
+            FrameKind::Fd {
+                brs: false,
+                esi: false,
+            } => "FD",
+            FrameKind::Fd {
+                brs: true,
+                esi: false,
+            } => "FB",
+            FrameKind::Fd {
+                brs: false,
+                esi: true,
+            } => "FE",
+            FrameKind::Fd {
+                brs: true,
+                esi: true,
+            } => "BI",
+            FrameKind::Remote(_) => "RR",
+            FrameKind::Error => "ER",
+        };
+
+        if self.version == TrcVersion::V2_0 {
+            writeln!(
+                self.writer,
+                "{:>7} {:>13.3} {} {:>8} {} {:<2} {}",
+                number, offset, kind, id, direction, len, data
+            )
+        } else {
+            writeln!(
+                self.writer,
+                "{:>7} {:>13.3} {} {} {:>8} {} - {:<2} {}",
+                number,
+                offset,
+                kind,
+                channel,
+                id,
+                direction,
+                len_to_dlc(len),
+                data
+            )
+        }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(trc: &str) -> Vec<TraceFrame> {
+        TrcReader::new(trc.as_bytes())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_version_1_1() {
+        let frames = read(
+            ";$FILEVERSION=1.1\n\
+             ;$STARTTIME=25569.5\n\
+             ;   Start time: 01.01.1970 12:00:00.000.0\n\
+             \x20    1)      3268.7  Rx         0100  8  00 01 02 03 04 05 06 07\n\
+             \x20    2)      3270.5  Tx     1FFFFFFF  4  FF FF FF FF\n\
+             \x20    3)      3271.0  Rx         0300  4  RTR\n\
+             \x20    4)      3272.0  Warng  FFFFFFFF  4  00 00 00 08  BUSHEAVY\n",
+        );
+
+        assert_eq!(frames.len(), 3);
+        assert!((frames[0].timestamp - (43200.0 + 3.2687)).abs() < 1e-6);
+        assert_eq!(frames[0].id, CanId::Standard(0x100));
+        assert_eq!(frames[0].data, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(frames[1].id, CanId::Extended(0x1FFFFFFF));
+        assert_eq!(frames[1].direction, Direction::Tx);
+        assert_eq!(frames[2].kind, FrameKind::Remote(4));
+    }
+
+    #[test]
+    fn test_version_1_3() {
+        let frames = read(
+            ";$FILEVERSION=1.3\n\
+             \x20    1)      1841.2 2  Rx         0100 -  8    00 00 00 00 00 00 00 00\n",
+        );
+
+        assert_eq!(frames[0].channel, Channel::Index(2));
+        assert!((frames[0].timestamp - 1.8412).abs() < 1e-9);
+        assert_eq!(frames[0].data.len(), 8);
+    }
+
+    #[test]
+    fn test_version_2_0() {
+        let frames = read(
+            ";$FILEVERSION=2.0\n\
+             ;$COLUMNS=N,O,T,I,d,l,D\n\
+             ;\n\
+             \x20     1      1059.900 DT     0300 Rx 7  00 00 00 00 04 00 00\n\
+             \x20     2      1283.231 FB 18EFC001 Tx 12 00 11 22 33 44 55 66 77 88 99 AA BB\n\
+             \x20     3      2002.234 ER          Rx 4  00 02 00 00\n\
+             \x20     4      2003.000 ST          Rx 00 00 00 08\n",
+        );
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].data.len(), 7);
+        assert_eq!(frames[1].id, CanId::Extended(0x18EFC001));
+        assert_eq!(
+            frames[1].kind,
+            FrameKind::Fd {
+                brs: true,
+                esi: false
+            }
+        );
+        assert_eq!(frames[1].data.len(), 12);
+        assert_eq!(frames[2].kind, FrameKind::Error);
+    }
+
+    #[test]
+    fn test_version_2_1() {
+        let frames = read(
+            ";$FILEVERSION=2.1\n\
+             ;$COLUMNS=N,O,T,B,I,d,R,L,D\n\
+             \x20     1      1059.900 DT 1     0300 Rx -  8    00 00 00 00 04 00 00 00\n\
+             \x20     2      1283.231 BI 2 18EFC001 Tx -  9    00 11 22 33 44 55 66 77 88 99 AA BB\n\
+             \x20     3      1298.987 RR 1     0400 Rx -  2\n",
+        );
+
+        assert_eq!(frames[1].channel, Channel::Index(2));
+        assert_eq!(frames[1].data.len(), 12);
+        assert_eq!(frames[2].kind, FrameKind::Remote(2));
+    }
+
+    #[test]
+    fn test_reader_reports_line() {
+        let err = TrcReader::new(
+            ";$FILEVERSION=2.1\n      1      1.000 DT 1 0300 Rx - 8 00\n".as_bytes(),
+        )
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+        assert!(err.to_string().starts_with("line 2"));
+
+        assert!(TrcReader::new(";$FILEVERSION=3.0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let classic = vec![
+            TraceFrame::new(
+                1436509052.25,
+                Channel::Index(1),
+                CanId::Extended(0x0CF00400),
+                vec![0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88],
+            ),
+            TraceFrame {
+                kind: FrameKind::Remote(4),
+                direction: Direction::Tx,
+                ..TraceFrame::new(
+                    1436509052.5,
+                    Channel::Index(2),
+                    CanId::Standard(0x7FF),
+                    vec![],
+                )
+            },
+        ];
+        let mut fd = classic.clone();
+        fd.push(TraceFrame {
+            kind: FrameKind::Fd {
+                brs: true,
+                esi: false,
+            },
+            ..TraceFrame::new(
+                1436509052.75,
+                Channel::Index(2),
+                CanId::Standard(0x123),
+                (0..16).collect(),
+            )
+        });
+        fd.push(TraceFrame {
+            kind: FrameKind::Error,
+            ..TraceFrame::new(1436509053.0, Channel::Index(2), CanId::default(), vec![])
+        });
+
+        for (version, frames) in [
+            (TrcVersion::V1_1, &classic),
+            (TrcVersion::V1_2, &classic),
+            (TrcVersion::V1_3, &classic),
+            (TrcVersion::V2_0, &fd),
+            (TrcVersion::V2_1, &fd),
+        ] {
+            let mut writer = TrcWriter::new(Vec::new(), version, 1436509052.0).unwrap();
+            for frame in frames {
+                writer.write_frame(frame).unwrap();
+            }
+            let written = writer.into_inner();
+
+            let reader = TrcReader::new(&written[..]).unwrap();
+            assert_eq!(reader.version(), version);
+            let read: Vec<_> = reader.collect::<io::Result<_>>().unwrap();
+
+            assert_eq!(read.len(), frames.len());
+            for (read, frame) in read.iter().zip(frames.iter()) {
+                assert!((read.timestamp - frame.timestamp).abs() < 1e-3);
+                assert_eq!(read.id, frame.id);
+                assert_eq!(read.kind, frame.kind);
+                assert_eq!(read.data, frame.data);
+                if version != TrcVersion::V1_1 && version != TrcVersion::V2_0 {
+                    assert_eq!(read.channel, frame.channel);
+                }
+                assert_eq!(read.direction, frame.direction);
+            }
+        }
+
+        let mut writer = TrcWriter::new(Vec::new(), TrcVersion::V1_1, 0.0).unwrap();
+        assert_eq!(
+            writer.write_frame(&fd[2]).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_unsupported_frames_keep_numbering() {
+        let frame = TraceFrame::new(0.0, Channel::Index(1), CanId::Standard(0x100), vec![1]);
+        let fd = TraceFrame {
+            kind: FrameKind::Fd {
+                brs: false,
+                esi: false,
+            },
+            ..frame.clone()
+        };
+
+        let mut writer = TrcWriter::new(Vec::new(), TrcVersion::V1_1, 0.0).unwrap();
+        writer.write_frame(&frame).unwrap();
+        assert!(writer.write_frame(&fd).is_err());
+        writer.write_frame(&frame).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+
+        let numbers: Vec<_> = written
+            .lines()
+            .filter(|line| !line.starts_with(';'))
+            .map(|line| line.split(')').next().unwrap().trim())
+            .collect();
+        assert_eq!(numbers, vec!["1", "2"]);
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use fastcan::codegen::generate;
+use fastcan::dbc::DbcLibrary;
+use fastcan::mapper::{DecodeFrame, EncodeMessage};
+
+mod frames {
+    include!("data/codegen_frames.rs");
+}
+
+mod layouts {
+    include!("data/layouts_frames.rs");
+}
+
+use frames::{Diagnostics, Eec1, Eec1EngineSpeed, Eec1EngineTorqueMode, Transmission};
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/codegen.dbc").unwrap();
+    assert_eq!(
+        generate(&lib),
+        include_str!("data/codegen_frames.rs"),
+        "regenerate tests/data/codegen_frames.rs from tests/data/codegen.dbc"
+    );
+
+    let lib = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+    assert_eq!(
+        generate(&lib),
+        include_str!("data/layouts_frames.rs"),
+        "regenerate tests/data/layouts_frames.rs from tests/data/layouts.dbc"
+    );
+}
+
+#[test]
+fn decode_and_encode() {
+    assert_eq!(Eec1::ID, 0x0CF00400);
+    assert_eq!((Eec1::EXTENDED, Transmission::EXTENDED), (true, false));
+    assert_eq!(Eec1::LEN, 8);
+
+    let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    let eec1 = Eec1::decode(&payload).unwrap();
+    assert_eq!(
+        eec1.engine_torque_mode,
+        Eec1EngineTorqueMode::AcceleratorPedal
+    );
+    assert_eq!(eec1.engine_speed, Eec1EngineSpeed::Value(2728.5));
+    assert_eq!(eec1.engine_temp, 62.0);
+
+    let encoded = eec1.encode();
+    assert_eq!(Eec1::decode(&encoded).unwrap(), eec1);
+    assert_eq!(encoded, [0x01, 0x00, 0x00, 0x44, 0x55, 0x66, 0x00, 0x00]);
+
+    let eec1 = Eec1::decode(&[0x0F, 0, 0, 0xFF, 0xFF, 0, 0, 0]).unwrap();
+    assert_eq!(eec1.engine_torque_mode, Eec1EngineTorqueMode::NotAvailable);
+    assert_eq!(eec1.engine_speed, Eec1EngineSpeed::NotAvailable);
+
+    assert!(Eec1::decode(&payload[..7]).is_none());
+}
+
+#[test]
+fn decode_like_library() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/codegen.dbc").unwrap();
+    let frame = lib.get_frame(0x123).unwrap();
+    let payload = [0xFE, 0xFF, 0x38, 0x00, 0x2A, 0x00, 0x00, 0x00];
+
+    let transmission = Transmission::decode(&payload).unwrap();
+    assert_eq!(transmission.gear, -2);
+    assert!(transmission.clutch_engaged);
+    assert_eq!(transmission.shift_count, 42);
+    let torque = frame.get_signal("Output_Torque").unwrap();
+    assert_eq!(torque.decode_raw(&payload), Some(0xFF38));
+    assert_eq!(transmission.output_torque, -100.0);
+
+    assert_eq!(
+        Transmission::decode(&transmission.encode()),
+        Some(transmission)
+    );
+}
+
+#[test]
+fn multiplexed_signals() {
+    let voltage = Diagnostics::decode(&[0x00, 0xB0, 0x04, 0x00]).unwrap();
+    assert_eq!(voltage.page, 0);
+    assert_eq!(voltage.battery_voltage, Some(12.0));
+    assert_eq!(voltage.oil_pressure, None);
+    assert_eq!(voltage.error_code, None);
+
+    let oil = Diagnostics {
+        page: 1,
+        battery_voltage: None,
+        oil_pressure: Some(400.0),
+        error_code: Some(0x1234),
+    };
+    assert_eq!(oil.encode(), [0x01, 0x64, 0x34, 0x12]);
+    assert_eq!(Diagnostics::decode(&oil.encode()), Some(oil));
+}
+
+/// Signals the library decodes from a payload, and the payload it encodes from them
+fn library(id: u32, payload: &[u8]) -> (HashMap<String, f32>, Vec<u8>) {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/layouts.dbc").unwrap();
+    let frame = lib.get_frame(id).unwrap();
+    let signals = frame.decode_frame(payload).unwrap();
+    let values = signals
+        .iter()
+        .map(|(name, value)| (name.clone(), f64::from(*value)))
+        .collect();
+    let mut encoded: Vec<u8> = frame.encode_message(&values).unwrap();
+    encoded.truncate(payload.len());
+    (signals, encoded)
+}
+
+#[test]
+fn short_frame_like_library() {
+    let payload = [0xAB, 0xCD];
+    let short = layouts::Short::decode(&payload).unwrap();
+    let (signals, encoded) = library(layouts::Short::ID, &payload);
+
+    assert_eq!(short.level, 1374.0);
+    assert_eq!(short.level, f64::from(signals["Level"]));
+    assert_eq!(f32::from(short.flags), signals["Flags"]);
+    assert_eq!(short.encode(), payload);
+    assert_eq!(&short.encode()[..], &encoded[..]);
+    assert!(layouts::Short::decode(&payload[..1]).is_none());
+}
+
+#[test]
+fn fd_frame_like_library() {
+    let mut payload = [0u8; 64];
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte = (i * 37 + 11) as u8;
+    }
+    let long = layouts::Long::decode(&payload).unwrap();
+    let (signals, encoded) = library(layouts::Long::ID, &payload);
+
+    assert_eq!(f32::from(long.checksum), signals["Checksum"]);
+    assert_eq!(f32::from(long.head), signals["Head"]);
+    assert_eq!(f32::from(long.tail), signals["Tail"]);
+    assert!((long.temperature as f32 - signals["Temperature"]).abs() < 1e-3);
+    assert_eq!(&long.encode()[..], &encoded[..]);
+    assert_eq!(layouts::Long::decode(&long.encode()), Some(long));
+}
+
+#[test]
+fn multiplexed_frame_like_library() {
+    for payload in [
+        [0x00, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE],
+        [0x01, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE],
+    ] {
+        let muxed = layouts::Muxed::decode(&payload).unwrap();
+        let (signals, encoded) = library(layouts::Muxed::ID, &payload);
+
+        assert_eq!(muxed.speed.is_some(), signals.contains_key("Speed"));
+        if let Some(speed) = muxed.speed {
+            assert!((speed as f32 - signals["Speed"]).abs() < 1e-3);
+        }
+        assert_eq!(
+            muxed.pressure.map(|p| p as f32),
+            signals.get("Pressure").copied()
+        );
+        assert_eq!(muxed.valve.map(f32::from), signals.get("Valve").copied());
+        assert_eq!(&muxed.encode()[..], &encoded[..]);
+    }
+}
@@ -0,0 +1,280 @@
+// Generated by fastcan::codegen, do not edit.
+
+/// `Transmission`, ID 0x123
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transmission {
+    /// `Gear`, -8 to 7
+    pub gear: i8,
+    /// `Clutch_Engaged`, 0 to 1
+    pub clutch_engaged: bool,
+    /// `ShiftCount`, 0 to 4294967296
+    pub shift_count: u32,
+    /// `Output_Torque`, -16384 to 16383.5 Nm
+    pub output_torque: f64,
+}
+
+#[allow(dead_code)]
+impl Transmission {
+    /// ID of the frame as seen on the bus
+    pub const ID: u32 = 0x123;
+    /// Whether the frame has an extended ID
+    pub const EXTENDED: bool = false;
+    /// Length of the frame in bytes
+    pub const LEN: usize = 8;
+
+    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let data = &data[..Self::LEN];
+        Some(Transmission {
+            gear: {
+                let raw = extract(data, 0, 4, true);
+                sign_extend(raw, 4) as i8
+            },
+            clutch_engaged: {
+                let raw = extract(data, 4, 1, true);
+                raw != 0
+            },
+            shift_count: {
+                let raw = extract(data, 32, 32, true);
+                raw as u32
+            },
+            output_torque: {
+                let raw = extract(data, 40, 16, false);
+                sign_extend(raw, 16) as f64 * 0.5
+            },
+        })
+    }
+
+    /// Encodes the frame
+    pub fn encode(&self) -> [u8; 8] {
+        let mut data = [0; 8];
+        insert(&mut data, 0, 4, true, self.gear as u64);
+        insert(&mut data, 4, 1, true, u64::from(self.clutch_engaged));
+        insert(&mut data, 32, 32, true, u64::from(self.shift_count));
+        insert(&mut data, 40, 16, false, (self.output_torque / 0.5).round() as i64 as u64);
+        data
+    }
+}
+
+/// `Diagnostics`, ID 0x400
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diagnostics {
+    /// `Page`, 0 to 255
+    pub page: u8,
+    /// `Battery_Voltage`, 0 to 655.35 V
+    pub battery_voltage: Option<f64>,
+    /// `Oil_Pressure`, 0 to 1000 kPa
+    pub oil_pressure: Option<f64>,
+    /// `Error_Code`, 0 to 65535
+    pub error_code: Option<u16>,
+}
+
+#[allow(dead_code)]
+impl Diagnostics {
+    /// ID of the frame as seen on the bus
+    pub const ID: u32 = 0x400;
+    /// Whether the frame has an extended ID
+    pub const EXTENDED: bool = false;
+    /// Length of the frame in bytes
+    pub const LEN: usize = 4;
+
+    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let data = &data[..Self::LEN];
+        let mux = extract(data, 0, 8, true);
+        Some(Diagnostics {
+            page: {
+                let raw = extract(data, 0, 8, true);
+                raw as u8
+            },
+            battery_voltage: if mux == 0 {
+                let raw = extract(data, 8, 16, true);
+                Some(raw as f64 * 0.01)
+            } else {
+                None
+            },
+            oil_pressure: if mux == 1 {
+                let raw = extract(data, 8, 8, true);
+                Some(raw as f64 * 4.0)
+            } else {
+                None
+            },
+            error_code: if mux == 1 {
+                let raw = extract(data, 16, 16, true);
+                Some(raw as u16)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Encodes the frame
+    pub fn encode(&self) -> [u8; 4] {
+        let mut data = [0; 4];
+        insert(&mut data, 0, 8, true, u64::from(self.page));
+        if let Some(value) = self.battery_voltage {
+            insert(&mut data, 8, 16, true, (value / 0.01).round() as i64 as u64);
+        }
+        if let Some(value) = self.oil_pressure {
+            insert(&mut data, 8, 8, true, (value / 4.0).round() as i64 as u64);
+        }
+        if let Some(value) = self.error_code {
+            insert(&mut data, 16, 16, true, u64::from(value));
+        }
+        data
+    }
+}
+
+/// Values of `Engine_Torque_Mode`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Eec1EngineTorqueMode {
+    /// Raw value 0
+    LowIdleGovernor,
+    /// Raw value 1
+    AcceleratorPedal,
+    /// Raw value 15
+    NotAvailable,
+    /// Value without label
+    Value(u8),
+}
+
+/// Values of `Engine_Speed`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Eec1EngineSpeed {
+    /// Raw value 65279
+    Error,
+    /// Raw value 65535
+    NotAvailable,
+    /// Value without label
+    Value(f64),
+}
+
+/// `EEC1`, ID 0x0CF00400
+///
+/// Electronic Engine Controller 1
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eec1 {
+    /// `Engine_Torque_Mode`, 0 to 15
+    pub engine_torque_mode: Eec1EngineTorqueMode,
+    /// `Engine_Speed`, 0 to 8031.88 rpm
+    ///
+    /// Actual engine speed
+    pub engine_speed: Eec1EngineSpeed,
+    /// `Engine_Temp`, -40 to 210 degC
+    pub engine_temp: f64,
+}
+
+#[allow(dead_code)]
+impl Eec1 {
+    /// ID of the frame as seen on the bus
+    pub const ID: u32 = 0x0CF00400;
+    /// Whether the frame has an extended ID
+    pub const EXTENDED: bool = true;
+    /// Length of the frame in bytes
+    pub const LEN: usize = 8;
+
+    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let data = &data[..Self::LEN];
+        Some(Eec1 {
+            engine_torque_mode: {
+                let raw = extract(data, 0, 4, true);
+                match raw as i64 {
+                    0 => Eec1EngineTorqueMode::LowIdleGovernor,
+                    1 => Eec1EngineTorqueMode::AcceleratorPedal,
+                    15 => Eec1EngineTorqueMode::NotAvailable,
+                    _ => Eec1EngineTorqueMode::Value(raw as u8),
+                }
+            },
+            engine_speed: {
+                let raw = extract(data, 24, 16, true);
+                match raw as i64 {
+                    65279 => Eec1EngineSpeed::Error,
+                    65535 => Eec1EngineSpeed::NotAvailable,
+                    _ => Eec1EngineSpeed::Value(raw as f64 * 0.125),
+                }
+            },
+            engine_temp: {
+                let raw = extract(data, 40, 8, true);
+                raw as f64 - 40.0
+            },
+        })
+    }
+
+    /// Encodes the frame
+    pub fn encode(&self) -> [u8; 8] {
+        let mut data = [0; 8];
+        let raw = match self.engine_torque_mode {
+            Eec1EngineTorqueMode::LowIdleGovernor => 0,
+            Eec1EngineTorqueMode::AcceleratorPedal => 1,
+            Eec1EngineTorqueMode::NotAvailable => 15,
+            Eec1EngineTorqueMode::Value(value) => u64::from(value),
+        };
+        insert(&mut data, 0, 4, true, raw);
+        let raw = match self.engine_speed {
+            Eec1EngineSpeed::Error => 65279,
+            Eec1EngineSpeed::NotAvailable => 65535,
+            Eec1EngineSpeed::Value(value) => (value / 0.125).round() as i64 as u64,
+        };
+        insert(&mut data, 24, 16, true, raw);
+        insert(&mut data, 40, 8, true, (self.engine_temp + 40.0).round() as i64 as u64);
+        data
+    }
+}
+
+/// Reads `len` bits starting at `start`
+#[allow(dead_code)]
+fn extract(data: &[u8], start: usize, len: usize, little_endian: bool) -> u64 {
+    (0..len).fold(0, |value, i| {
+        let bit = start + i;
+        let byte = if little_endian {
+            Some(bit / 8)
+        } else {
+            (data.len().max(8) - 1).checked_sub(bit / 8)
+        };
+        match byte.and_then(|byte| data.get(byte)) {
+            Some(byte) => value | (u64::from((byte >> (bit % 8)) & 1) << i),
+            None => value,
+        }
+    })
+}
+
+/// Writes the lower `len` bits of `value` starting at `start`
+#[allow(dead_code)]
+fn insert(data: &mut [u8], start: usize, len: usize, little_endian: bool, value: u64) {
+    let last = data.len().max(8) - 1;
+    for i in 0..len {
+        let bit = start + i;
+        let byte = if little_endian {
+            Some(bit / 8)
+        } else {
+            last.checked_sub(bit / 8)
+        };
+        if let Some(byte) = byte.and_then(|byte| data.get_mut(byte)) {
+            if (value >> i) & 1 == 1 {
+                *byte |= 1 << (bit % 8);
+            }
+        }
+    }
+}
+
+/// Interprets the lower `len` bits of `raw` as two's complement number
+#[allow(dead_code)]
+fn sign_extend(raw: u64, len: usize) -> i64 {
+    let shift = 64 - len;
+    ((raw << shift) as i64) >> shift
+}
@@ -0,0 +1,246 @@
+// Generated by fastcan::codegen, do not edit.
+
+/// `Short`, ID 0x500
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Short {
+    /// `Flags`, 0 to 15
+    pub flags: u8,
+    /// `Level`, 0 to 2047.5 %
+    pub level: f64,
+}
+
+#[allow(dead_code)]
+impl Short {
+    /// ID of the frame as seen on the bus
+    pub const ID: u32 = 0x500;
+    /// Whether the frame has an extended ID
+    pub const EXTENDED: bool = false;
+    /// Length of the frame in bytes
+    pub const LEN: usize = 2;
+
+    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let data = &data[..Self::LEN];
+        Some(Short {
+            flags: {
+                let raw = extract(data, 8, 4, true);
+                raw as u8
+            },
+            level: {
+                let raw = extract(data, 52, 12, false);
+                raw as f64 * 0.5
+            },
+        })
+    }
+
+    /// Encodes the frame
+    pub fn encode(&self) -> [u8; 2] {
+        let mut data = [0; 2];
+        insert(&mut data, 8, 4, true, u64::from(self.flags));
+        insert(&mut data, 52, 12, false, (self.level / 0.5).round() as i64 as u64);
+        data
+    }
+}
+
+/// `Long`, ID 0x600
+///
+/// CAN FD frame with signals past the first 8 bytes
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Long {
+    /// `Checksum`, 0 to 255
+    pub checksum: u8,
+    /// `Head`, 0 to 65535
+    pub head: u16,
+    /// `Tail`, 0 to 65535
+    pub tail: u16,
+    /// `Temperature`, -91.2 to 11.1 degC
+    pub temperature: f64,
+}
+
+#[allow(dead_code)]
+impl Long {
+    /// ID of the frame as seen on the bus
+    pub const ID: u32 = 0x600;
+    /// Whether the frame has an extended ID
+    pub const EXTENDED: bool = false;
+    /// Length of the frame in bytes
+    pub const LEN: usize = 64;
+
+    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let data = &data[..Self::LEN];
+        Some(Long {
+            checksum: {
+                let raw = extract(data, 0, 8, false);
+                raw as u8
+            },
+            head: {
+                let raw = extract(data, 16, 16, true);
+                raw as u16
+            },
+            tail: {
+                let raw = extract(data, 480, 16, true);
+                raw as u16
+            },
+            temperature: {
+                let raw = extract(data, 500, 10, false);
+                sign_extend(raw, 10) as f64 * 0.1 - 40.0
+            },
+        })
+    }
+
+    /// Encodes the frame
+    pub fn encode(&self) -> [u8; 64] {
+        let mut data = [0; 64];
+        insert(&mut data, 0, 8, false, u64::from(self.checksum));
+        insert(&mut data, 16, 16, true, u64::from(self.head));
+        insert(&mut data, 480, 16, true, u64::from(self.tail));
+        insert(&mut data, 500, 10, false, ((self.temperature + 40.0) / 0.1).round() as i64 as u64);
+        data
+    }
+}
+
+/// Values of `Mode`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MuxedMode {
+    /// Raw value 0
+    Drive,
+    /// Raw value 1
+    Service,
+    /// Value without label
+    Value(u8),
+}
+
+/// `Muxed`, ID 0x700
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Muxed {
+    /// `Mode`, 0 to 15
+    pub mode: MuxedMode,
+    /// `Pressure`, 0 to 510 kPa
+    pub pressure: Option<f64>,
+    /// `Valve`, -2048 to 2047
+    pub valve: Option<i16>,
+    /// `Speed`, 0 to 655.35 km/h
+    pub speed: Option<f64>,
+}
+
+#[allow(dead_code)]
+impl Muxed {
+    /// ID of the frame as seen on the bus
+    pub const ID: u32 = 0x700;
+    /// Whether the frame has an extended ID
+    pub const EXTENDED: bool = false;
+    /// Length of the frame in bytes
+    pub const LEN: usize = 8;
+
+    /// Decodes the frame, returns `None` if the payload is shorter than `LEN`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let data = &data[..Self::LEN];
+        let mux = extract(data, 0, 4, true);
+        Some(Muxed {
+            mode: {
+                let raw = extract(data, 0, 4, true);
+                match raw as i64 {
+                    0 => MuxedMode::Drive,
+                    1 => MuxedMode::Service,
+                    _ => MuxedMode::Value(raw as u8),
+                }
+            },
+            pressure: if mux == 1 {
+                let raw = extract(data, 8, 8, true);
+                Some(raw as f64 * 2.0)
+            } else {
+                None
+            },
+            valve: if mux == 1 {
+                let raw = extract(data, 32, 12, false);
+                Some(sign_extend(raw, 12) as i16)
+            } else {
+                None
+            },
+            speed: if mux == 0 {
+                let raw = extract(data, 40, 16, false);
+                Some(raw as f64 * 0.01)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Encodes the frame
+    pub fn encode(&self) -> [u8; 8] {
+        let mut data = [0; 8];
+        let raw = match self.mode {
+            MuxedMode::Drive => 0,
+            MuxedMode::Service => 1,
+            MuxedMode::Value(value) => u64::from(value),
+        };
+        insert(&mut data, 0, 4, true, raw);
+        if let Some(value) = self.pressure {
+            insert(&mut data, 8, 8, true, (value / 2.0).round() as i64 as u64);
+        }
+        if let Some(value) = self.valve {
+            insert(&mut data, 32, 12, false, value as u64);
+        }
+        if let Some(value) = self.speed {
+            insert(&mut data, 40, 16, false, (value / 0.01).round() as i64 as u64);
+        }
+        data
+    }
+}
+
+/// Reads `len` bits starting at `start`
+#[allow(dead_code)]
+fn extract(data: &[u8], start: usize, len: usize, little_endian: bool) -> u64 {
+    (0..len).fold(0, |value, i| {
+        let bit = start + i;
+        let byte = if little_endian {
+            Some(bit / 8)
+        } else {
+            (data.len().max(8) - 1).checked_sub(bit / 8)
+        };
+        match byte.and_then(|byte| data.get(byte)) {
+            Some(byte) => value | (u64::from((byte >> (bit % 8)) & 1) << i),
+            None => value,
+        }
+    })
+}
+
+/// Writes the lower `len` bits of `value` starting at `start`
+#[allow(dead_code)]
+fn insert(data: &mut [u8], start: usize, len: usize, little_endian: bool, value: u64) {
+    let last = data.len().max(8) - 1;
+    for i in 0..len {
+        let bit = start + i;
+        let byte = if little_endian {
+            Some(bit / 8)
+        } else {
+            last.checked_sub(bit / 8)
+        };
+        if let Some(byte) = byte.and_then(|byte| data.get_mut(byte)) {
+            if (value >> i) & 1 == 1 {
+                *byte |= 1 << (bit % 8);
+            }
+        }
+    }
+}
+
+/// Interprets the lower `len` bits of `raw` as two's complement number
+#[allow(dead_code)]
+fn sign_extend(raw: u64, len: usize) -> i64 {
+    let shift = 64 - len;
+    ((raw << shift) as i64) >> shift
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "j1939")]
+extern crate fastcan;
+
+use fastcan::dbc::DbcLibrary;
+use fastcan::id::CanId;
+use fastcan::j1939::transport::TransportReassembler;
+
+#[test]
+fn decode_reassembled_dm1() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/j1939.dbc").unwrap();
+    let mut reassembler = TransportReassembler::new();
+
+    // DM1 from source address 0x17, which is not the one in the DBC
+    reassembler.push(
+        0.0,
+        CanId::Extended(0x1CECFF17),
+        &[0x20, 10, 0, 2, 0xFF, 0xCA, 0xFE, 0x00],
+    );
+    reassembler.push(
+        0.05,
+        CanId::Extended(0x1CEBFF17),
+        &[1, 0x00, 0x04, 0xBE, 0x00, 0x20, 0x00, 0x01],
+    );
+    let events = reassembler.push(
+        0.10,
+        CanId::Extended(0x1CEBFF17),
+        &[2, 0x00, 0x00, 0x05, 0xFF, 0xFF, 0xFF, 0xFF],
+    );
+
+    let message = events[0].as_ref().unwrap();
+    assert_eq!(message.source_address, 0x17);
+
+    let signals = message.decode(&lib).unwrap();
+
+    assert_eq!(signals["AmberWarningLampStatus"], 1.0);
+    assert_eq!(signals["DTC1_SPN"], 190.0);
+    assert_eq!(signals["DTC1_FMI"], 1.0);
+    assert_eq!(signals["DTC2_OccurrenceCount"], 5.0);
+    assert_eq!(
+        lib.get_signal_by_spn(624).unwrap().get_definition().bit_len,
+        2
+    );
+}
@@ -0,0 +1,16 @@
+#![cfg(feature = "macros")]
+
+mod frames {
+    fastcan::dbc!("tests/data/codegen.dbc");
+}
+
+use frames::{Eec1, Eec1EngineSpeed};
+
+#[test]
+fn reexported_macro() {
+    let payload = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    let eec1 = Eec1::decode(&payload).unwrap();
+
+    assert_eq!(eec1.engine_speed, Eec1EngineSpeed::Value(2728.5));
+    assert_eq!(Eec1::decode(&eec1.encode()), Some(eec1));
+}
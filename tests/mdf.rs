@@ -0,0 +1,48 @@
+extern crate fastcan;
+
+use fastcan::dbc::DbcLibrary;
+use fastcan::id::CanId;
+use fastcan::mdf::MdfReader;
+use fastcan::trace::{Channel, Direction, FrameKind};
+
+#[test]
+fn read_bus_logging() {
+    let reader = MdfReader::open("./tests/data/sample.mf4").unwrap();
+    let start = reader.start_time();
+    let frames: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+
+    // frames of both data groups in order of their timestamps
+    assert!((start - 1436509052.249).abs() < 1e-6);
+    assert_eq!(frames.len(), 5);
+    for (frame, offset) in frames.iter().zip(&[0.0, 0.01, 0.02, 0.03, 0.04]) {
+        assert!((frame.timestamp - start - offset).abs() < 1e-6);
+    }
+
+    assert_eq!(frames[0].id, CanId::Extended(0x0CF00400));
+    assert_eq!(frames[1].id, CanId::Standard(0x123));
+    assert_eq!(frames[1].direction, Direction::Tx);
+    assert_eq!(frames[1].data, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(frames[2].channel, Channel::Index(2));
+    assert_eq!(frames[2].id, CanId::Extended(0x0CF00400));
+    assert_eq!(
+        frames[4].kind,
+        FrameKind::Fd {
+            brs: true,
+            esi: false
+        }
+    );
+}
+
+#[test]
+fn decode_bus_logging() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    let reader = MdfReader::open("./tests/data/sample.mf4").unwrap();
+
+    let decoded: Vec<_> = lib.decode_trace(reader).collect::<Result<_, _>>().unwrap();
+
+    let speeds: Vec<_> = decoded
+        .iter()
+        .map(|decoded| decoded.signals["Engine_Speed"])
+        .collect();
+    assert_eq!(speeds, [2728.5, 2730.0, 2741.0]);
+}
@@ -0,0 +1,106 @@
+extern crate fastcan;
+
+use fastcan::dbc::DbcLibrary;
+use fastcan::mapper::{DecodeFrame, DecodeMessage};
+use fastcan::trace::asc::AscReader;
+use fastcan::trace::blf::BlfReader;
+use fastcan::trace::candump::CandumpReader;
+use fastcan::trace::trc::TrcReader;
+use fastcan::trace::{Channel, Direction, FrameKind};
+
+#[test]
+fn decode_candump_log() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    let reader = CandumpReader::open("./tests/data/sample.log").unwrap();
+
+    let decoded: Vec<_> = lib.decode_trace(reader).collect::<Result<_, _>>().unwrap();
+
+    // unknown IDs, including EEC1 from another source address, and the remote frame are skipped
+    assert_eq!(decoded.len(), 2);
+    assert!((decoded[0].timestamp - 1436509052.249713).abs() < 1e-6);
+    assert_eq!(decoded[0].frame.get_name(), "EEC1");
+    assert_eq!(decoded[0].signals["Engine_Speed"], 2728.5);
+    assert_eq!(decoded[1].signals["Engine_Speed"], 2741.0);
+}
+
+#[test]
+fn decode_asc_trace() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    let reader = AscReader::open("./tests/data/sample.asc").unwrap();
+
+    let decoded: Vec<_> = lib.decode_trace(reader).collect::<Result<_, _>>().unwrap();
+
+    // the FD frame is decoded like a classic one, remote and error frames are skipped
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded[0].frame.get_name(), "EEC1");
+    assert_eq!(decoded[0].signals["Engine_Speed"], 2728.5);
+    assert!((decoded[1].timestamp - 0.04).abs() < 1e-9);
+    assert_eq!(decoded[1].signals["Engine_Speed"], 2741.0);
+}
+
+#[test]
+fn decode_blf_log() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    let reader = BlfReader::open("./tests/data/sample.blf").unwrap();
+    let start = reader.start_time();
+
+    let decoded: Vec<_> = lib.decode_trace(reader).collect::<Result<_, _>>().unwrap();
+
+    // classic, FD and 64 byte FD message objects, spread over two compressed containers
+    assert!((start - 1436509052.249).abs() < 1e-6);
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded[0].frame.get_name(), "EEC1");
+    assert!((decoded[0].timestamp - start).abs() < 1e-6);
+    assert_eq!(decoded[0].signals["Engine_Speed"], 2728.5);
+    assert!((decoded[1].timestamp - start - 0.04).abs() < 1e-6);
+    assert_eq!(decoded[1].signals["Engine_Speed"], 2741.0);
+    assert_eq!(decoded[2].signals["Engine_Speed"], 2728.5);
+}
+
+#[test]
+fn read_blf_frames() {
+    let frames: Vec<_> = BlfReader::open("./tests/data/sample.blf")
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(frames.len(), 7);
+    assert_eq!(frames[1].direction, Direction::Tx);
+    assert_eq!(frames[3].kind, FrameKind::Remote(0));
+    assert_eq!(frames[4].kind, FrameKind::Error);
+    assert_eq!(frames[5].channel, Channel::Index(2));
+    assert_eq!(
+        frames[5].kind,
+        FrameKind::Fd {
+            brs: true,
+            esi: false
+        }
+    );
+    assert_eq!(frames[6].direction, Direction::Tx);
+}
+
+#[test]
+fn decode_trc_trace() {
+    let lib = DbcLibrary::from_dbc_file("./tests/data/sample.dbc").unwrap();
+    let frame = lib.get_frame(2364539904).unwrap();
+    let reader = TrcReader::open("./tests/data/sample.trc").unwrap();
+
+    let frames: Vec<_> = reader
+        .filter_map(|frame| frame.ok())
+        .filter(|trace| trace.is_data() && trace.id == frame.get_can_id())
+        .collect();
+
+    assert_eq!(frames.len(), 2);
+    assert!((frames[0].timestamp - 1436509052.249).abs() < 1e-3);
+    assert_eq!(
+        frame.decode_frame(&frames[0].data[..]).unwrap()["Engine_Speed"],
+        2728.5
+    );
+    assert_eq!(
+        frame
+            .get_signal("Engine_Speed")
+            .unwrap()
+            .decode_message(&frames[1].data[..]),
+        Some(2741.0)
+    );
+}